@@ -1,71 +1,23 @@
-use checkm8::search::Searcher;
-use checkm8::{SearchCommand, SearchControl, SearchInfo};
+use checkm8::SearchControl;
+use checkm8::bench;
+use checkm8::engine::Engine;
 use criterion::{Criterion, criterion_group, criterion_main};
-use crossbeam_channel::unbounded;
-use shakmaty::fen::Fen;
-use shakmaty::{CastlingMode, Chess};
+use shakmaty::Chess;
 use std::hint::black_box;
-use std::str::FromStr;
-use std::thread;
 use std::time::Duration;
 
 fn search_positions_to_depth(positions: &Vec<Chess>, depth: u8) {
-    let (cmd_tx, cmd_rx) = unbounded();
-    let (info_tx, info_rx) = unbounded();
-
-    thread::spawn(|| Searcher::new(cmd_rx, info_tx).run());
+    let mut engine = Engine::new();
 
     for position in positions {
-        // send start signal
-        cmd_tx
-            .send(SearchCommand::Start {
-                position: position.clone(),
-                control: SearchControl::ToDepth(depth),
-            })
-            .unwrap();
-
-        // Wait for best move output
-        loop {
-            match info_rx.recv() {
-                Ok(SearchInfo::BestMove(_)) => break,
-                _ => (),
-            }
-        }
+        engine.set_chess(position.clone());
+        // Positions without moves fail to search, which is part of the benchmark
+        let _ = engine.search(SearchControl::ToDepth(depth));
     }
 }
 
-fn parse_fen(fen_str: &str) -> Chess {
-    Fen::from_str(fen_str)
-        .unwrap()
-        .into_position(CastlingMode::Standard)
-        .unwrap()
-}
-
 fn perfomance_bench(c: &mut Criterion) {
-    let fens = [
-        "6Q1/p1p3P1/1k1p2N1/p1n1p2P/5r2/1b6/2n4K/b1q2b2 b - - 29 30",
-        "6QR/8/3p1kN1/1P5P/3N1r2/1b4P1/3r4/2K2b2 b - - 13 10",
-        "5b2/2pk2P1/3p2N1/pP2p2P/5r2/1b4P1/2K5/b1q5 w - - 17 16",
-        "3R3R/2p3P1/1k1p4/pP6/3N4/1b4P1/2nr4/b3K3 w - - 34 9",
-        "3k2QR/p5P1/3p2N1/pPn1p3/5r2/1b4P1/2n4P/b1K5 b - - 2 25",
-        "6QR/2p3P1/6k1/pPn1p2P/3N1r2/8/K1n5/b1q2b2 b - - 21 2",
-        "4k1Q1/p5P1/6N1/pPn1p3/8/1b4P1/3r3P/2K2b2 b - - 26 11",
-        "3R1b2/k1p3P1/6N1/pPn4P/5r2/6K1/2n4P/b1q5 w - - 13 23",
-        "5bQ1/k1p3P1/8/1P5P/3N1r2/1b4P1/K1nr3P/b1q2b2 w - - 22 6",
-        "4kbQR/p5P1/8/p7/3N4/Kb4P1/3r4/2q2b2 w - - 6 18",
-        "3R1bQ1/k5P1/3p2N1/pP2p2P/3N4/8/2n4P/1Kq5 w - - 11 1",
-        "3R1bQR/k1p5/3p4/pPn1p3/8/1b4P1/2K4P/b4b2 w - - 8 11",
-        "3R2QR/pk4P1/3p2N1/1Pn1p3/5r2/1b1K4/3r3P/2q5 w - - 6 30",
-        "4kb1R/2p3P1/3p4/p1n1p3/3N1r2/8/2K4P/b4b2 w - - 4 12",
-        "3R1b1k/p7/8/4p2P/3N1r2/1b2K1P1/2nr3P/5b2 w - - 12 22",
-        "3R2Q1/1kp5/3p2N1/pPn1p2P/3N4/6P1/8/b4K2 w - - 15 15",
-        "3R1bQ1/k5P1/3p2N1/p1n1p2P/8/1b6/1Knr3P/2q2b2 w - - 26 3",
-        "3R1bQR/2p5/3p1kN1/pPn1p3/5r2/6P1/3r2KP/b4b2 w - - 15 5",
-        "5b2/pkp3P1/3p4/7P/8/1b4K1/2nr3P/b1q2b2 w - - 39 11",
-        "3R2Q1/p1p1k3/8/p3p2P/5r2/6PK/8/b1q2b2 w - - 10 6",
-    ];
-
-    let positions: Vec<_> = fens.into_iter().map(parse_fen).collect();
+    let positions = bench::positions();
 
     c.bench_function("Depth 3 Search", |b| {
         b.iter(|| search_positions_to_depth(black_box(&positions), 3))
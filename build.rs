@@ -1,10 +1,25 @@
 use shakmaty::fen::Fen;
 use shakmaty::{CastlingMode, Chess};
+use std::env;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
+use std::path::Path;
 use std::str::FromStr;
 
+// Indexing shared with the library, which embeds the generated bitbase
+#[path = "src/scoring/endgame/kpk/index.rs"]
+mod index;
+
+#[path = "src/scoring/endgame/kpk/generate.rs"]
+mod kpk;
+
 fn main() {
+    filter_fens();
+    generate_kpk();
+}
+
+/// Removes FENs which can not be parsed into legal positions.
+fn filter_fens() {
     // Path to the FENs file
     let fens_path = "assets/fens.txt";
 
@@ -33,3 +48,17 @@ fn main() {
     // Write filtered lines back to the file (overwriting it)
     fs::write(fens_path, filtered.join("\n") + "\n").expect("Failed to write filtered fens.txt");
 }
+
+/// Generates the KPK bitbase into the output directory.
+fn generate_kpk() {
+    for source in [
+        "src/scoring/endgame/kpk/index.rs",
+        "src/scoring/endgame/kpk/generate.rs",
+    ] {
+        println!("cargo:rerun-if-changed={}", source);
+    }
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    let path = Path::new(&out_dir).join("kpk.bin");
+    fs::write(path, kpk::generate()).expect("Failed to write KPK bitbase");
+}
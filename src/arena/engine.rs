@@ -0,0 +1,343 @@
+//! Client side of the UCI protocol, driving an engine in a child process.
+
+use crate::pgn::EngineScore;
+use crossbeam_channel::{Receiver, RecvTimeoutError, unbounded};
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Move, fen::Fen, uci::UciMove};
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+// Time an engine gets to answer `uci` and `isready`
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Time an engine gets to exit after `quit` before it is killed
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How to start and configure an engine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EngineConfig {
+    pub name: String,
+    pub command: String,
+    pub args: Vec<String>,
+    // UCI options set after the handshake
+    pub options: Vec<(String, String)>,
+}
+
+impl EngineConfig {
+    /// Configuration running a command without arguments or options, named after the file.
+    pub fn new(command: &str) -> Self {
+        let name = Path::new(command)
+            .file_stem()
+            .map_or(command.into(), |stem| stem.to_string_lossy().into_owned());
+
+        EngineConfig {
+            name,
+            command: command.into(),
+            args: Vec::new(),
+            options: Vec::new(),
+        }
+    }
+}
+
+impl FromStr for EngineConfig {
+    type Err = String;
+
+    /// Parses a specification such as `cmd=./checkm8 name=nnue option.UseNNUE=true`, where
+    /// `arg=` adds a command line argument.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config: Option<EngineConfig> = None;
+        let mut name = None;
+        let mut args = Vec::new();
+        let mut options = Vec::new();
+
+        for field in s.split_whitespace() {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, found '{}'", field))?;
+
+            match key {
+                "cmd" => config = Some(EngineConfig::new(value)),
+                "name" => name = Some(value.to_string()),
+                "arg" => args.push(value.to_string()),
+                _ => match key.strip_prefix("option.") {
+                    Some(option) => options.push((option.to_string(), value.to_string())),
+                    None => return Err(format!("unknown engine setting '{}'", key)),
+                },
+            }
+        }
+
+        let mut config = config.ok_or("missing cmd= in engine specification")?;
+        config.name = name.unwrap_or(config.name);
+        config.args = args;
+        config.options = options;
+        Ok(config)
+    }
+}
+
+/// Limits of a single search, sent with `go`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchLimits {
+    Clock {
+        white: Duration,
+        black: Duration,
+        white_increment: Duration,
+        black_increment: Duration,
+    },
+    MoveTime(Duration),
+    Nodes(u64),
+    Depth(u8),
+}
+
+impl fmt::Display for SearchLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchLimits::Clock {
+                white,
+                black,
+                white_increment,
+                black_increment,
+            } => write!(
+                f,
+                "wtime {} btime {} winc {} binc {}",
+                white.as_millis(),
+                black.as_millis(),
+                white_increment.as_millis(),
+                black_increment.as_millis()
+            ),
+            SearchLimits::MoveTime(time) => write!(f, "movetime {}", time.as_millis()),
+            SearchLimits::Nodes(nodes) => write!(f, "nodes {}", nodes),
+            SearchLimits::Depth(depth) => write!(f, "depth {}", depth),
+        }
+    }
+}
+
+/// Answer of an engine to `go`, with the last score it reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: UciMove,
+    // Score from the perspective of the side to move
+    pub score: Option<EngineScore>,
+    pub depth: u8,
+}
+
+/// A running UCI engine.
+pub struct Engine {
+    pub name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    /// Starts an engine, completes the UCI handshake and sets its options.
+    pub fn start(config: &EngineConfig) -> io::Result<Self> {
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // Output is read on a separate thread, so that waiting for it can time out
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (line_tx, line_rx) = unbounded();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if line_tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Engine {
+            name: config.name.clone(),
+            child,
+            stdin,
+            lines: line_rx,
+        };
+
+        engine.send("uci")?;
+        engine.wait_for("uciok", Instant::now() + STARTUP_TIMEOUT)?;
+        for (name, value) in &config.options {
+            engine.send(&format!("setoption name {} value {}", name, value))?;
+        }
+        engine.ready()?;
+
+        Ok(engine)
+    }
+
+    /// Prepares the engine for a new game.
+    pub fn new_game(&mut self) -> io::Result<()> {
+        self.send("ucinewgame")?;
+        self.ready()
+    }
+
+    /// Waits until the engine has processed all previous commands.
+    pub fn ready(&mut self) -> io::Result<()> {
+        self.send("isready")?;
+        self.wait_for("readyok", Instant::now() + STARTUP_TIMEOUT)
+    }
+
+    /// Searches the position reached by playing the moves from the initial position.
+    ///
+    /// Fails with [`io::ErrorKind::TimedOut`] if no move arrives before the timeout, in which
+    /// case the engine is told to stop.
+    pub fn go(
+        &mut self,
+        initial: &Chess,
+        moves: &[Move],
+        limits: &SearchLimits,
+        timeout: Duration,
+    ) -> io::Result<SearchResult> {
+        let deadline = Instant::now() + timeout;
+
+        let mut command = if *initial == Chess::default() {
+            "position startpos".to_string()
+        } else {
+            format!(
+                "position fen {}",
+                Fen::from_position(initial, EnPassantMode::Legal)
+            )
+        };
+        if !moves.is_empty() {
+            command.push_str(" moves");
+            for &mv in moves {
+                command.push_str(&format!(
+                    " {}",
+                    UciMove::from_move(mv, CastlingMode::Standard)
+                ));
+            }
+        }
+        self.send(&command)?;
+        self.send(&format!("go {}", limits))?;
+
+        let mut score = None;
+        let mut depth = 0;
+        loop {
+            let line = match self.read_line(deadline) {
+                Ok(line) => line,
+                Err(e) => {
+                    if e.kind() == io::ErrorKind::TimedOut {
+                        self.stop();
+                    }
+                    return Err(e);
+                }
+            };
+
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("info") => parse_info(tokens, &mut score, &mut depth),
+                Some("bestmove") => {
+                    let best_move = tokens
+                        .next()
+                        .and_then(|mv| mv.parse().ok())
+                        .ok_or_else(|| invalid_data(format!("invalid bestmove '{}'", line)))?;
+                    return Ok(SearchResult {
+                        best_move,
+                        score,
+                        depth,
+                    });
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Stops a search and discards its move, so that it is not taken as the answer to the
+    /// next search.
+    fn stop(&mut self) {
+        if self.send("stop").is_err() {
+            return;
+        }
+
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while let Ok(line) = self.read_line(deadline) {
+            if line.starts_with("bestmove") {
+                break;
+            }
+        }
+    }
+
+    /// Whether the engine process is still running.
+    pub fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()
+    }
+
+    fn read_line(&self, deadline: Instant) -> io::Result<String> {
+        self.lines.recv_deadline(deadline).map_err(|e| match e {
+            RecvTimeoutError::Timeout => {
+                io::Error::new(io::ErrorKind::TimedOut, "engine did not answer in time")
+            }
+            RecvTimeoutError::Disconnected => {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "engine exited")
+            }
+        })
+    }
+
+    fn wait_for(&self, token: &str, deadline: Instant) -> io::Result<()> {
+        while self.read_line(deadline)?.trim() != token {}
+        Ok(())
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.send("quit").ok();
+
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            if !self.is_running() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// Updates the score and depth from the tokens of an `info` line.
+fn parse_info<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+    score: &mut Option<EngineScore>,
+    depth: &mut u8,
+) {
+    while let Some(token) = tokens.next() {
+        match token {
+            "depth" => {
+                if let Some(value) = tokens.next().and_then(|value| value.parse().ok()) {
+                    *depth = value;
+                }
+            }
+            "score" => {
+                let kind = tokens.next();
+                let value = tokens.next().and_then(|value| value.parse().ok());
+                *score = match (kind, value) {
+                    (Some("cp"), Some(cp)) => Some(EngineScore::Centipawns(cp)),
+                    (Some("mate"), Some(moves)) => Some(EngineScore::Mate(moves)),
+                    _ => *score,
+                };
+            }
+            // The rest of the line is free text
+            "string" => return,
+            _ => (),
+        }
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,321 @@
+//! Games between two engines.
+
+use super::engine::{Engine, SearchLimits};
+use crate::pgn::{EngineComment, EngineScore, Game};
+use chrono::Local;
+use shakmaty::{
+    ByColor, Chess, Color, EnPassantMode, KnownOutcome, Outcome, Position, zobrist::Zobrist64,
+};
+use std::{fmt, io, str::FromStr, time::Duration, time::Instant};
+
+// Time an engine gets for a search without a clock before it is considered hung
+const HANG_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Score standing in for mates when adjudicating (in centipawns)
+const MATE_CP: i32 = 100_000;
+
+/// How much time or effort engines get for their moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    // Time for the whole game plus an increment per move
+    Clock { base: Duration, increment: Duration },
+    MoveTime(Duration),
+    Nodes(u64),
+    Depth(u8),
+}
+
+impl FromStr for TimeControl {
+    type Err = ();
+
+    /// Parses `BASE+INC` in seconds, `movetime=MS`, `nodes=N` or `depth=N`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let seconds = |value: &str| {
+            value
+                .parse::<f64>()
+                .ok()
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .ok_or(())
+        };
+
+        match s.split_once('=') {
+            Some(("movetime", ms)) => Ok(TimeControl::MoveTime(Duration::from_millis(
+                ms.parse().map_err(|_| ())?,
+            ))),
+            Some(("nodes", nodes)) => Ok(TimeControl::Nodes(nodes.parse().map_err(|_| ())?)),
+            Some(("depth", depth)) => Ok(TimeControl::Depth(depth.parse().map_err(|_| ())?)),
+            Some(_) => Err(()),
+            None => {
+                let (base, increment) = s.split_once('+').unwrap_or((s, "0"));
+                Ok(TimeControl::Clock {
+                    base: seconds(base)?,
+                    increment: seconds(increment)?,
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeControl::Clock { base, increment } => {
+                write!(f, "{}+{}", base.as_secs_f64(), increment.as_secs_f64())
+            }
+            TimeControl::MoveTime(time) => write!(f, "movetime={}", time.as_millis()),
+            TimeControl::Nodes(nodes) => write!(f, "nodes={}", nodes),
+            TimeControl::Depth(depth) => write!(f, "depth={}", depth),
+        }
+    }
+}
+
+/// Rules for ending games early based on the scores reported by both engines.
+///
+/// A rule is disabled when its number of plies is zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Adjudication {
+    // Draw once both engines report scores within the draw score for this many plies
+    pub draw_plies: u32,
+    pub draw_score: i32,
+    // First move number at which games can be adjudicated as draws
+    pub draw_move_number: u32,
+    // Win once both engines agree on a score beyond the resign score for this many plies
+    pub resign_plies: u32,
+    pub resign_score: i32,
+    // Games still running after this many plies are drawn
+    pub max_plies: u32,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Adjudication {
+            draw_plies: 8,
+            draw_score: 10,
+            draw_move_number: 40,
+            resign_plies: 6,
+            resign_score: 1000,
+            max_plies: 400,
+        }
+    }
+}
+
+/// Settings shared by all games of a match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub time_control: TimeControl,
+    // Time engines may exceed their clock by before losing on time
+    pub time_margin: Duration,
+    pub adjudication: Adjudication,
+}
+
+/// A finished game.
+pub struct GameResult {
+    pub game: Game,
+    pub outcome: KnownOutcome,
+    // Human readable reason the game ended, such as "white mates"
+    pub reason: String,
+}
+
+/// Plays a game from an opening position.
+pub fn play(
+    white: &mut Engine,
+    black: &mut Engine,
+    opening: &Chess,
+    settings: &Settings,
+) -> GameResult {
+    let mut game = Game::from_position(opening.clone());
+    game.set_tag("Event", "checkm8 match");
+    game.set_tag("Date", &Local::now().format("%Y.%m.%d").to_string());
+    game.set_tag("White", &white.name);
+    game.set_tag("Black", &black.name);
+    game.set_tag("TimeControl", &settings.time_control.to_string());
+
+    let (outcome, termination, reason) = {
+        let mut engines = ByColor { white, black };
+        run(&mut engines, opening, settings, &mut game)
+    };
+
+    game.set_result(Outcome::Known(outcome));
+    game.set_tag("Termination", termination);
+
+    GameResult {
+        game,
+        outcome,
+        reason,
+    }
+}
+
+/// Plays moves until the game ends, returning the outcome with its PGN termination and reason.
+fn run(
+    engines: &mut ByColor<&mut Engine>,
+    opening: &Chess,
+    settings: &Settings,
+    game: &mut Game,
+) -> (KnownOutcome, &'static str, String) {
+    let loss = |color: Color| KnownOutcome::Decisive { winner: !color };
+
+    for color in Color::ALL {
+        if let Err(e) = engines[color].new_game() {
+            return (
+                loss(color),
+                "abandoned",
+                format!("{} failed to start: {}", color, e),
+            );
+        }
+    }
+
+    let (base, increment) = match settings.time_control {
+        TimeControl::Clock { base, increment } => (base, increment),
+        _ => (Duration::ZERO, Duration::ZERO),
+    };
+    let mut clocks = ByColor::new_with(|_| base);
+
+    let mut position = opening.clone();
+    let mut moves = Vec::new();
+    let mut hashes = vec![hash(&position)];
+    // Scores reported for every ply, from white's perspective
+    let mut scores = Vec::new();
+
+    loop {
+        if let Outcome::Known(outcome) = position.outcome() {
+            let reason = match outcome {
+                KnownOutcome::Decisive { winner } => format!("{} mates", winner),
+                KnownOutcome::Draw if position.is_stalemate() => "stalemate".into(),
+                KnownOutcome::Draw => "insufficient material".into(),
+            };
+            return (outcome, "normal", reason);
+        }
+        if position.halfmoves() >= 100 {
+            return (KnownOutcome::Draw, "normal", "fifty move rule".into());
+        }
+        let current = hashes.last().unwrap();
+        if hashes.iter().filter(|&hash| hash == current).count() >= 3 {
+            return (KnownOutcome::Draw, "normal", "threefold repetition".into());
+        }
+        if let Some((outcome, reason)) = adjudicate(&scores, &position, &settings.adjudication) {
+            return (outcome, "adjudication", reason);
+        }
+
+        let turn = position.turn();
+        let (limits, timeout) = match settings.time_control {
+            TimeControl::Clock { .. } => (
+                SearchLimits::Clock {
+                    white: clocks.white,
+                    black: clocks.black,
+                    white_increment: increment,
+                    black_increment: increment,
+                },
+                clocks[turn] + settings.time_margin,
+            ),
+            TimeControl::MoveTime(time) => (SearchLimits::MoveTime(time), HANG_TIMEOUT),
+            TimeControl::Nodes(nodes) => (SearchLimits::Nodes(nodes), HANG_TIMEOUT),
+            TimeControl::Depth(depth) => (SearchLimits::Depth(depth), HANG_TIMEOUT),
+        };
+
+        let start = Instant::now();
+        let result = engines[turn].go(opening, &moves, &limits, timeout);
+        let elapsed = start.elapsed();
+
+        let result = match result {
+            Ok(result) => result,
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                return (
+                    loss(turn),
+                    "time forfeit",
+                    format!("{} loses on time", turn),
+                );
+            }
+            Err(e) => {
+                return (
+                    loss(turn),
+                    "abandoned",
+                    format!("{} disconnects: {}", turn, e),
+                );
+            }
+        };
+
+        if let TimeControl::Clock { .. } = settings.time_control {
+            if elapsed > clocks[turn] + settings.time_margin {
+                return (
+                    loss(turn),
+                    "time forfeit",
+                    format!("{} loses on time", turn),
+                );
+            }
+            clocks[turn] = clocks[turn].saturating_sub(elapsed) + increment;
+        }
+
+        let Ok(mv) = result.best_move.to_move(&position) else {
+            let reason = format!("{} makes an illegal move: {}", turn, result.best_move);
+            return (loss(turn), "rules infraction", reason);
+        };
+
+        let comment = result.score.map(|score| EngineComment {
+            score,
+            depth: result.depth,
+            time: elapsed,
+        });
+        game.push(mv, comment.map(|comment| comment.to_string()));
+
+        scores.push(result.score.map(|score| {
+            let cp = match score {
+                EngineScore::Centipawns(cp) => cp,
+                EngineScore::Mate(moves) if moves > 0 => MATE_CP,
+                EngineScore::Mate(_) => -MATE_CP,
+            };
+            turn.fold_wb(cp, -cp)
+        }));
+        position.play_unchecked(mv);
+        moves.push(mv);
+        hashes.push(hash(&position));
+    }
+}
+
+/// Ends the game early if the recent scores of both engines are decisive or drawish.
+///
+/// Scores are those reported for every ply played so far, from white's perspective.
+pub fn adjudicate(
+    scores: &[Option<i32>],
+    position: &Chess,
+    adjudication: &Adjudication,
+) -> Option<(KnownOutcome, String)> {
+    if adjudication.max_plies > 0 && scores.len() >= adjudication.max_plies as usize {
+        return Some((KnownOutcome::Draw, "maximum game length".into()));
+    }
+
+    // Scores of the last plies, all of which must be known
+    let recent = |plies: u32| -> Option<Vec<i32>> {
+        let plies = plies as usize;
+        if plies == 0 || scores.len() < plies {
+            return None;
+        }
+        scores[scores.len() - plies..].iter().copied().collect()
+    };
+
+    if let Some(recent) = recent(adjudication.resign_plies) {
+        for winner in Color::ALL {
+            let sign = winner.fold_wb(1, -1);
+            if recent
+                .iter()
+                .all(|&score| score * sign >= adjudication.resign_score)
+            {
+                let reason = format!("{} resigns", !winner);
+                return Some((KnownOutcome::Decisive { winner }, reason));
+            }
+        }
+    }
+
+    if position.fullmoves().get() >= adjudication.draw_move_number
+        && let Some(recent) = recent(adjudication.draw_plies)
+        && recent
+            .iter()
+            .all(|score| score.abs() <= adjudication.draw_score)
+    {
+        return Some((KnownOutcome::Draw, "draw by adjudication".into()));
+    }
+
+    None
+}
+
+fn hash(position: &Chess) -> u64 {
+    position.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0
+}
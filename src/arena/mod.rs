@@ -0,0 +1,52 @@
+//! Engine matches, played between UCI engines running as child processes.
+//!
+//! [`engine`] drives a single engine over UCI, [`game`] plays a game between two engines
+//! under a time control with adjudication, and [`sprt`] decides when a match has shown an
+//! Elo difference. [`tournament`] schedules games between several engines and rates them.
+
+use crate::pgn::read_games;
+use shakmaty::{CastlingMode, Chess, fen::Fen};
+use std::{fs, io, path::Path};
+
+pub mod engine;
+pub mod game;
+pub mod sprt;
+pub mod tournament;
+
+/// Loads opening positions from a PGN file, using the end of each main line, or from a file
+/// with one FEN or EPD position per line.
+pub fn load_openings(path: impl AsRef<Path>) -> io::Result<Vec<Chess>> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    if path.extension().is_some_and(|extension| extension == "pgn") {
+        return read_games(&text)
+            .into_iter()
+            .map(|game| game.map(|game| game.position()))
+            .collect::<Result<_, _>>()
+            .map_err(|e| invalid(e.to_string()));
+    }
+
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_position(line).ok_or_else(|| invalid(format!("invalid FEN '{}'", line))))
+        .collect()
+}
+
+/// Parses a FEN, or the position of an EPD line, ignoring its operations.
+fn parse_position(line: &str) -> Option<Chess> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+
+    // EPD lines have no move counters, but operations may follow the position
+    let counters = fields.len() >= 6 && fields[4..6].iter().all(|f| f.parse::<u32>().is_ok());
+    let length = if counters { 6 } else { 4 };
+
+    fields
+        .get(..length)?
+        .join(" ")
+        .parse::<Fen>()
+        .ok()?
+        .into_position(CastlingMode::Standard)
+        .ok()
+}
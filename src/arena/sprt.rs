@@ -0,0 +1,136 @@
+//! Match statistics and the sequential probability ratio test.
+//!
+//! Scores are converted to Elo with the logistic model. The log-likelihood ratio uses the
+//! generalised SPRT approximation on game results, comparing the hypotheses that the Elo
+//! difference is `elo0` (H0) or `elo1` (H1). A match can stop as soon as the ratio leaves the
+//! bounds given by the error rates `alpha` and `beta`.
+
+use std::fmt;
+
+// Normal quantile of the 95% confidence interval
+const Z_95: f64 = 1.959964;
+
+/// Game results from the perspective of the first engine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Results {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Results {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Fraction of points scored.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// Variance of the points scored per game.
+    fn variance(&self) -> f64 {
+        let score = self.score();
+        let games = self.games() as f64;
+        (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / games
+    }
+
+    /// Elo difference with the half width of its 95% confidence interval, if both can be
+    /// estimated.
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        if self.games() == 0 {
+            return None;
+        }
+
+        let score = self.score();
+        let margin = Z_95 * (self.variance() / self.games() as f64).sqrt();
+        let (low, high) = (score - margin, score + margin);
+        if low <= 0.0 || high >= 1.0 {
+            return None;
+        }
+
+        Some((elo(score), (elo(high) - elo(low)) / 2.0))
+    }
+}
+
+impl fmt::Display for Results {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} - {} - {} [{:.3}] {}",
+            self.wins,
+            self.losses,
+            self.draws,
+            self.score(),
+            self.games()
+        )
+    }
+}
+
+/// Decision of the test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    Continue,
+    AcceptH0,
+    AcceptH1,
+}
+
+/// Sequential probability ratio test between two Elo hypotheses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    /// Lower and upper bounds of the log-likelihood ratio.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Log-likelihood ratio of H1 against H0.
+    pub fn llr(&self, results: &Results) -> f64 {
+        // Without both wins and losses the variance is not yet meaningful
+        if results.wins == 0 || results.losses == 0 {
+            return 0.0;
+        }
+
+        let score = results.score();
+        let variance = results.variance();
+        let (score0, score1) = (expected_score(self.elo0), expected_score(self.elo1));
+
+        results.games() as f64 * (score1 - score0) * (2.0 * score - score0 - score1)
+            / (2.0 * variance)
+    }
+
+    pub fn decision(&self, results: &Results) -> Decision {
+        let llr = self.llr(results);
+        let (lower, upper) = self.bounds();
+
+        if llr >= upper {
+            Decision::AcceptH1
+        } else if llr <= lower {
+            Decision::AcceptH0
+        } else {
+            Decision::Continue
+        }
+    }
+}
+
+/// Expected score for an Elo difference.
+pub fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Elo difference for an expected score.
+pub fn elo(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
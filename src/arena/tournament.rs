@@ -0,0 +1,350 @@
+//! Tournaments between several engines, with a crosstable and rating estimates.
+//!
+//! Ratings are maximum likelihood estimates of the logistic Elo model over all games, with
+//! one virtual draw added to every pairing as a prior. The prior keeps the ratings of engines
+//! which won or lost every game finite. Ratings are centred on an average of zero, and their
+//! 95% confidence intervals are estimated from the Fisher information of every engine.
+
+use super::sprt::{Results, expected_score};
+use shakmaty::KnownOutcome;
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+};
+
+// Normal quantile of the 95% confidence interval
+const Z_95: f64 = 1.959964;
+
+// Iterations of the rating estimation and the change in Elo considered converged
+const MAX_ITERATIONS: usize = 10_000;
+const TOLERANCE: f64 = 1e-6;
+const MAX_STEP: f64 = 200.0;
+
+// Derivative of the expected score with respect to the Elo difference, per unit of variance
+const ELO_SLOPE: f64 = std::f64::consts::LN_10 / 400.0;
+
+/// Which engines meet each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    // Every engine plays every other engine
+    RoundRobin,
+    // The first engine plays every other engine
+    Gauntlet,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "roundrobin" | "round-robin" => Ok(Format::RoundRobin),
+            "gauntlet" => Ok(Format::Gauntlet),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A scheduled game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pairing {
+    pub white: usize,
+    pub black: usize,
+    pub opening: usize,
+}
+
+/// Schedules the games of a tournament. Every pairing plays each of its openings twice, with
+/// colours reversed, and every round uses the next opening.
+pub fn schedule(format: Format, engines: usize, rounds: usize, openings: usize) -> Vec<Pairing> {
+    let mut pairs = Vec::new();
+    for first in 0..engines {
+        for second in first + 1..engines {
+            if format == Format::RoundRobin || first == 0 {
+                pairs.push((first, second));
+            }
+        }
+    }
+
+    let mut games = Vec::new();
+    for round in 0..rounds {
+        for &(first, second) in &pairs {
+            let opening = round % openings.max(1);
+            games.push(Pairing {
+                white: first,
+                black: second,
+                opening,
+            });
+            games.push(Pairing {
+                white: second,
+                black: first,
+                opening,
+            });
+        }
+    }
+    games
+}
+
+/// Rating estimate of an engine.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rating {
+    pub elo: f64,
+    // Half width of the 95% confidence interval
+    pub error: f64,
+}
+
+/// Results of a tournament so far.
+pub struct Standings {
+    names: Vec<String>,
+    // Results of every engine against every other engine, from the first engine's perspective
+    results: Vec<Vec<Results>>,
+}
+
+impl Standings {
+    pub fn new(names: Vec<String>) -> Self {
+        let count = names.len();
+        Standings {
+            names,
+            results: vec![vec![Results::default(); count]; count],
+        }
+    }
+
+    /// Records the outcome of a game.
+    pub fn add(&mut self, white: usize, black: usize, outcome: KnownOutcome) {
+        match outcome {
+            KnownOutcome::Decisive { winner } if winner.is_white() => {
+                self.results[white][black].wins += 1;
+                self.results[black][white].losses += 1;
+            }
+            KnownOutcome::Decisive { .. } => {
+                self.results[white][black].losses += 1;
+                self.results[black][white].wins += 1;
+            }
+            KnownOutcome::Draw => {
+                self.results[white][black].draws += 1;
+                self.results[black][white].draws += 1;
+            }
+        }
+    }
+
+    /// Results of an engine against the whole field.
+    pub fn total(&self, engine: usize) -> Results {
+        self.results[engine]
+            .iter()
+            .fold(Results::default(), |total, results| Results {
+                wins: total.wins + results.wins,
+                draws: total.draws + results.draws,
+                losses: total.losses + results.losses,
+            })
+    }
+
+    /// Rating estimates of all engines.
+    pub fn ratings(&self) -> Vec<Rating> {
+        let count = self.names.len();
+        let mut elo = vec![0.0; count];
+
+        // Games and points between every pair, including the virtual draw
+        let games = |i: usize, j: usize| {
+            let games = self.results[i][j].games() as f64;
+            if games > 0.0 { games + 1.0 } else { 0.0 }
+        };
+        let points = |i: usize, j: usize| {
+            let results = &self.results[i][j];
+            results.wins as f64 + results.draws as f64 / 2.0 + 0.5
+        };
+
+        // Newton iterations on every rating in turn
+        for _ in 0..MAX_ITERATIONS {
+            let mut change: f64 = 0.0;
+            for i in 0..count {
+                let (mut surplus, mut information) = (0.0, 0.0);
+                for j in (0..count).filter(|&j| j != i && games(i, j) > 0.0) {
+                    let expected = expected_score(elo[i] - elo[j]);
+                    surplus += points(i, j) - games(i, j) * expected;
+                    information += games(i, j) * expected * (1.0 - expected) * ELO_SLOPE;
+                }
+                if information > 0.0 {
+                    // Steps are limited to keep far off starting points from overshooting
+                    let step = (surplus / information).clamp(-MAX_STEP, MAX_STEP);
+                    elo[i] += step;
+                    change = change.max(step.abs());
+                }
+            }
+
+            let mean = elo.iter().sum::<f64>() / count as f64;
+            elo.iter_mut().for_each(|elo| *elo -= mean);
+            if change < TOLERANCE {
+                break;
+            }
+        }
+
+        (0..count)
+            .map(|i| {
+                let information: f64 = (0..count)
+                    .filter(|&j| j != i)
+                    .map(|j| {
+                        let expected = expected_score(elo[i] - elo[j]);
+                        games(i, j) * expected * (1.0 - expected) * ELO_SLOPE * ELO_SLOPE
+                    })
+                    .sum();
+                let error = if information > 0.0 {
+                    Z_95 / information.sqrt()
+                } else {
+                    f64::INFINITY
+                };
+                Rating { elo: elo[i], error }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Standings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ratings = self.ratings();
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|&a, &b| ratings[b].elo.total_cmp(&ratings[a].elo));
+        let width = self
+            .names
+            .iter()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+
+        writeln!(
+            f,
+            "{:>4} {:<width$} {:>7} {:>6} {:>6} {:>7} {:>6}",
+            "Rank", "Name", "Elo", "+/-", "Games", "Score", "Draws"
+        )?;
+        for (rank, &i) in order.iter().enumerate() {
+            let total = self.total(i);
+            let (score, draws) = match total.games() {
+                0 => (0.0, 0.0),
+                games => (total.score(), total.draws as f64 / games as f64),
+            };
+            writeln!(
+                f,
+                "{:>4} {:<width$} {:>+7.1} {:>6.1} {:>6} {:>6.1}% {:>5.1}%",
+                rank + 1,
+                self.names[i],
+                ratings[i].elo,
+                ratings[i].error,
+                total.games(),
+                score * 100.0,
+                draws * 100.0
+            )?;
+        }
+
+        // Crosstable of wins, draws and losses against every opponent
+        let cell = order
+            .iter()
+            .flat_map(|&i| order.iter().map(move |&j| (i, j)))
+            .map(|(i, j)| crosstable_cell(&self.results[i][j]).len())
+            .max()
+            .unwrap_or(0)
+            .max(width);
+
+        writeln!(f)?;
+        write!(f, "{:<width$}", "")?;
+        for &j in &order {
+            write!(f, " {:>cell$}", self.names[j])?;
+        }
+        writeln!(f)?;
+        for &i in &order {
+            write!(f, "{:<width$}", self.names[i])?;
+            for &j in &order {
+                let text = if i == j {
+                    "-".to_string()
+                } else {
+                    crosstable_cell(&self.results[i][j])
+                };
+                write!(f, " {:>cell$}", text)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn crosstable_cell(results: &Results) -> String {
+    format!("{}-{}-{}", results.wins, results.draws, results.losses)
+}
+
+/// Record of finished games, written as they finish so that tournaments can be resumed.
+///
+/// The file starts with the engine names and the number of games, followed by one line per
+/// finished game with its index in the schedule and its result.
+pub struct StateFile {
+    file: File,
+}
+
+impl StateFile {
+    /// Opens a state file, returning the results of the games it records. The file is created
+    /// if it does not exist, and must belong to the same tournament otherwise.
+    pub fn open(
+        path: impl AsRef<Path>,
+        names: &[String],
+        games: usize,
+    ) -> io::Result<(Self, HashMap<usize, KnownOutcome>)> {
+        let path = path.as_ref();
+        let header = [
+            format!("engines {}", names.join(" ")),
+            format!("games {}", games),
+        ];
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut finished = HashMap::new();
+        if path.exists() {
+            let text = fs::read_to_string(path)?;
+            let mut lines = text.split_inclusive('\n').peekable();
+            for expected in &header {
+                if lines.next().map(str::trim_end) != Some(expected) {
+                    return Err(invalid(format!(
+                        "'{}' belongs to a different tournament",
+                        path.display()
+                    )));
+                }
+            }
+
+            // A game recorded while the tournament was interrupted may have left an incomplete
+            // last line, which is dropped so that the game is played again
+            let mut complete = text.len();
+            while let Some(line) = lines.next() {
+                let parsed = line
+                    .trim_end()
+                    .split_once(' ')
+                    .and_then(|(index, outcome)| {
+                        Some((index.parse::<usize>().ok()?, outcome.parse().ok()?))
+                    });
+                match parsed {
+                    Some((index, outcome)) if index < games && line.ends_with('\n') => {
+                        finished.insert(index, outcome);
+                    }
+                    _ if lines.peek().is_none() => complete -= line.len(),
+                    _ => {
+                        return Err(invalid(format!("invalid state line '{}'", line.trim_end())));
+                    }
+                }
+            }
+
+            let file = OpenOptions::new().append(true).open(path)?;
+            file.set_len(complete as u64)?;
+            return Ok((StateFile { file }, finished));
+        }
+
+        let mut file = File::create(path)?;
+        for line in &header {
+            writeln!(file, "{}", line)?;
+        }
+        Ok((StateFile { file }, finished))
+    }
+
+    /// Records a finished game.
+    pub fn record(&mut self, index: usize, outcome: KnownOutcome) -> io::Result<()> {
+        writeln!(self.file, "{} {}", index, outcome)?;
+        self.file.flush()
+    }
+}
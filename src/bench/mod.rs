@@ -0,0 +1,103 @@
+//! Fixed-depth search of a fixed set of positions.
+//!
+//! The total node count is a signature of the search: a change which alters it changes what
+//! the engine does, while a change which keeps it is a pure speed change. Engine testing
+//! frameworks run `checkm8 bench` to read the signature and the speed of a build, so the
+//! positions and depth must stay fixed and the search must be deterministic.
+
+use crate::{SearchControl, engine::Engine, scoring::Evaluator};
+use shakmaty::{CastlingMode, Chess, fen::Fen};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Depth searched when none is given.
+pub const DEFAULT_DEPTH: u8 = 5;
+
+/// Positions searched by the benchmark, shared with the criterion benchmarks.
+pub const FENS: [&str; 20] = [
+    "6Q1/p1p3P1/1k1p2N1/p1n1p2P/5r2/1b6/2n4K/b1q2b2 b - - 29 30",
+    "6QR/8/3p1kN1/1P5P/3N1r2/1b4P1/3r4/2K2b2 b - - 13 10",
+    "5b2/2pk2P1/3p2N1/pP2p2P/5r2/1b4P1/2K5/b1q5 w - - 17 16",
+    "3R3R/2p3P1/1k1p4/pP6/3N4/1b4P1/2nr4/b3K3 w - - 34 9",
+    "3k2QR/p5P1/3p2N1/pPn1p3/5r2/1b4P1/2n4P/b1K5 b - - 2 25",
+    "6QR/2p3P1/6k1/pPn1p2P/3N1r2/8/K1n5/b1q2b2 b - - 21 2",
+    "4k1Q1/p5P1/6N1/pPn1p3/8/1b4P1/3r3P/2K2b2 b - - 26 11",
+    "3R1b2/k1p3P1/6N1/pPn4P/5r2/6K1/2n4P/b1q5 w - - 13 23",
+    "5bQ1/k1p3P1/8/1P5P/3N1r2/1b4P1/K1nr3P/b1q2b2 w - - 22 6",
+    "4kbQR/p5P1/8/p7/3N4/Kb4P1/3r4/2q2b2 w - - 6 18",
+    "3R1bQ1/k5P1/3p2N1/pP2p2P/3N4/8/2n4P/1Kq5 w - - 11 1",
+    "3R1bQR/k1p5/3p4/pPn1p3/8/1b4P1/2K4P/b4b2 w - - 8 11",
+    "3R2QR/pk4P1/3p2N1/1Pn1p3/5r2/1b1K4/3r3P/2q5 w - - 6 30",
+    "4kb1R/2p3P1/3p4/p1n1p3/3N1r2/8/2K4P/b4b2 w - - 4 12",
+    "3R1b1k/p7/8/4p2P/3N1r2/1b2K1P1/2nr3P/5b2 w - - 12 22",
+    "3R2Q1/1kp5/3p2N1/pPn1p2P/3N4/6P1/8/b4K2 w - - 15 15",
+    "3R1bQ1/k5P1/3p2N1/p1n1p2P/8/1b6/1Knr3P/2q2b2 w - - 26 3",
+    "3R1bQR/2p5/3p1kN1/pPn1p3/5r2/6P1/3r2KP/b4b2 w - - 15 5",
+    "5b2/pkp3P1/3p4/7P/8/1b4K1/2nr3P/b1q2b2 w - - 39 11",
+    "3R2Q1/p1p1k3/8/p3p2P/5r2/6PK/8/b1q2b2 w - - 10 6",
+];
+
+/// Positions of the benchmark.
+pub fn positions() -> Vec<Chess> {
+    FENS.iter()
+        .map(|fen| {
+            fen.parse::<Fen>()
+                .unwrap()
+                .into_position(CastlingMode::Standard)
+                .unwrap()
+        })
+        .collect()
+}
+
+/// Result of a benchmark run.
+pub struct Bench {
+    // Nodes searched in every position
+    pub nodes: Vec<u64>,
+    pub time: Duration,
+}
+
+impl Bench {
+    pub fn total_nodes(&self) -> u64 {
+        self.nodes.iter().sum()
+    }
+
+    pub fn nps(&self) -> u64 {
+        (self.total_nodes() as f64 / self.time.as_secs_f64().max(1e-9)) as u64
+    }
+}
+
+impl fmt::Display for Bench {
+    /// Ends with the total nodes and speed in the form testing frameworks look for.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (number, nodes) in self.nodes.iter().enumerate() {
+            writeln!(f, "Position {:>2}: {} nodes", number + 1, nodes)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Time: {} ms", self.time.as_millis())?;
+        write!(f, "{} nodes {} nps", self.total_nodes(), self.nps())
+    }
+}
+
+/// Searches every position to the given depth on a search thread of its own.
+pub fn run(depth: u8, evaluator: Evaluator) -> Bench {
+    let mut engine = Engine::new();
+
+    // Moves must not be picked at random, or the node count could change between runs
+    engine.set_evaluator(evaluator);
+    engine.set_randomness(0);
+
+    let start = Instant::now();
+    let mut nodes = Vec::new();
+    for position in positions() {
+        engine.set_chess(position);
+
+        // Positions without moves fail to search and count no nodes
+        let result = engine.search(SearchControl::ToDepth(depth));
+        nodes.push(result.map_or(0, |result| result.nodes));
+    }
+    let time = start.elapsed();
+
+    Bench { nodes, time }
+}
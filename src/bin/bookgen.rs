@@ -0,0 +1,99 @@
+use checkm8::book::builder::{BookBuilder, BuildSettings};
+use checkm8::pgn::read_games;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Instant;
+
+/// Command line settings.
+struct Config {
+    output: String,
+    settings: BuildSettings,
+    inputs: Vec<String>,
+}
+
+fn main() {
+    let config = parse_args();
+    let start = Instant::now();
+
+    let mut builder = BookBuilder::new(config.settings);
+    let (mut games, mut skipped) = (0, 0);
+    for input in &config.inputs {
+        let text = match fs::read(input) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                eprintln!("Failed to read '{}': {}", input, e);
+                process::exit(1);
+            }
+        };
+
+        for game in read_games(&text) {
+            let added = game
+                .map_err(|e| e.to_string())
+                .and_then(|game| builder.add_game(&game).map_err(|e| e.to_string()));
+            match added {
+                Ok(()) => games += 1,
+                Err(e) => {
+                    skipped += 1;
+                    eprintln!("Skipping game {} of '{}': {}", games + skipped, input, e);
+                }
+            }
+        }
+    }
+
+    let book = builder.build();
+    if let Err(e) = book.save(&config.output) {
+        eprintln!("Failed to write book '{}': {}", config.output, e);
+        process::exit(1);
+    }
+
+    eprintln!(
+        "Read {} games ({} skipped) in {:.1}s",
+        games,
+        skipped,
+        start.elapsed().as_secs_f64()
+    );
+    eprintln!("Wrote {} entries to '{}'", book.len(), config.output);
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        output: "book.bin".into(),
+        settings: BuildSettings::default(),
+        inputs: Vec::new(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => config.output = args.next().unwrap_or_else(|| usage()),
+            "--max-ply" => config.settings.max_ply = parse_value(args.next()),
+            "--min-games" => config.settings.min_games = parse_value(args.next()),
+            "--min-score" => config.settings.min_score = parse_value(args.next()),
+            flag if flag.starts_with("--") => usage(),
+            input => config.inputs.push(input.into()),
+        }
+    }
+
+    if config.inputs.is_empty() || config.settings.min_score > 100 {
+        usage();
+    }
+
+    config
+}
+
+fn parse_value<T: std::str::FromStr>(arg: Option<String>) -> T {
+    arg.and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| usage())
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: bookgen [--output FILE] [--max-ply N] [--min-games N] [--min-score PERCENT] PGN..."
+    );
+    eprintln!(
+        "Builds a Polyglot book from the moves played in the games, weighted by the points \
+         they scored. Defaults: book.bin, 30 plies, 3 games, 0 percent."
+    );
+    process::exit(2);
+}
@@ -0,0 +1,411 @@
+use checkm8::engine::Engine;
+use checkm8::{Score, SearchControl};
+use rand::{RngExt, SeedableRng, rngs::Xoshiro256PlusPlus};
+use shakmaty::fen::Fen;
+use shakmaty::zobrist::Zobrist64;
+use shakmaty::{CastlingMode, Chess, Color, EnPassantMode, KnownOutcome, Outcome, Position};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+// Positions with a larger score are not recorded, and the game is adjudicated
+const MAX_RECORD_SCORE: Score = 3000;
+// Plies a side must stay above the adjudication score to be declared the winner
+const ADJUDICATION_PLIES: u32 = 8;
+// Games still running after this many plies are declared drawn
+const MAX_GAME_PLIES: u32 = 400;
+// Size of a record in the binary format
+const RECORD_SIZE: usize = 32;
+
+/// Command line settings.
+struct Config {
+    positions: u64,
+    threads: usize,
+    depth: Option<u8>,
+    nodes: Option<u64>,
+    random_plies: u32,
+    // Seed of the opening generators, one per worker
+    seed: u64,
+    output: String,
+}
+
+impl Config {
+    fn control(&self) -> SearchControl {
+        match (self.depth, self.nodes) {
+            (_, Some(nodes)) => SearchControl::Nodes(nodes),
+            (Some(depth), None) => SearchControl::ToDepth(depth),
+            (None, None) => SearchControl::ToDepth(4),
+        }
+    }
+}
+
+/// A recorded position, waiting for the game result.
+struct Record {
+    position: Chess,
+    hash: u64,
+    // Search score from white's perspective
+    score: i16,
+}
+
+/// Final result of a game from white's perspective: 0 = loss, 1 = draw, 2 = win.
+type GameResult = u8;
+
+/// Output files shared between all worker threads.
+struct Output {
+    text: BufWriter<File>,
+    binary: BufWriter<File>,
+    seen: HashSet<u64>,
+    written: u64,
+}
+
+fn main() {
+    let config = Arc::new(parse_args());
+
+    let output = match open_output(&config.output) {
+        Ok(output) => Arc::new(Mutex::new(output)),
+        Err(e) => {
+            eprintln!("Failed to open output '{}': {}", config.output, e);
+            process::exit(1);
+        }
+    };
+
+    let resumed = output.lock().unwrap().written;
+    if resumed > 0 {
+        eprintln!("Resuming with {} existing positions", resumed);
+    }
+
+    // Every run picks other openings unless its seed is given
+    eprintln!("Seed: {}", config.seed);
+
+    let start = Instant::now();
+    let workers: Vec<_> = (0..config.threads)
+        .map(|index| {
+            let config = Arc::clone(&config);
+            let output = Arc::clone(&output);
+            thread::spawn(move || worker(&config, &output, index as u64))
+        })
+        .collect();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let written = output.lock().unwrap().written - resumed;
+    eprintln!(
+        "\nWrote {} positions in {:.1}s",
+        written,
+        start.elapsed().as_secs_f64()
+    );
+}
+
+/// Plays games until enough positions have been written.
+fn worker(config: &Config, output: &Mutex<Output>, index: u64) {
+    let mut engine = Engine::new();
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(config.seed.wrapping_add(index));
+
+    while output.lock().unwrap().written < config.positions {
+        let (records, result) = play_game(config, &mut engine, &mut rng);
+
+        let mut output = output.lock().unwrap();
+        for record in records {
+            if output.written >= config.positions {
+                break;
+            }
+            if let Err(e) = output.write(&record, result) {
+                eprintln!("Failed to write record: {}", e);
+                process::exit(1);
+            }
+        }
+        if let Err(e) = output.flush() {
+            eprintln!("Failed to flush output: {}", e);
+            process::exit(1);
+        }
+
+        eprint!("\r{} / {} positions", output.written, config.positions);
+    }
+}
+
+/// Plays a single self-play game from a randomised opening.
+fn play_game(
+    config: &Config,
+    engine: &mut Engine,
+    rng: &mut Xoshiro256PlusPlus,
+) -> (Vec<Record>, GameResult) {
+    let mut position = random_opening(config.random_plies, rng);
+    let mut records = Vec::new();
+    let mut repetitions: HashMap<u64, u8> = HashMap::new();
+    let mut winning_plies = 0;
+    let mut plies = 0;
+
+    let result = loop {
+        // Natural end of the game
+        if let Outcome::Known(outcome) = position.outcome() {
+            break match outcome {
+                KnownOutcome::Decisive { winner } => winner.fold_wb(2, 0),
+                KnownOutcome::Draw => 1,
+            };
+        }
+
+        // Draw by repetition or the fifty move rule
+        let hash = hash(&position);
+        let count = repetitions.entry(hash).or_default();
+        *count += 1;
+        if *count >= 3 || position.halfmoves() >= 100 || plies >= MAX_GAME_PLIES {
+            break 1;
+        }
+
+        engine.set_chess(position.clone());
+        let result = match engine.search(config.control()) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Search failed: {}", e);
+                process::exit(1);
+            }
+        };
+        let mv = result.best_move;
+
+        // Only a completed depth gives a score
+        let score = (result.depth > 0).then_some(result.score);
+        let white_score = score.map(|s| position.turn().fold_wb(s, -s));
+
+        // Adjudicate clearly decided games
+        match white_score {
+            Some(s) if s.abs() >= MAX_RECORD_SCORE => {
+                winning_plies += 1;
+                if winning_plies >= ADJUDICATION_PLIES {
+                    break if s > 0 { 2 } else { 0 };
+                }
+            }
+            _ => winning_plies = 0,
+        }
+
+        // Only record quiet positions with a usable score
+        if let Some(s) = white_score {
+            let quiet = !position.is_check() && !mv.is_capture() && !mv.is_promotion();
+            if quiet && s.abs() < MAX_RECORD_SCORE {
+                records.push(Record {
+                    position: position.clone(),
+                    hash,
+                    score: s as i16,
+                });
+            }
+        }
+
+        position = position.play(mv).unwrap();
+        plies += 1;
+    };
+
+    (records, result)
+}
+
+/// Plays a number of random moves from the starting position.
+fn random_opening(plies: u32, rng: &mut Xoshiro256PlusPlus) -> Chess {
+    loop {
+        let mut position = Chess::default();
+
+        for _ in 0..plies {
+            let moves = position.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let mv = moves[rng.random_range(0..moves.len())];
+            position = position.play(mv).unwrap();
+        }
+
+        // Retry openings which already ended the game
+        if !position.is_game_over() {
+            return position;
+        }
+    }
+}
+
+fn hash(position: &Chess) -> u64 {
+    position.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0
+}
+
+impl Output {
+    fn write(&mut self, record: &Record, result: GameResult) -> io::Result<()> {
+        // Skip positions seen before, also in earlier runs
+        if !self.seen.insert(record.hash) {
+            return Ok(());
+        }
+
+        writeln!(
+            self.text,
+            "{}",
+            text_line(&record.position, record.score, result)
+        )?;
+        self.binary
+            .write_all(&encode(&record.position, record.score, result))?;
+        self.written += 1;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.text.flush()?;
+        self.binary.flush()
+    }
+}
+
+/// Opens the output files, picking up the records of an interrupted run.
+///
+/// Records are read back from the text file, so an incomplete last line is dropped and the
+/// binary file is rewritten to match. Both files are rewritten next to the old ones and then
+/// renamed over them, the text file last, so an interruption never loses the existing records.
+fn open_output(name: &str) -> io::Result<Output> {
+    let text_path = format!("{}.txt", name);
+    let binary_path = format!("{}.bin", name);
+    let text_tmp = format!("{}.txt.tmp", name);
+    let binary_tmp = format!("{}.bin.tmp", name);
+
+    let mut existing = Vec::new();
+    if Path::new(&text_path).exists() {
+        for line in BufReader::new(File::open(&text_path)?).lines() {
+            if let Some(record) = parse_text_line(&line?) {
+                existing.push(record);
+            }
+        }
+    }
+
+    let mut output = Output {
+        text: BufWriter::new(File::create(&text_tmp)?),
+        binary: BufWriter::new(File::create(&binary_tmp)?),
+        seen: HashSet::new(),
+        written: 0,
+    };
+
+    for (record, result) in existing {
+        output.write(&record, result)?;
+    }
+    output.flush()?;
+    output.text.get_ref().sync_all()?;
+    output.binary.get_ref().sync_all()?;
+    fs::rename(&binary_tmp, &binary_path)?;
+    fs::rename(&text_tmp, &text_path)?;
+
+    // Continue appending to the rewritten files
+    output.text = BufWriter::new(OpenOptions::new().append(true).open(&text_path)?);
+    output.binary = BufWriter::new(OpenOptions::new().append(true).open(&binary_path)?);
+    debug_assert_eq!(
+        fs::metadata(&binary_path)?.len(),
+        output.written * RECORD_SIZE as u64
+    );
+
+    Ok(output)
+}
+
+/// Plain-text record: `<fen> | <score> | <result>`, with the result as 1.0, 0.5 or 0.0.
+fn text_line(position: &Chess, score: i16, result: GameResult) -> String {
+    let fen = Fen::from_position(position, EnPassantMode::Legal);
+    let result = match result {
+        2 => "1.0",
+        1 => "0.5",
+        _ => "0.0",
+    };
+    format!("{} | {} | {}", fen, score, result)
+}
+
+fn parse_text_line(line: &str) -> Option<(Record, GameResult)> {
+    let mut fields = line.split(" | ");
+    let fen = Fen::from_str(fields.next()?).ok()?;
+    let score = fields.next()?.parse().ok()?;
+    let result = match fields.next()? {
+        "1.0" => 2,
+        "0.5" => 1,
+        "0.0" => 0,
+        _ => return None,
+    };
+
+    let position: Chess = fen.into_position(CastlingMode::Standard).ok()?;
+    let record = Record {
+        hash: hash(&position),
+        position,
+        score,
+    };
+    Some((record, result))
+}
+
+/// Compact binary record of 32 bytes, all values little-endian:
+///
+/// - occupied squares as a 64-bit bitboard
+/// - one 4-bit piece code per occupied square in ascending square order, where
+///   `code = color * 6 + role - 1` with white = 0
+/// - side to move (0 = white, 1 = black)
+/// - search score from white's perspective as i16
+/// - game result from white's perspective (0 = loss, 1 = draw, 2 = win)
+/// - halfmove clock as u8, fullmove number as u16 and one reserved byte
+fn encode(position: &Chess, score: i16, result: GameResult) -> [u8; RECORD_SIZE] {
+    let mut bytes = [0; RECORD_SIZE];
+    let board = position.board();
+
+    bytes[0..8].copy_from_slice(&u64::from(board.occupied()).to_le_bytes());
+
+    for (i, (_, piece)) in board.iter().enumerate() {
+        let color = match piece.color {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+        let code = color * 6 + piece.role as u8 - 1;
+        bytes[8 + i / 2] |= code << (4 * (i % 2));
+    }
+
+    bytes[24] = position.turn().fold_wb(0, 1);
+    bytes[25..27].copy_from_slice(&score.to_le_bytes());
+    bytes[27] = result;
+    bytes[28] = position.halfmoves().min(u8::MAX as u32) as u8;
+    bytes[29..31]
+        .copy_from_slice(&(position.fullmoves().get().min(u16::MAX as u32) as u16).to_le_bytes());
+
+    bytes
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        positions: 100_000,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        depth: None,
+        nodes: None,
+        random_plies: 8,
+        seed: rand::random(),
+        output: "datagen".into(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--positions" => config.positions = parse_value(&value),
+            "--threads" => config.threads = parse_value(&value),
+            "--depth" => config.depth = Some(parse_value(&value)),
+            "--nodes" => config.nodes = Some(parse_value(&value)),
+            "--random-plies" => config.random_plies = parse_value(&value),
+            "--seed" => config.seed = parse_value(&value),
+            "--output" => config.output = value,
+            _ => usage(),
+        }
+    }
+
+    config
+}
+
+fn parse_value<T: FromStr>(value: &str) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: datagen [--positions N] [--threads N] [--depth N | --nodes N] \
+         [--random-plies N] [--seed N] [--output NAME]"
+    );
+    eprintln!("Writes NAME.txt and NAME.bin, resuming from NAME.txt if it exists.");
+    eprintln!("Openings are picked from a random seed unless one is given.");
+    process::exit(2);
+}
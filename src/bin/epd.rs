@@ -0,0 +1,159 @@
+use checkm8::SearchControl;
+use checkm8::epd::{Attempt, Runner, read_suite};
+use checkm8::util::json_string;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+/// Command line settings.
+struct Config {
+    suites: Vec<String>,
+    depth: Option<u8>,
+    nodes: Option<u64>,
+    movetime: u64,
+    json: Option<String>,
+}
+
+impl Config {
+    fn control(&self) -> SearchControl {
+        match (self.depth, self.nodes) {
+            (_, Some(nodes)) => SearchControl::Nodes(nodes),
+            (Some(depth), None) => SearchControl::ToDepth(depth),
+            (None, None) => SearchControl::TimeLimit(self.movetime),
+        }
+    }
+}
+
+fn main() {
+    let config = parse_args();
+    let mut runner = Runner::new();
+
+    let mut results: Vec<(&str, Vec<Attempt>)> = Vec::new();
+    for suite in &config.suites {
+        let text = match fs::read_to_string(suite) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Failed to read suite '{}': {}", suite, e);
+                process::exit(1);
+            }
+        };
+
+        let mut attempts = Vec::new();
+        for (line, epd) in read_suite(&text) {
+            let epd = match epd {
+                Ok(epd) => epd,
+                Err(e) => {
+                    eprintln!("Skipping {}:{}: {}", suite, line, e);
+                    continue;
+                }
+            };
+
+            let attempt = runner.run(&epd, config.control());
+            let name = attempt
+                .id
+                .clone()
+                .unwrap_or_else(|| format!("{}:{}", suite, line));
+            match attempt.solution_time {
+                Some(time) => println!(
+                    "{:<16} solved {:<8} {:>7.2}s {:>10} nodes",
+                    name,
+                    attempt.best_move.to_string(),
+                    time.as_secs_f64(),
+                    attempt.solution_nodes.unwrap_or(0)
+                ),
+                None => println!(
+                    "{:<16} failed {:<8} ({})",
+                    name,
+                    attempt.best_move.to_string(),
+                    epd.solution()
+                ),
+            }
+            attempts.push(attempt);
+        }
+        results.push((suite, attempts));
+    }
+
+    let attempts: Vec<&Attempt> = results.iter().flat_map(|(_, a)| a).collect();
+    let solved = attempts.iter().filter(|a| a.solved).count();
+    let time: Duration = attempts.iter().map(|a| a.time).sum();
+    let nodes: u64 = attempts.iter().map(|a| a.nodes).sum();
+    println!();
+    println!(
+        "Solved {} of {} ({:.1}%) in {:.1}s, {} nodes",
+        solved,
+        attempts.len(),
+        100.0 * solved as f64 / attempts.len().max(1) as f64,
+        time.as_secs_f64(),
+        nodes
+    );
+
+    if let Some(path) = &config.json {
+        let suites: Vec<String> = results
+            .iter()
+            .map(|(suite, attempts)| {
+                let positions: Vec<String> = attempts.iter().map(Attempt::to_json).collect();
+                format!(
+                    "{{\"name\":{},\"solved\":{},\"total\":{},\"positions\":[{}]}}",
+                    json_string(suite),
+                    attempts.iter().filter(|a| a.solved).count(),
+                    attempts.len(),
+                    positions.join(",")
+                )
+            })
+            .collect();
+        let report = format!(
+            "{{\"solved\":{},\"total\":{},\"time_ms\":{},\"nodes\":{},\"suites\":[{}]}}\n",
+            solved,
+            attempts.len(),
+            time.as_millis(),
+            nodes,
+            suites.join(",")
+        );
+
+        if let Err(e) = fs::write(path, report) {
+            eprintln!("Failed to write report to '{}': {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        suites: Vec::new(),
+        depth: None,
+        nodes: None,
+        movetime: 1000,
+        json: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut next = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--depth" => config.depth = Some(parse_value(next())),
+            "--nodes" => config.nodes = Some(parse_value(next())),
+            "--movetime" => config.movetime = parse_value(next()),
+            "--json" => config.json = Some(next()),
+            _ if arg.starts_with("--") => usage(),
+            _ => config.suites.push(arg),
+        }
+    }
+
+    if config.suites.is_empty() {
+        usage();
+    }
+
+    config
+}
+
+fn parse_value<T: std::str::FromStr>(value: String) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: epd [--depth N | --nodes N | --movetime MS] [--json FILE] SUITE...");
+    eprintln!("Searches every position for one second unless a limit is given, and writes");
+    eprintln!("the results of all positions to FILE as JSON.");
+    process::exit(2);
+}
@@ -0,0 +1,245 @@
+use checkm8::arena::{
+    engine::{Engine, EngineConfig},
+    game::{Adjudication, Settings, TimeControl, play},
+    load_openings,
+    sprt::{Decision, Results, Sprt},
+};
+use shakmaty::{Chess, KnownOutcome};
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process;
+use std::time::Duration;
+
+/// Command line settings.
+struct Config {
+    engines: Vec<EngineConfig>,
+    settings: Settings,
+    openings: Option<String>,
+    // Games are played in pairs, so odd numbers are rounded up
+    games: u32,
+    pgn: Option<String>,
+    sprt: Option<Sprt>,
+}
+
+fn main() {
+    let config = parse_args();
+
+    let openings = match &config.openings {
+        Some(path) => match load_openings(path) {
+            Ok(openings) if !openings.is_empty() => openings,
+            Ok(_) => {
+                eprintln!("No openings found in '{}'", path);
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to load openings from '{}': {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => vec![Chess::default()],
+    };
+
+    let mut engines = [
+        start_engine(&config.engines[0]),
+        start_engine(&config.engines[1]),
+    ];
+    let names = [engines[0].name.clone(), engines[1].name.clone()];
+    if let Some(sprt) = &config.sprt {
+        let (lower, upper) = sprt.bounds();
+        eprintln!(
+            "SPRT: elo0 {} elo1 {} alpha {} beta {}, LLR bounds [{:.2}, {:.2}]",
+            sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta, lower, upper
+        );
+    }
+
+    let mut results = Results::default();
+    for number in 0..config.games.div_ceil(2) * 2 {
+        // Both engines play each opening once with either colour
+        let opening = &openings[(number / 2) as usize % openings.len()];
+        let first_is_white = number % 2 == 0;
+
+        let [first, second] = &mut engines;
+        let (white, black) = if first_is_white {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        let mut played = play(white, black, opening, &config.settings);
+        played.game.set_tag("Round", &(number + 1).to_string());
+
+        match played.outcome {
+            KnownOutcome::Decisive { winner } if winner.is_white() == first_is_white => {
+                results.wins += 1
+            }
+            KnownOutcome::Decisive { .. } => results.losses += 1,
+            KnownOutcome::Draw => results.draws += 1,
+        }
+
+        println!(
+            "Game {}: {} - {} {} ({})",
+            number + 1,
+            played.game.tag("White").unwrap_or("?"),
+            played.game.tag("Black").unwrap_or("?"),
+            played.game.result(),
+            played.reason
+        );
+        report(&names, &results, config.sprt.as_ref());
+
+        if let Some(path) = &config.pgn {
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", played.game));
+            if let Err(e) = written {
+                eprintln!("Failed to write game to '{}': {}", path, e);
+            }
+        }
+
+        // Engines which crashed or hung are replaced for the next game
+        for (engine, config) in engines.iter_mut().zip(&config.engines) {
+            if !engine.is_running() || engine.ready().is_err() {
+                eprintln!("Restarting {}", config.name);
+                *engine = start_engine(config);
+            }
+        }
+
+        if let Some(sprt) = &config.sprt {
+            match sprt.decision(&results) {
+                Decision::Continue => (),
+                Decision::AcceptH0 => {
+                    println!("SPRT: H0 accepted");
+                    break;
+                }
+                Decision::AcceptH1 => {
+                    println!("SPRT: H1 accepted");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Prints the running score, Elo estimate and log-likelihood ratio.
+fn report(names: &[String; 2], results: &Results, sprt: Option<&Sprt>) {
+    println!("Score of {} vs {}: {}", names[0], names[1], results);
+
+    match results.elo() {
+        Some((elo, error)) => println!("Elo difference: {:.1} +/- {:.1}", elo, error),
+        None => println!("Elo difference: unknown"),
+    }
+
+    if let Some(sprt) = sprt {
+        let (lower, upper) = sprt.bounds();
+        println!("LLR: {:.2} ({:.2}, {:.2})", sprt.llr(results), lower, upper);
+    }
+}
+
+fn start_engine(config: &EngineConfig) -> Engine {
+    match Engine::start(config) {
+        Ok(engine) => engine,
+        Err(e) => {
+            eprintln!("Failed to start engine '{}': {}", config.command, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        engines: Vec::new(),
+        settings: Settings {
+            time_control: TimeControl::Clock {
+                base: Duration::from_secs(10),
+                increment: Duration::from_millis(100),
+            },
+            time_margin: Duration::from_millis(100),
+            adjudication: Adjudication::default(),
+        },
+        openings: None,
+        games: 1000,
+        pgn: None,
+        sprt: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut next = || args.next().unwrap_or_else(|| usage());
+        let adjudication = &mut config.settings.adjudication;
+
+        match arg.as_str() {
+            "--engine" => match next().parse() {
+                Ok(engine) => config.engines.push(engine),
+                Err(e) => {
+                    eprintln!("Invalid engine: {}", e);
+                    usage();
+                }
+            },
+            "--tc" => config.settings.time_control = parse_value(next()),
+            "--time-margin" => {
+                config.settings.time_margin = Duration::from_millis(parse_value(next()))
+            }
+            "--openings" => config.openings = Some(next()),
+            "--games" => config.games = parse_value(next()),
+            "--pgn" => config.pgn = Some(next()),
+            "--sprt" => {
+                config.sprt = Some(Sprt {
+                    elo0: parse_value(next()),
+                    elo1: parse_value(next()),
+                    alpha: 0.05,
+                    beta: 0.05,
+                })
+            }
+            "--alpha" | "--beta" => {
+                let value = parse_value(next());
+                match (&mut config.sprt, arg.as_str()) {
+                    (Some(sprt), "--alpha") => sprt.alpha = value,
+                    (Some(sprt), _) => sprt.beta = value,
+                    (None, _) => usage(),
+                }
+            }
+            "--draw" => {
+                adjudication.draw_move_number = parse_value(next());
+                adjudication.draw_score = parse_value(next());
+                adjudication.draw_plies = parse_value(next());
+            }
+            "--resign" => {
+                adjudication.resign_score = parse_value(next());
+                adjudication.resign_plies = parse_value(next());
+            }
+            "--max-plies" => adjudication.max_plies = parse_value(next()),
+            _ => usage(),
+        }
+    }
+
+    if config.engines.len() != 2 || config.games == 0 {
+        usage();
+    }
+
+    config
+}
+
+fn parse_value<T: std::str::FromStr>(value: String) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: match --engine SPEC --engine SPEC [OPTIONS]");
+    eprintln!();
+    eprintln!("Engine specifications look like 'cmd=./checkm8 name=new option.UseNNUE=true'.");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  --tc TC                       10+0.1, movetime=MS, nodes=N or depth=N");
+    eprintln!("  --time-margin MS              Time engines may exceed their clock by (100)");
+    eprintln!("  --openings FILE               PGN, FEN or EPD openings, played with both colours");
+    eprintln!("  --games N                     Maximum number of games (1000)");
+    eprintln!("  --pgn FILE                    Append the games to a PGN file");
+    eprintln!("  --sprt ELO0 ELO1              Stop once the SPRT accepts either hypothesis");
+    eprintln!("  --alpha A, --beta B           SPRT error rates (0.05)");
+    eprintln!("  --draw MOVE SCORE PLIES       Draw adjudication (40 10 8)");
+    eprintln!("  --resign SCORE PLIES          Resign adjudication (1000 6)");
+    eprintln!("  --max-plies N                 Draw games longer than this (400)");
+    eprintln!("Adjudication rules are disabled by giving zero plies.");
+    process::exit(2);
+}
@@ -0,0 +1,157 @@
+use checkm8::perft::{Perft, PerftPosition};
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Chess, EnPassantMode};
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Instant;
+
+/// Command line settings.
+struct Config {
+    position: Chess,
+    // Maximum depth of suite positions, all known counts are checked without it
+    depth: Option<u32>,
+    divide: bool,
+    bulk: bool,
+    hash_mb: usize,
+    suite: Option<String>,
+}
+
+fn main() {
+    let config = parse_args();
+    let mut perft = Perft::new(config.bulk, config.hash_mb);
+
+    let Some(path) = &config.suite else {
+        let depth = config.depth.unwrap_or(5);
+        if config.divide {
+            divide(&mut perft, &config.position, depth);
+        } else {
+            for depth in 1..=depth {
+                let (nodes, seconds) = timed(|| perft.count(&config.position, depth));
+                println!(
+                    "Depth {:>2}: {:>14} nodes {:>8.2}s {:>12} nps",
+                    depth,
+                    nodes,
+                    seconds,
+                    (nodes as f64 / seconds.max(1e-9)) as u64
+                );
+            }
+        }
+        return;
+    };
+
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Failed to read suite '{}': {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    let mut failures = 0;
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let suite_position: PerftPosition = match line.parse() {
+            Ok(suite_position) => suite_position,
+            Err(e) => {
+                eprintln!("{}:{}: {}", path, number + 1, e);
+                process::exit(1);
+            }
+        };
+
+        let fen = Fen::from_position(&suite_position.position, EnPassantMode::Legal);
+        for &(depth, expected) in &suite_position.counts {
+            if config.depth.is_some_and(|max| depth > max) {
+                continue;
+            }
+
+            let (nodes, seconds) = timed(|| perft.count(&suite_position.position, depth));
+            let status = if nodes == expected { "ok" } else { "FAILED" };
+            println!(
+                "{} depth {}: {} nodes, expected {} ({:.2}s) {}",
+                fen, depth, nodes, expected, seconds, status
+            );
+            if nodes != expected {
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        eprintln!("{} counts did not match", failures);
+        process::exit(1);
+    }
+}
+
+/// Prints the node count below every move and their total.
+fn divide(perft: &mut Perft, position: &Chess, depth: u32) {
+    let (divide, seconds) = timed(|| perft.divide(position, depth));
+    for (mv, nodes) in &divide {
+        println!(
+            "{}: {}",
+            UciMove::from_move(*mv, CastlingMode::Standard),
+            nodes
+        );
+    }
+
+    let nodes: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+    println!();
+    println!("Moves: {}", divide.len());
+    println!("Nodes: {} ({:.2}s)", nodes, seconds);
+}
+
+fn timed<T>(f: impl FnOnce() -> T) -> (T, f64) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed().as_secs_f64())
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        position: Chess::default(),
+        depth: None,
+        divide: false,
+        bulk: false,
+        hash_mb: 0,
+        suite: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut next = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--fen" => {
+                config.position = next()
+                    .parse::<Fen>()
+                    .ok()
+                    .and_then(|fen| fen.into_position(CastlingMode::Standard).ok())
+                    .unwrap_or_else(|| usage())
+            }
+            "--depth" => config.depth = Some(parse_value(next())),
+            "--divide" => config.divide = true,
+            "--bulk" => config.bulk = true,
+            "--hash" => config.hash_mb = parse_value(next()),
+            "--suite" => config.suite = Some(next()),
+            _ => usage(),
+        }
+    }
+
+    config
+}
+
+fn parse_value<T: std::str::FromStr>(value: String) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: perft [--fen FEN] [--depth N] [--divide] [--bulk] [--hash MB]");
+    eprintln!("       perft --suite FILE [--depth N] [--bulk] [--hash MB]");
+    eprintln!();
+    eprintln!("Counts leaf nodes to every depth up to N (5), or below every move with --divide.");
+    eprintln!("Suites hold a FEN with ';D<depth> <nodes>' counts per line, which are checked up");
+    eprintln!("to depth N (all by default).");
+    process::exit(2);
+}
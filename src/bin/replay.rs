@@ -0,0 +1,111 @@
+use checkm8::session::{Transcript, diff, is_progress, normalize, replay};
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+/// Command line settings.
+struct Config {
+    transcripts: Vec<String>,
+    // Info fields whose values are not compared
+    ignore: Vec<String>,
+    // Whether progress infos are left out of the comparison
+    ignore_progress: bool,
+    timeout: u64,
+}
+
+fn main() {
+    let config = parse_args();
+    let timeout = Duration::from_millis(config.timeout);
+
+    let mut failures = 0;
+    for path in &config.transcripts {
+        let transcript: Transcript = match fs::read_to_string(path).map(|text| text.parse()) {
+            Ok(Ok(transcript)) => transcript,
+            Ok(Err(e)) => {
+                eprintln!("{}: {}", path, e);
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to read transcript '{}': {}", path, e);
+                process::exit(1);
+            }
+        };
+
+        let normalized = |lines: Vec<String>| -> Vec<String> {
+            lines
+                .iter()
+                .filter(|line| !(config.ignore_progress && is_progress(line)))
+                .map(|line| normalize(line, &config.ignore))
+                .collect()
+        };
+        let expected = normalized(transcript.output());
+        let actual = normalized(replay(&transcript, timeout));
+
+        let changes = diff(&expected, &actual);
+        if changes.is_empty() {
+            println!("{}: ok ({} lines)", path, expected.len());
+        } else {
+            println!("{}: output differs", path);
+            for change in &changes {
+                println!("{}", change);
+            }
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        eprintln!(
+            "{} of {} transcripts differ",
+            failures,
+            config.transcripts.len()
+        );
+        process::exit(1);
+    }
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        transcripts: Vec::new(),
+        ignore: Vec::new(),
+        ignore_progress: false,
+        timeout: 10_000,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut next = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--ignore" => config.ignore.push(next()),
+            "--ignore-timing" => {
+                for field in ["time", "nps", "nodes", "hashfull"] {
+                    config.ignore.push(field.into());
+                }
+                config.ignore_progress = true;
+            }
+            "--timeout" => config.timeout = parse_value(next()),
+            _ if arg.starts_with("--") => usage(),
+            _ => config.transcripts.push(arg),
+        }
+    }
+
+    if config.transcripts.is_empty() {
+        usage();
+    }
+
+    config
+}
+
+fn parse_value<T: std::str::FromStr>(value: String) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: replay [--ignore FIELD]... [--ignore-timing] [--timeout MS] TRANSCRIPT...");
+    eprintln!("Feeds the input of every transcript recorded with `checkm8 --record` to the");
+    eprintln!("engine and compares its output with the recorded one. The values of the given");
+    eprintln!("info fields are not compared, --ignore-timing ignores time, nps, nodes and");
+    eprintln!("hashfull, and the progress infos sent during searches. Waits MS (10000) for");
+    eprintln!("every output line expected before an input.");
+    process::exit(2);
+}
@@ -3,7 +3,10 @@ use std::io::{self, Read, Write};
 use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+// How long the child gets to shut down after being sent quit
+const QUIT_TIMEOUT: Duration = Duration::from_secs(5);
 
 fn main() {
     // Path to the release binary
@@ -66,7 +69,7 @@ fn main() {
                         let input_str = String::from_utf8_lossy(&buffer[..n]).to_lowercase();
                         if input_str.contains("quit") {
                             let _ = quit_tx.send(());
-                            // Forward "quit" to child before killing
+                            // Forward "quit" to child, which shuts down on its own
                             let _ = child_stdin_arc.lock().unwrap().write_all(&buffer[..n]);
                             break;
                         }
@@ -107,10 +110,14 @@ fn main() {
         });
     }
 
-    // Main thread: wait for quit or child exit
+    // Main thread: wait for child exit, killing it if it does not quit in time
+    let mut quit_deadline = None;
     let status = loop {
-        // If quit signal received, kill child
-        if let Ok(_) = quit_rx.try_recv() {
+        if quit_rx.try_recv().is_ok() {
+            quit_deadline = Some(Instant::now() + QUIT_TIMEOUT);
+        }
+        if quit_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            eprintln!("Child did not quit in time, killing it");
             let mut child = child_arc.lock().unwrap();
             let _ = child.kill();
             let status = child.wait().expect("Failed to wait on child process");
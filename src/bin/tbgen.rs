@@ -0,0 +1,138 @@
+use checkm8::tablebase::{
+    MAX_PIECES, Material, Tablebase,
+    generate::{can_mate, generate_with_dependencies},
+};
+use shakmaty::Role;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Instant;
+
+/// Command line settings.
+struct Config {
+    output: String,
+    materials: Vec<Material>,
+}
+
+fn main() {
+    let config = parse_args();
+
+    if let Err(e) = fs::create_dir_all(&config.output) {
+        eprintln!(
+            "Failed to create output directory '{}': {}",
+            config.output, e
+        );
+        process::exit(1);
+    }
+
+    // Tables from earlier runs are reused instead of being generated again
+    let mut tablebase = match Tablebase::load(&config.output) {
+        Ok(tablebase) => tablebase,
+        Err(e) => {
+            eprintln!(
+                "Failed to load existing tables from '{}': {}",
+                config.output, e
+            );
+            process::exit(1);
+        }
+    };
+    if !tablebase.is_empty() {
+        eprintln!("Loaded {} existing tables", tablebase.len());
+    }
+
+    let start = Instant::now();
+    let mut generated = 0;
+    for material in &config.materials {
+        let mut table_start = Instant::now();
+        generate_with_dependencies(material, &mut tablebase, &mut |table| {
+            if let Err(e) = table.save(&config.output) {
+                eprintln!("Failed to save table {}: {}", table.material(), e);
+                process::exit(1);
+            }
+            eprintln!(
+                "Generated {} in {:.1}s",
+                table.material(),
+                table_start.elapsed().as_secs_f64()
+            );
+            generated += 1;
+            table_start = Instant::now();
+        });
+    }
+
+    eprintln!(
+        "\nGenerated {} tables in {:.1}s",
+        generated,
+        start.elapsed().as_secs_f64()
+    );
+}
+
+/// All materials with up to the maximum number of pieces which can end in checkmate.
+fn all_materials() -> Vec<Material> {
+    let roles = [
+        Role::Queen,
+        Role::Rook,
+        Role::Bishop,
+        Role::Knight,
+        Role::Pawn,
+    ];
+    let mut signatures = Vec::new();
+
+    for a in roles {
+        signatures.push(format!("K{}K", a.upper_char()));
+        for b in roles {
+            signatures.push(format!("K{}{}K", a.upper_char(), b.upper_char()));
+            signatures.push(format!("K{}K{}", a.upper_char(), b.upper_char()));
+        }
+    }
+
+    let mut materials: Vec<Material> = Vec::new();
+    for signature in signatures {
+        // Signatures are built from valid pieces, so parsing only fails for too many pieces
+        let Ok(material) = signature.parse::<Material>() else {
+            continue;
+        };
+        let material = material.canonical();
+        if material.count() <= MAX_PIECES && can_mate(&material) && !materials.contains(&material) {
+            materials.push(material);
+        }
+    }
+
+    // Smaller tables first, as larger ones depend on them
+    materials.sort_by_key(|material| material.count());
+    materials
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        output: "tablebases".into(),
+        materials: Vec::new(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => config.output = args.next().unwrap_or_else(|| usage()),
+            "--all" => config.materials.extend(all_materials()),
+            signature => match signature.parse() {
+                Ok(material) => config.materials.push(material),
+                Err(_) => usage(),
+            },
+        }
+    }
+
+    if config.materials.is_empty() {
+        usage();
+    }
+
+    config
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: tbgen [--output DIR] (--all | SIGNATURE...)");
+    eprintln!(
+        "Generates distance to mate tables for signatures such as KQKR with up to {} pieces, \
+         together with the smaller tables they depend on.",
+        MAX_PIECES
+    );
+    process::exit(2);
+}
@@ -0,0 +1,284 @@
+use checkm8::arena::{
+    engine::{Engine, EngineConfig},
+    game::{Adjudication, Settings, TimeControl, play},
+    load_openings,
+    tournament::{Format, Pairing, Standings, StateFile, schedule},
+};
+use shakmaty::Chess;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Command line settings.
+struct Config {
+    engines: Vec<EngineConfig>,
+    format: Format,
+    rounds: usize,
+    settings: Settings,
+    openings: Option<String>,
+    concurrency: usize,
+    pgn: Option<String>,
+    state: Option<String>,
+}
+
+/// Results and output files shared between all worker threads.
+struct Output {
+    standings: Standings,
+    state: Option<StateFile>,
+    finished: usize,
+}
+
+fn main() {
+    let config = parse_args();
+
+    let openings = match &config.openings {
+        Some(path) => match load_openings(path) {
+            Ok(openings) if !openings.is_empty() => openings,
+            Ok(_) => {
+                eprintln!("No openings found in '{}'", path);
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to load openings from '{}': {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => vec![Chess::default()],
+    };
+
+    let names: Vec<String> = config.engines.iter().map(|e| e.name.clone()).collect();
+    let games = schedule(
+        config.format,
+        config.engines.len(),
+        config.rounds,
+        openings.len(),
+    );
+    let mut output = Output {
+        standings: Standings::new(names.clone()),
+        state: None,
+        finished: 0,
+    };
+
+    // Games recorded in the state file are not played again
+    let mut pending: Vec<usize> = (0..games.len()).collect();
+    if let Some(path) = &config.state {
+        match StateFile::open(path, &names, games.len()) {
+            Ok((state, finished)) => {
+                for (&index, &outcome) in &finished {
+                    let pairing = games[index];
+                    output.standings.add(pairing.white, pairing.black, outcome);
+                }
+                if !finished.is_empty() {
+                    eprintln!("Resuming after {} finished games", finished.len());
+                }
+                pending.retain(|index| !finished.contains_key(index));
+                output.finished = finished.len();
+                output.state = Some(state);
+            }
+            Err(e) => {
+                eprintln!("Failed to open state file '{}': {}", path, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let output = Mutex::new(output);
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..config.concurrency {
+            scope.spawn(|| {
+                worker(&config, &games, &pending, &next, &openings, &output);
+            });
+        }
+    });
+
+    println!();
+    print!("{}", output.into_inner().unwrap().standings);
+}
+
+/// Plays pending games until none are left, keeping its own engine processes.
+fn worker(
+    config: &Config,
+    games: &[Pairing],
+    pending: &[usize],
+    next: &AtomicUsize,
+    openings: &[Chess],
+    output: &Mutex<Output>,
+) {
+    let mut engines: Vec<Option<Engine>> = config.engines.iter().map(|_| None).collect();
+
+    while let Some(&index) = pending.get(next.fetch_add(1, Ordering::Relaxed)) {
+        let pairing = games[index];
+
+        let mut white = take_engine(&mut engines, &config.engines, pairing.white);
+        let mut black = take_engine(&mut engines, &config.engines, pairing.black);
+        let mut played = play(
+            &mut white,
+            &mut black,
+            &openings[pairing.opening],
+            &config.settings,
+        );
+        played.game.set_tag("Event", "checkm8 tournament");
+        played.game.set_tag("Round", &(index + 1).to_string());
+        engines[pairing.white] = Some(white);
+        engines[pairing.black] = Some(black);
+
+        let mut output = output.lock().unwrap();
+        output
+            .standings
+            .add(pairing.white, pairing.black, played.outcome);
+        output.finished += 1;
+
+        println!(
+            "Game {} ({}/{}): {} - {} {} ({})",
+            index + 1,
+            output.finished,
+            games.len(),
+            config.engines[pairing.white].name,
+            config.engines[pairing.black].name,
+            played.game.result(),
+            played.reason
+        );
+
+        if let Some(path) = &config.pgn {
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", played.game));
+            if let Err(e) = written {
+                eprintln!("Failed to write game to '{}': {}", path, e);
+            }
+        }
+
+        // Games are recorded after the PGN, so that resumed tournaments never miss a game
+        if let Some(state) = &mut output.state
+            && let Err(e) = state.record(index, played.outcome)
+        {
+            eprintln!(
+                "Failed to record game {} in the state file: {}",
+                index + 1,
+                e
+            );
+        }
+    }
+}
+
+/// Takes the engine out of the worker's engines, starting it if it is not running.
+fn take_engine(engines: &mut [Option<Engine>], configs: &[EngineConfig], index: usize) -> Engine {
+    if let Some(mut engine) = engines[index].take()
+        && engine.is_running()
+    {
+        return engine;
+    }
+
+    match Engine::start(&configs[index]) {
+        Ok(engine) => engine,
+        Err(e) => {
+            eprintln!("Failed to start engine '{}': {}", configs[index].command, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        engines: Vec::new(),
+        format: Format::RoundRobin,
+        rounds: 1,
+        settings: Settings {
+            time_control: TimeControl::Clock {
+                base: Duration::from_secs(10),
+                increment: Duration::from_millis(100),
+            },
+            time_margin: Duration::from_millis(100),
+            adjudication: Adjudication::default(),
+        },
+        openings: None,
+        concurrency: 1,
+        pgn: None,
+        state: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut next = || args.next().unwrap_or_else(|| usage());
+        let adjudication = &mut config.settings.adjudication;
+
+        match arg.as_str() {
+            "--engine" => match next().parse() {
+                Ok(engine) => config.engines.push(engine),
+                Err(e) => {
+                    eprintln!("Invalid engine: {}", e);
+                    usage();
+                }
+            },
+            "--format" => config.format = parse_value(next()),
+            "--rounds" => config.rounds = parse_value(next()),
+            "--tc" => config.settings.time_control = parse_value(next()),
+            "--time-margin" => {
+                config.settings.time_margin = Duration::from_millis(parse_value(next()))
+            }
+            "--openings" => config.openings = Some(next()),
+            "--concurrency" => config.concurrency = parse_value(next()),
+            "--pgn" => config.pgn = Some(next()),
+            "--state" => config.state = Some(next()),
+            "--draw" => {
+                adjudication.draw_move_number = parse_value(next());
+                adjudication.draw_score = parse_value(next());
+                adjudication.draw_plies = parse_value(next());
+            }
+            "--resign" => {
+                adjudication.resign_score = parse_value(next());
+                adjudication.resign_plies = parse_value(next());
+            }
+            "--max-plies" => adjudication.max_plies = parse_value(next()),
+            _ => usage(),
+        }
+    }
+
+    // Engines are told apart by name in the output and the state file
+    let mut names: Vec<&String> = config.engines.iter().map(|e| &e.name).collect();
+    names.sort();
+    names.dedup();
+    if names.len() != config.engines.len() {
+        eprintln!("Engine names must be unique");
+        usage();
+    }
+
+    if config.engines.len() < 2 || config.rounds == 0 || config.concurrency == 0 {
+        usage();
+    }
+
+    config
+}
+
+fn parse_value<T: std::str::FromStr>(value: String) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: tournament --engine SPEC --engine SPEC... [OPTIONS]");
+    eprintln!();
+    eprintln!("Engine specifications look like 'cmd=./checkm8 name=new option.UseNNUE=true'.");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  --format FORMAT               roundrobin or gauntlet of the first engine");
+    eprintln!("  --rounds N                    Opening pairs played by every pairing (1)");
+    eprintln!("  --tc TC                       10+0.1, movetime=MS, nodes=N or depth=N");
+    eprintln!("  --time-margin MS              Time engines may exceed their clock by (100)");
+    eprintln!("  --openings FILE               PGN, FEN or EPD openings, one per round");
+    eprintln!("  --concurrency N               Games played at the same time (1)");
+    eprintln!("  --pgn FILE                    Append the games to a PGN file");
+    eprintln!("  --state FILE                  Record finished games to resume the tournament");
+    eprintln!("  --draw MOVE SCORE PLIES       Draw adjudication (40 10 8)");
+    eprintln!("  --resign SCORE PLIES          Resign adjudication (1000 6)");
+    eprintln!("  --max-plies N                 Draw games longer than this (400)");
+    eprintln!("Adjudication rules are disabled by giving zero plies.");
+    process::exit(2);
+}
@@ -0,0 +1,139 @@
+//! Building books from the moves played in games.
+//!
+//! Every move of a game's main line is counted with the result of the game, seen from the side
+//! playing it. Moves passing the filters of [`BuildSettings`] end up in the book, weighted by
+//! the half points they scored.
+
+use super::{Book, Entry, encode_move, key};
+use crate::pgn::Game;
+use shakmaty::{KnownOutcome, Outcome, Position};
+use std::{collections::HashMap, fmt};
+
+/// Which moves of the games make it into the book.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuildSettings {
+    // Moves are collected up to this ply
+    pub max_ply: usize,
+    // Moves played fewer times are left out
+    pub min_games: u32,
+    // Moves scoring less for the side playing them are left out (in percent)
+    pub min_score: u32,
+}
+
+impl Default for BuildSettings {
+    fn default() -> Self {
+        BuildSettings {
+            max_ply: 30,
+            min_games: 3,
+            min_score: 0,
+        }
+    }
+}
+
+/// Error of a game whose moves cannot be counted, as it has no result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownResult;
+
+impl fmt::Display for UnknownResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown result")
+    }
+}
+
+impl std::error::Error for UnknownResult {}
+
+/// Results of a move from the perspective of the side playing it.
+#[derive(Clone, Copy, Debug, Default)]
+struct MoveStats {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+impl MoveStats {
+    fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Half points scored, used as the book weight.
+    fn points(&self) -> u32 {
+        2 * self.wins + self.draws
+    }
+
+    /// Score in percent.
+    fn score(&self) -> u32 {
+        self.points() * 50 / self.games()
+    }
+}
+
+/// Collects move statistics from games and turns them into a book.
+pub struct BookBuilder {
+    settings: BuildSettings,
+    // Move statistics per position key
+    statistics: HashMap<u64, HashMap<u16, MoveStats>>,
+}
+
+impl BookBuilder {
+    pub fn new(settings: BuildSettings) -> Self {
+        BookBuilder {
+            settings,
+            statistics: HashMap::new(),
+        }
+    }
+
+    /// Records the result of every move of the main line up to the maximum ply.
+    pub fn add_game(&mut self, game: &Game) -> Result<(), UnknownResult> {
+        let Outcome::Known(outcome) = game.result() else {
+            return Err(UnknownResult);
+        };
+
+        let mut position = game.initial_position().clone();
+        for mv in game.mainline().take(self.settings.max_ply) {
+            let stats = self
+                .statistics
+                .entry(key(&position))
+                .or_default()
+                .entry(encode_move(mv))
+                .or_default();
+            match outcome {
+                KnownOutcome::Decisive { winner } if winner == position.turn() => stats.wins += 1,
+                KnownOutcome::Decisive { .. } => stats.losses += 1,
+                KnownOutcome::Draw => stats.draws += 1,
+            }
+
+            position.play_unchecked(mv);
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the moves passing the filters, weighted by the points they scored.
+    pub fn build(&self) -> Book {
+        let mut entries = Vec::new();
+
+        for (&key, moves) in &self.statistics {
+            let moves: Vec<(u16, u32)> = moves
+                .iter()
+                .filter(|(_, stats)| stats.games() >= self.settings.min_games)
+                .filter(|(_, stats)| stats.score() >= self.settings.min_score)
+                .map(|(&mv, stats)| (mv, stats.points()))
+                .filter(|&(_, points)| points > 0)
+                .collect();
+
+            // Weights of frequent positions are scaled down to fit, keeping their ratios
+            let max_points = moves.iter().map(|&(_, points)| points).max().unwrap_or(0);
+            let divisor = max_points.div_ceil(u16::MAX as u32).max(1);
+
+            for (mv, points) in moves {
+                entries.push(Entry {
+                    key,
+                    mv,
+                    weight: (points / divisor).max(1) as u16,
+                    learn: 0,
+                });
+            }
+        }
+
+        Book::from_entries(entries)
+    }
+}
@@ -0,0 +1,258 @@
+//! Polyglot opening books.
+//!
+//! A book file is a sequence of 16 byte big-endian entries, sorted by position key:
+//!
+//! | Field  | Type | Description                                  |
+//! |--------|------|----------------------------------------------|
+//! | Key    | u64  | Polyglot Zobrist hash of the position        |
+//! | Move   | u16  | Book move, see [`encode_move`]               |
+//! | Weight | u16  | Relative frequency or quality of the move    |
+//! | Learn  | u32  | Learning data, ignored                       |
+
+use rand::{Rng, RngExt};
+use shakmaty::{
+    Chess, EnPassantMode, File, Move, Position, Rank, Role, Square, zobrist::Zobrist64,
+};
+use std::{cmp::Reverse, fmt, fs, io, path::Path, str::FromStr};
+
+pub mod builder;
+
+/// Size of a single book entry in bytes.
+pub const ENTRY_SIZE: usize = 16;
+
+/// A single book entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl Entry {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut key = [0; 8];
+        key.copy_from_slice(&bytes[0..8]);
+
+        Entry {
+            key: u64::from_be_bytes(key),
+            mv: u16::from_be_bytes([bytes[8], bytes[9]]),
+            weight: u16::from_be_bytes([bytes[10], bytes[11]]),
+            learn: u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mv.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+/// How a move is picked among the book moves of a position.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Selection {
+    // Random move with probability proportional to its weight
+    #[default]
+    Weighted,
+    // Move with the highest weight
+    Best,
+}
+
+impl Selection {
+    /// Names used for the UCI option.
+    pub const NAMES: [&'static str; 2] = ["Weighted", "Best"];
+}
+
+impl FromStr for Selection {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "weighted" => Ok(Selection::Weighted),
+            "best" => Ok(Selection::Best),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selection::Weighted => write!(f, "Weighted"),
+            Selection::Best => write!(f, "Best"),
+        }
+    }
+}
+
+/// A move found in the book, together with its share of the total weight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookMove {
+    pub mv: Move,
+    pub weight: u16,
+    pub total_weight: u32,
+}
+
+/// An opening book loaded into memory.
+pub struct Book {
+    entries: Vec<Entry>,
+}
+
+impl Book {
+    /// Loads a book from a file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Book::from_bytes(&fs::read(path)?)
+    }
+
+    /// Parses a book from its file contents.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "book size is not a multiple of the entry size",
+            ));
+        }
+
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(Entry::from_bytes)
+            .collect();
+
+        Ok(Book::from_entries(entries))
+    }
+
+    /// Creates a book from its entries, in any order.
+    pub fn from_entries(mut entries: Vec<Entry>) -> Self {
+        // Lookups rely on the entries being sorted, which not every book writer guarantees.
+        // Polyglot tools also expect the moves of a position by decreasing weight.
+        entries.sort_by_key(|entry| (entry.key, Reverse(entry.weight)));
+
+        Book { entries }
+    }
+
+    /// Writes the book to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Serialises the book in the Polyglot format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map(|entry| entry.to_bytes())
+            .collect()
+    }
+
+    /// Number of entries in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Legal book moves of a position with their weights, skipping moves with zero weight.
+    pub fn moves(&self, position: &Chess) -> Vec<(Move, u16)> {
+        let key = key(position);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter(|entry| entry.weight > 0)
+            .filter_map(|entry| Some((decode_move(position, entry.mv)?, entry.weight)))
+            .collect()
+    }
+
+    /// Picks a book move for a position, if it is in the book. Weighted picks are drawn from
+    /// the generator, so that games can be reproduced from its seed.
+    pub fn select(
+        &self,
+        position: &Chess,
+        selection: Selection,
+        rng: &mut impl Rng,
+    ) -> Option<BookMove> {
+        let moves = self.moves(position);
+        let total_weight: u32 = moves.iter().map(|&(_, weight)| weight as u32).sum();
+
+        let &(mv, weight) = match selection {
+            Selection::Best => moves.iter().max_by_key(|&&(_, weight)| weight)?,
+            Selection::Weighted => {
+                if total_weight == 0 {
+                    return None;
+                }
+                let mut pick = rng.random_range(0..total_weight);
+                moves.iter().find(|&&(_, weight)| {
+                    let found = pick < weight as u32;
+                    pick = pick.saturating_sub(weight as u32);
+                    found
+                })?
+            }
+        };
+
+        Some(BookMove {
+            mv,
+            weight,
+            total_weight,
+        })
+    }
+}
+
+/// Polyglot key of a position.
+pub fn key(position: &Chess) -> u64 {
+    // Polyglot only hashes the en passant square if the capture is possible
+    let hash: Zobrist64 = position.zobrist_hash(EnPassantMode::Legal);
+    hash.0
+}
+
+/// Encodes a move in the Polyglot format.
+///
+/// Bits 0-5 hold the destination and bits 6-11 the origin square, both as file + 8 * rank.
+/// Bits 12-14 hold the promotion piece (1 = knight up to 4 = queen). Castling is encoded as
+/// the king capturing its own rook.
+pub fn encode_move(mv: Move) -> u16 {
+    let (from, to) = match mv {
+        Move::Castle { king, rook } => (king, rook),
+        // Drops do not exist in standard chess
+        _ => (mv.from().unwrap_or(mv.to()), mv.to()),
+    };
+    let promotion = match mv.promotion() {
+        Some(Role::Knight) => 1,
+        Some(Role::Bishop) => 2,
+        Some(Role::Rook) => 3,
+        Some(Role::Queen) => 4,
+        _ => 0,
+    };
+
+    (to as u16) | (from as u16) << 6 | promotion << 12
+}
+
+/// Decodes a Polyglot move, returning None if it is not legal in the position.
+pub fn decode_move(position: &Chess, raw: u16) -> Option<Move> {
+    let square = |bits: u16| {
+        Square::from_coords(
+            File::new((bits & 0x7) as u32),
+            Rank::new(((bits >> 3) & 0x7) as u32),
+        )
+    };
+    let to = square(raw);
+    let from = square(raw >> 6);
+    let promotion = match (raw >> 12) & 0x7 {
+        0 => None,
+        1 => Some(Role::Knight),
+        2 => Some(Role::Bishop),
+        3 => Some(Role::Rook),
+        4 => Some(Role::Queen),
+        _ => return None,
+    };
+
+    position.legal_moves().into_iter().find(|&mv| match mv {
+        // Castling moves are stored as the king capturing its own rook
+        Move::Castle { king, rook } => king == from && rook == to,
+        _ => mv.from() == Some(from) && mv.to() == to && mv.promotion() == promotion,
+    })
+}
@@ -0,0 +1,127 @@
+use shakmaty_uci::UciMessage;
+use std::{fmt, str::FromStr};
+
+/// A command received from the user interface.
+///
+/// Besides the standard UCI messages, a few non-standard debugging commands are understood.
+pub enum Command {
+    Uci(UciMessage),
+    // Print the evaluation breakdown of the current position
+    Eval,
+    // Count the leaf nodes below the current position, divided by move
+    Perft {
+        depth: u32,
+        bulk: bool,
+        hash_mb: usize,
+    },
+    // Search the benchmark positions to a fixed depth
+    Bench {
+        depth: Option<u8>,
+    },
+    // A line which is no known command, passed on only to be logged
+    Unknown(String),
+}
+
+// Hash table size of `perft ... hash` without a size (in megabytes)
+const DEFAULT_PERFT_HASH_MB: usize = 16;
+
+/// Error returned for input which is neither UCI nor a known custom command.
+#[derive(Debug)]
+pub struct ParseCommandError;
+
+impl FromStr for Command {
+    type Err = ParseCommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace();
+
+        match tokens.next() {
+            Some("eval") => Ok(Command::Eval),
+            Some("bench") => match tokens.next().map(str::parse) {
+                Some(Ok(depth)) => Ok(Command::Bench { depth: Some(depth) }),
+                Some(Err(_)) => Err(ParseCommandError),
+                None => Ok(Command::Bench { depth: None }),
+            },
+            // `go perft` is accepted as well, as other engines understand it
+            Some("perft") => parse_perft(tokens),
+            Some("go") if tokens.clone().next() == Some("perft") => parse_perft(tokens.skip(1)),
+            _ => s
+                .parse::<UciMessage>()
+                .map(Command::Uci)
+                .map_err(|_| ParseCommandError),
+        }
+    }
+}
+
+impl Command {
+    /// Parses a line of input, which is an unknown command unless blank or understood.
+    pub fn parse_line(line: &str) -> Option<Command> {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+        Some(
+            trimmed
+                .parse()
+                .unwrap_or_else(|_| Command::Unknown(trimmed.to_string())),
+        )
+    }
+}
+
+/// Parses the arguments of `perft <depth> [bulk] [hash [<mb>]]`.
+fn parse_perft<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<Command, ParseCommandError> {
+    let mut tokens = tokens.peekable();
+    let depth = tokens
+        .next()
+        .and_then(|depth| depth.parse().ok())
+        .ok_or(ParseCommandError)?;
+    let (mut bulk, mut hash_mb) = (false, 0);
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "bulk" => bulk = true,
+            "hash" => {
+                hash_mb = match tokens.peek().and_then(|size| size.parse().ok()) {
+                    Some(size) => {
+                        tokens.next();
+                        size
+                    }
+                    None => DEFAULT_PERFT_HASH_MB,
+                }
+            }
+            _ => return Err(ParseCommandError),
+        }
+    }
+
+    Ok(Command::Perft {
+        depth,
+        bulk,
+        hash_mb,
+    })
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Uci(msg) => write!(f, "{}", msg),
+            Command::Eval => write!(f, "eval"),
+            Command::Perft {
+                depth,
+                bulk,
+                hash_mb,
+            } => {
+                write!(f, "perft {}", depth)?;
+                if *bulk {
+                    write!(f, " bulk")?;
+                }
+                if *hash_mb > 0 {
+                    write!(f, " hash {}", hash_mb)?;
+                }
+                Ok(())
+            }
+            Command::Bench { depth: None } => write!(f, "bench"),
+            Command::Bench { depth: Some(depth) } => write!(f, "bench {}", depth),
+            Command::Unknown(line) => write!(f, "{}", line),
+        }
+    }
+}
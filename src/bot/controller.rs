@@ -1,66 +1,300 @@
-use crate::{SearchCommand, SearchControl, SearchInfo, SEARCH_TIME_MS};
+use crate::{
+    DEFAULT_SEED, SEARCH_TIME_MS, Score, SearchCommand, SearchControl, SearchInfo, bench,
+    book::Book,
+    bot::{
+        command::Command,
+        options::Options,
+        output::{Output, WriteOutput},
+    },
+    engine::{PositionError, setup_position},
+    logging::{Kind, Level, Logger},
+    perft::Perft,
+    scoring::{
+        Evaluator,
+        nnue::{Accumulator, Network},
+        trace::EvalTrace,
+    },
+    search::mate_distance,
+    session::{Direction, Recorder},
+    tablebase::Tablebase,
+};
 use chrono::Local;
 use crossbeam_channel::{select, Receiver, Sender};
-use shakmaty::{CastlingMode, Chess, Position};
+use rand::{SeedableRng, rngs::Xoshiro256PlusPlus};
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Position, fen::Fen};
 use shakmaty_uci::{UciInfo, UciInfoScore, UciMessage, UciMove, UciSearchControl};
-use std::{fs::OpenOptions, io::Write};
+use std::{
+    fmt,
+    io::Stdout,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+/// Error of a command which the controller could not carry out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControllerError {
+    // A position command which cannot be set up
+    Position(PositionError),
+    // The search thread ended while the engine was running
+    SearchStopped,
+}
+
+impl From<PositionError> for ControllerError {
+    fn from(err: PositionError) -> Self {
+        ControllerError::Position(err)
+    }
+}
+
+impl fmt::Display for ControllerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControllerError::Position(err) => err.fmt(f),
+            ControllerError::SearchStopped => write!(f, "search thread stopped"),
+        }
+    }
+}
+
+impl std::error::Error for ControllerError {}
 
 /// Handles incoming commands, sends outgoing messages and produces runtime logs.
-pub struct Controller {
-    input_rx: Receiver<UciMessage>,
+///
+/// Messages go to stdout unless the controller is created with another output.
+pub struct Controller<O = WriteOutput<Stdout>> {
+    input_rx: Receiver<Command>,
     cmd_tx: Sender<SearchCommand>,
     info_rx: Receiver<SearchInfo>,
+    // Abort flag of the searcher, see `Searcher::abort_flag`
+    abort: Arc<AtomicBool>,
+    // Start of the search which has not sent its best move yet, if any
+    search_start: Option<Instant>,
     position: Chess,
-    log_file: &'static str,
+    options: Options,
+    evaluator: Evaluator,
+    book: Option<Book>,
+    // Picks weighted book moves, seeded like the searcher
+    book_rng: Xoshiro256PlusPlus,
+    logger: Logger,
+    output: O,
+    // Transcript of the session, if it is recorded
+    recorder: Option<Recorder>,
 }
 
 impl Controller {
     pub fn new(
-        input_rx: Receiver<UciMessage>,
+        input_rx: Receiver<Command>,
+        cmd_tx: Sender<SearchCommand>,
+        info_rx: Receiver<SearchInfo>,
+        abort: Arc<AtomicBool>,
+        logger: Logger,
+    ) -> Self {
+        Controller::with_output(
+            input_rx,
+            cmd_tx,
+            info_rx,
+            abort,
+            logger,
+            WriteOutput::default(),
+        )
+    }
+}
+
+impl<O: Output> Controller<O> {
+    /// Creates a controller sending its messages to the given output.
+    pub fn with_output(
+        input_rx: Receiver<Command>,
         cmd_tx: Sender<SearchCommand>,
         info_rx: Receiver<SearchInfo>,
-        log_file: &'static str,
+        abort: Arc<AtomicBool>,
+        logger: Logger,
+        output: O,
     ) -> Self {
         let controller = Controller {
             input_rx,
             cmd_tx,
             info_rx,
+            abort,
+            search_start: None,
             position: Chess::default(),
-            log_file,
+            options: Options::default(),
+            evaluator: Evaluator::default(),
+            book: None,
+            book_rng: Xoshiro256PlusPlus::seed_from_u64(DEFAULT_SEED),
+            logger,
+            output,
+            recorder: None,
         };
 
         let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
-        controller.log("");
-        controller.log(&format!("------ Engine started at {} ------", timestamp));
+        controller.log(
+            Level::Info,
+            &format!("------ Engine started at {} ------", timestamp),
+        );
 
         controller
     }
 
-    /// Runs the controller.
-    pub fn run(mut self) {
-        loop {
+    /// Records every line sent in the session transcript.
+    pub fn with_recorder(mut self, recorder: Option<Recorder>) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    /// Runs the controller until `quit` or the end of the input, then tells the searcher to
+    /// abort its search and quit.
+    pub fn run(&mut self) -> Result<(), ControllerError> {
+        let result = loop {
             select! {
-                recv(self.input_rx) -> cmd => {
-                    let cmd = cmd.unwrap();
-                    self.log(&format!(" IN: '{}'", &cmd));
-                    if self.handle_input(cmd) {
-                        break;
+                recv(self.input_rx) -> cmd => match cmd {
+                    Ok(cmd) => {
+                        self.logger.log(Level::Info, Kind::In, &cmd.to_string());
+                        match self.handle_input(cmd) {
+                            Ok(true) => break Ok(()),
+                            Ok(false) => (),
+                            Err(err) => break Err(self.report_stopped(err)),
+                        }
                     }
-                }
+                    // The input ended without quit, as scripts do, so their last search is finished
+                    Err(_) => {
+                        self.log(Level::Info, "Input closed");
+                        self.finish_search();
+                        break Ok(());
+                    }
+                },
+
+                recv(self.info_rx) -> info => match info {
+                    Ok(info) => self.handle_info(info),
+                    // Without a searcher no command can be answered anymore
+                    Err(_) => break Err(self.report_stopped(ControllerError::SearchStopped)),
+                },
+            }
+        };
 
-                recv(self.info_rx) -> info => self.handle_info(info.unwrap()),
+        // The searcher may be gone already, in which case there is nothing to stop
+        self.abort.store(true, Ordering::Relaxed);
+        let _ = self.cmd_tx.send(SearchCommand::Quit);
+        result
+    }
+
+    /// Waits for the best move of the running search, if any
+    fn finish_search(&mut self) {
+        while self.search_start.is_some() {
+            match self.info_rx.recv() {
+                Ok(info) => self.handle_info(info),
+                Err(_) => break,
             }
         }
     }
 
+    /// Tells the user interface and the log about an error which ends the session
+    fn report_stopped(&mut self, err: ControllerError) -> ControllerError {
+        self.log(Level::Error, &err.to_string());
+        self.send_info_string(format!("Error: {}", err));
+        err
+    }
+
+    /// Passes a command to the searcher, which fails once the search thread is gone
+    fn command(&self, command: SearchCommand) -> Result<(), ControllerError> {
+        self.cmd_tx
+            .send(command)
+            .map_err(|_| ControllerError::SearchStopped)
+    }
+
     /// Sends an outbound message
-    fn send(&self, msg: UciMessage) {
-        println!("{msg}");
-        self.log(&format!("OUT: '{}'", msg));
+    fn send(&mut self, msg: UciMessage) {
+        self.send_line(&msg.to_string());
     }
 
-    /// Handles incoming commands from user interface
-    fn handle_input(&mut self, message: UciMessage) -> bool {
+    /// Sends a raw line of output, used for non-standard commands
+    fn send_line(&mut self, line: &str) {
+        self.output.send_line(line);
+        self.logger.log(Level::Info, Kind::Out, line);
+        if let Some(recorder) = &self.recorder {
+            recorder.record(Direction::Out, line);
+        }
+    }
+
+    /// Sends a free-form message to the user interface
+    fn send_info_string(&mut self, text: String) {
+        self.send(UciMessage::Info(UciInfo {
+            string: Some(text),
+            ..Default::default()
+        }));
+    }
+
+    /// Handles incoming commands from user interface, returning whether to quit
+    fn handle_input(&mut self, command: Command) -> Result<bool, ControllerError> {
+        match command {
+            Command::Uci(message) => self.handle_uci(message),
+
+            // Print evaluation breakdown of the current position
+            Command::Eval => {
+                let trace = EvalTrace::new(&self.position);
+                for line in trace.to_string().lines() {
+                    self.send_line(line);
+                }
+
+                if let Evaluator::Nnue(network) = &self.evaluator {
+                    let acc = Accumulator::new(&self.position, network);
+                    let score = network.evaluate(&acc, self.position.turn());
+                    self.send_line(&format!("NNUE evaluation: {} cp (side to move)", score));
+                }
+                Ok(false)
+            }
+
+            // Count leaf nodes below the current position
+            Command::Perft {
+                depth,
+                bulk,
+                hash_mb,
+            } => {
+                self.perft(depth, bulk, hash_mb);
+                Ok(false)
+            }
+
+            // Search the benchmark positions with the current evaluation
+            Command::Bench { depth } => {
+                let depth = depth.unwrap_or(bench::DEFAULT_DEPTH);
+                let result = bench::run(depth, self.evaluator.clone());
+                for line in result.to_string().lines() {
+                    self.send_line(line);
+                }
+                Ok(false)
+            }
+
+            // Unknown commands are ignored, as the UCI protocol asks
+            Command::Unknown(line) => {
+                self.log(Level::Warn, &format!("Unknown command '{}'", line));
+                Ok(false)
+            }
+        }
+    }
+
+    /// Prints the leaf node count below every move of the current position, and their total
+    fn perft(&mut self, depth: u32, bulk: bool, hash_mb: usize) {
+        let start = Instant::now();
+        let divide = Perft::new(bulk, hash_mb).divide(&self.position, depth);
+
+        for (mv, nodes) in &divide {
+            let mv = UciMove::from_move(*mv, CastlingMode::Standard);
+            self.send_line(&format!("{}: {}", mv, nodes));
+        }
+
+        let nodes: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+        let elapsed = start.elapsed();
+        self.send_line("");
+        self.send_line(&format!("Nodes searched: {}", nodes));
+        self.send_line(&format!(
+            "Time: {} ms ({} nps)",
+            elapsed.as_millis(),
+            (nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64
+        ));
+    }
+
+    /// Handles incoming UCI messages from user interface
+    fn handle_uci(&mut self, message: UciMessage) -> Result<bool, ControllerError> {
         match message {
             // Uci handshake
             UciMessage::Uci => {
@@ -72,112 +306,428 @@ impl Controller {
                     name: None,
                     author: Some(env!("CARGO_PKG_AUTHORS").into()),
                 });
+                for option in Options::declarations() {
+                    self.send(UciMessage::Option(option));
+                }
                 self.send(UciMessage::UciOk);
             }
             UciMessage::IsReady => self.send(UciMessage::ReadyOk),
 
+            // Change engine settings
+            UciMessage::SetOption { name, value } => self.set_option(&name, value)?,
+
             // Reset
             UciMessage::UciNewGame => {
                 self.position = Chess::default();
             }
 
-            // Set a position
-            UciMessage::Position { fen, moves, .. } => {
-                let mut position = if let Some(fen) = fen {
-                    fen.into_position(CastlingMode::Standard).unwrap()
-                } else {
-                    Chess::default()
-                };
+            // Set a position, keeping the previous one if it is invalid
+            UciMessage::Position { fen, moves, .. } => match setup_position(fen.as_ref(), &moves) {
+                Ok(position) => self.position = position,
+                Err(err) => self.report_error(&err.into()),
+            },
 
-                for mv in moves {
-                    let m = mv.to_move(&position).unwrap();
-                    position = position.play(m).unwrap();
-                }
-                self.position = position;
+            // Start a search, unless the book has a move
+            UciMessage::Go { search_control, .. } => self.go(search_control)?,
+
+            // Stop current search, which still answers with its best move
+            UciMessage::Stop => {
+                self.abort.store(true, Ordering::Relaxed);
+                self.command(SearchCommand::Stop)?;
             }
 
+            // Terminate bot
+            UciMessage::Quit => return Ok(true),
+
+            _ => (), // Other commands are not handled here.
+        }
+        Ok(false)
+    }
+
+    /// Answers a `go` command from the book, or starts a search
+    fn go(&mut self, search_control: Option<UciSearchControl>) -> Result<(), ControllerError> {
+        if self.play_game_over() || self.play_book_move() {
+            return Ok(());
+        }
+
+        let control = match search_control {
             // Search to fixed depth
-            UciMessage::Go {
-                search_control:
-                    Some(UciSearchControl {
-                        depth: Some(depth), ..
-                    }),
-                ..
-            } => self
-                .cmd_tx
-                .send(SearchCommand::Start {
-                    position: self.position.clone(),
-                    control: SearchControl::ToDepth(depth),
-                })
-                .unwrap(),
+            Some(UciSearchControl {
+                depth: Some(depth), ..
+            }) => SearchControl::ToDepth(depth),
+
+            // Search a fixed number of nodes
+            Some(UciSearchControl {
+                nodes: Some(nodes), ..
+            }) => SearchControl::Nodes(nodes),
 
             // Any other search command will search for a fixed amount of time
-            UciMessage::Go { .. } => self
-                .cmd_tx
-                .send(SearchCommand::Start {
-                    position: self.position.clone(),
-                    control: SearchControl::TimeLimit(SEARCH_TIME_MS),
-                })
-                .unwrap(),
+            _ => SearchControl::TimeLimit(SEARCH_TIME_MS),
+        };
 
-            // Stop current search
-            UciMessage::Stop => self.cmd_tx.send(SearchCommand::Stop).unwrap(),
+        let fen = Fen::from_position(&self.position, EnPassantMode::Legal);
+        let limit = match control {
+            SearchControl::ToDepth(depth) => format!("depth {}", depth),
+            SearchControl::TimeLimit(time) => format!("movetime {}", time),
+            SearchControl::Nodes(nodes) => format!("nodes {}", nodes),
+        };
+        self.logger.log(
+            Level::Info,
+            Kind::Search,
+            &format!("started with {} on {}", limit, fen),
+        );
 
-            // Terminate bot
-            UciMessage::Quit => return true,
+        // A stop sent before this search must not end it
+        self.abort.store(false, Ordering::Relaxed);
+        self.search_start = Some(Instant::now());
+        self.command(SearchCommand::Start {
+            position: self.position.clone(),
+            control,
+        })
+    }
 
-            _ => (), // Other commands are not handled here.
+    /// Answers with the null move if the game is over, returning whether it was
+    fn play_game_over(&mut self) -> bool {
+        if !self.position.legal_moves().is_empty() {
+            return false;
+        }
+
+        // Checkmated positions score as mate in 0, stalemates as a draw
+        let score = if self.position.is_check() {
+            UciInfoScore {
+                mate: Some(0),
+                ..Default::default()
+            }
+        } else {
+            UciInfoScore {
+                cp: Some(0),
+                ..Default::default()
+            }
+        };
+        self.send(UciMessage::Info(UciInfo {
+            depth: Some(0),
+            score: Some(score),
+            ..Default::default()
+        }));
+        self.send(UciMessage::BestMove {
+            best_move: UciMove::Null,
+            ponder: None,
+        });
+        true
+    }
+
+    /// Plays a move from the book if the current position is in it, returning whether it did
+    fn play_book_move(&mut self) -> bool {
+        let Some(book) = &self.book else {
+            return false;
+        };
+        if self.position.fullmoves().get() > self.options.book_depth {
+            return false;
+        }
+        let Some(book_move) = book.select(
+            &self.position,
+            self.options.book_selection,
+            &mut self.book_rng,
+        ) else {
+            return false;
+        };
+
+        let best_move = UciMove::from_move(book_move.mv, CastlingMode::Standard);
+        self.send_info_string(format!(
+            "Book move {} (weight {} of {})",
+            best_move, book_move.weight, book_move.total_weight
+        ));
+        self.send(UciMessage::BestMove {
+            best_move,
+            ponder: None,
+        });
+        true
+    }
+
+    /// Tells the user interface and the log about a command which failed
+    fn report_error(&mut self, err: &ControllerError) {
+        self.log(Level::Error, &err.to_string());
+        self.send_info_string(format!("Error: {}, command ignored", err));
+    }
+
+    /// Applies a `setoption` command
+    fn set_option(&mut self, name: &str, value: Option<String>) -> Result<(), ControllerError> {
+        let value = value.unwrap_or_default();
+
+        // Option names are case insensitive
+        match name.to_ascii_lowercase().as_str() {
+            "usennue" => {
+                self.options.use_nnue = value.eq_ignore_ascii_case("true");
+                self.update_evaluator()?;
+            }
+            "evalfile" => {
+                self.options.eval_file = value;
+                self.update_evaluator()?;
+            }
+            "ownbook" => {
+                self.options.own_book = value.eq_ignore_ascii_case("true");
+                self.update_book();
+            }
+            "bookfile" => {
+                self.options.book_file = value;
+                self.update_book();
+            }
+            "bookdepth" => match Options::parse_book_depth(&value) {
+                Some(depth) => self.options.book_depth = depth,
+                None => self.log(Level::Warn, &format!("Invalid BookDepth '{}'", value)),
+            },
+            "bookselection" => match value.parse() {
+                Ok(selection) => self.options.book_selection = selection,
+                Err(()) => self.log(Level::Warn, &format!("Invalid BookSelection '{}'", value)),
+            },
+            "seed" => match Options::parse_seed(&value) {
+                Some(seed) => {
+                    self.options.seed = seed;
+                    self.book_rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+                    self.command(SearchCommand::SetSeed(seed))?;
+                }
+                None => self.log(Level::Warn, &format!("Invalid Seed '{}'", value)),
+            },
+            "randomness" => match Options::parse_randomness(&value) {
+                Some(randomness) => {
+                    self.options.randomness = randomness;
+                    self.command(SearchCommand::SetRandomness(randomness as i32))?;
+                }
+                None => self.log(Level::Warn, &format!("Invalid Randomness '{}'", value)),
+            },
+            "logfile" => {
+                self.logger.set_path(&value);
+                self.options.log_file = value;
+            }
+            "loglevel" => match Level::parse_filter(&value) {
+                Ok(level) => {
+                    self.logger.set_level(level);
+                    self.options.log_level = level;
+                }
+                Err(_) => self.log(Level::Warn, &format!("Invalid LogLevel '{}'", value)),
+            },
+            "logjson" => {
+                self.options.log_json = value.eq_ignore_ascii_case("true");
+                self.logger.set_json(self.options.log_json);
+            }
+            "tablebasepath" => {
+                self.options.tablebase_path = value;
+                self.update_tablebase()?;
+            }
+            _ => self.log(Level::Warn, &format!("Unknown option '{}'", name)),
         }
-        false
+        Ok(())
+    }
+
+    /// Selects the evaluation backend from the current options and passes it to the searcher
+    fn update_evaluator(&mut self) -> Result<(), ControllerError> {
+        self.evaluator = if self.options.use_nnue {
+            match Network::load(&self.options.eval_file) {
+                Ok(network) => {
+                    self.send_info_string(format!(
+                        "NNUE evaluation using {} ({} hidden neurons)",
+                        self.options.eval_file,
+                        network.hidden_size()
+                    ));
+                    Evaluator::Nnue(Arc::new(network))
+                }
+                Err(err) => {
+                    self.send_info_string(format!(
+                        "Failed to load network {}: {}, using classical evaluation",
+                        self.options.eval_file, err
+                    ));
+                    Evaluator::Classical
+                }
+            }
+        } else {
+            Evaluator::Classical
+        };
+
+        self.command(SearchCommand::SetEvaluator(self.evaluator.clone()))
+    }
+
+    /// Loads the opening book from the current options
+    fn update_book(&mut self) {
+        if !self.options.own_book {
+            self.book = None;
+            return;
+        }
+
+        self.book = match Book::load(&self.options.book_file) {
+            Ok(book) => {
+                self.send_info_string(format!(
+                    "Loaded book {} ({} entries)",
+                    self.options.book_file,
+                    book.len()
+                ));
+                Some(book)
+            }
+            Err(err) => {
+                self.send_info_string(format!(
+                    "Failed to load book {}: {}",
+                    self.options.book_file, err
+                ));
+                None
+            }
+        };
+    }
+
+    /// Loads the tablebase from the current options and passes it to the searcher
+    fn update_tablebase(&mut self) -> Result<(), ControllerError> {
+        let path = self.options.tablebase_path.trim();
+
+        let tablebase = if path.is_empty() || path == "<empty>" {
+            None
+        } else {
+            match Tablebase::load(path) {
+                Ok(tablebase) => {
+                    self.send_info_string(format!(
+                        "Loaded {} tablebases from {}",
+                        tablebase.len(),
+                        path
+                    ));
+                    Some(Arc::new(tablebase))
+                }
+                Err(err) => {
+                    self.send_info_string(format!(
+                        "Failed to load tablebases from {}: {}",
+                        path, err
+                    ));
+                    None
+                }
+            }
+        };
+
+        self.command(SearchCommand::SetTablebase(tablebase))
     }
 
     fn handle_info(&mut self, message: SearchInfo) {
         match message {
             // Emit best move to user interface
-            SearchInfo::BestMove(mv) => self.send(UciMessage::BestMove {
-                best_move: UciMove::from_move(mv, CastlingMode::Standard),
-                ponder: None,
-            }),
+            SearchInfo::BestMove(mv) => {
+                let best_move = UciMove::from_move(mv, CastlingMode::Standard);
+                self.end_search(&format!("best move {}", best_move));
+                self.send(UciMessage::BestMove {
+                    best_move,
+                    ponder: None,
+                })
+            }
+
+            // Answer with any legal move, so that the user interface is not left waiting
+            SearchInfo::Error(reason) => {
+                self.end_search("failed");
+                self.log(Level::Error, &reason);
+                self.send_info_string(format!("Error: {}", reason));
+
+                let best_move = match self.position.legal_moves().first() {
+                    Some(&mv) => UciMove::from_move(mv, CastlingMode::Standard),
+                    None => UciMove::Null,
+                };
+                self.send(UciMessage::BestMove {
+                    best_move,
+                    ponder: None,
+                });
+            }
 
             // Emit info to user interface
             SearchInfo::Info {
                 depth,
+                seldepth,
                 pv,
                 score,
                 nodes,
+                time,
+                nps,
+                tbhits,
             } => {
+                self.logger.log(
+                    Level::Debug,
+                    Kind::Search,
+                    &format!(
+                        "depth {} seldepth {} score {} nodes {} nps {}",
+                        depth, seldepth, score, nodes, nps
+                    ),
+                );
+                // The search keeps no hash table, so there is no hashfull to report
                 let info_msg = UciMessage::Info(UciInfo {
                     depth: Some(depth),
-                    score: Some(UciInfoScore {
-                        cp: Some(score),
-                        ..Default::default()
-                    }),
+                    seldepth: Some(seldepth),
+                    time: Some(time),
+                    score: Some(uci_score(score, depth)),
                     pv: pv
                         .into_iter()
                         .map(|mv| UciMove::from_move(mv, CastlingMode::Standard))
                         .collect(),
                     nodes: Some(nodes),
+                    nps: Some(nps),
+                    // Only searches with tablebases report hits
+                    tbhits: (tbhits > 0).then_some(tbhits),
 
                     ..Default::default()
                 });
 
                 self.send(info_msg);
             }
+
+            // Emit the progress of the running search
+            SearchInfo::Progress {
+                depth,
+                currmove,
+                currmovenumber,
+                nodes,
+                time,
+                nps,
+                tbhits,
+            } => {
+                self.send(UciMessage::Info(UciInfo {
+                    depth: Some(depth),
+                    time: Some(time),
+                    nodes: Some(nodes),
+                    currmove: Some(UciMove::from_move(currmove, CastlingMode::Standard)),
+                    currmovenumber: Some(currmovenumber),
+                    nps: Some(nps),
+                    tbhits: (tbhits > 0).then_some(tbhits),
+
+                    ..Default::default()
+                }));
+            }
         }
     }
 
-    fn log(&self, line: &str) {
-        let mut log_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(self.log_file)
-            .unwrap();
-        writeln!(&mut log_file, "{}", line).unwrap()
+    /// Logs the end of the running search
+    fn end_search(&mut self, result: &str) {
+        let elapsed = self
+            .search_start
+            .take()
+            .map_or(0, |start| start.elapsed().as_millis());
+        let message = format!("{} after {} ms", result, elapsed);
+        self.logger.log(Level::Info, Kind::Search, &message);
     }
 }
 
-impl Drop for Controller {
+impl<O> Controller<O> {
+    /// Logs an engine message
+    pub(crate) fn log(&self, level: Level, message: &str) {
+        self.logger.log(level, Kind::Engine, message);
+    }
+}
+
+impl<O> Drop for Controller<O> {
     fn drop(&mut self) {
-        self.log("------ Engine closed ------");
+        self.log(Level::Info, "------ Engine closed ------");
+    }
+}
+
+/// Reports found checkmates as mate in moves and every other score in centipawns.
+fn uci_score(score: Score, depth: u8) -> UciInfoScore {
+    match mate_distance(score, depth) {
+        Some(mate) => UciInfoScore {
+            mate: Some(mate),
+            ..Default::default()
+        },
+        None => UciInfoScore {
+            cp: Some(score),
+            ..Default::default()
+        },
     }
 }
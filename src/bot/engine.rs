@@ -0,0 +1,96 @@
+use crate::{
+    bot::{controller::Controller, input::InputListener},
+    logging::{Level, LogConfig, Logger},
+    search::Searcher,
+    session::Recorder,
+};
+use crossbeam_channel::unbounded;
+use std::{
+    io::{self, BufRead, Write},
+    process::ExitCode,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How long the searcher gets to finish its search and quit after the engine stops.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The UCI engine, owning the threads which listen for input and search, with the controller
+/// running on the thread calling `run`.
+pub struct UciEngine {
+    controller: Controller,
+    input: JoinHandle<()>,
+    searcher: JoinHandle<()>,
+}
+
+impl UciEngine {
+    /// Starts the input and search threads, reading commands from the input and logging as
+    /// configured, and recording the session if a recorder is given.
+    pub fn new(
+        log: LogConfig,
+        input: impl BufRead + Send + 'static,
+        recorder: Option<Recorder>,
+    ) -> Self {
+        // Initialize channels
+        let (input_tx, input_rx) = unbounded();
+        let (cmd_tx, cmd_rx) = unbounded();
+        let (info_tx, info_rx) = unbounded();
+
+        let searcher = Searcher::new(cmd_rx, info_tx);
+        let abort = searcher.abort_flag();
+        let listener = InputListener::new(input_tx, input).with_recorder(recorder.clone());
+
+        UciEngine {
+            controller: Controller::new(input_rx, cmd_tx, info_rx, abort, Logger::new(log))
+                .with_recorder(recorder),
+            input: thread::spawn(|| listener.run()),
+            searcher: thread::spawn(|| searcher.run()),
+        }
+    }
+
+    /// Runs the engine until `quit` or the end of the input, and shuts it down.
+    ///
+    /// Fails if the searcher stopped on its own, panicked, or did not quit in time.
+    pub fn run(mut self) -> ExitCode {
+        let mut status = match self.controller.run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(_) => ExitCode::FAILURE,
+        };
+
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        match join(self.searcher, deadline) {
+            Some(Ok(())) => (),
+            Some(Err(_)) => {
+                self.controller.log(Level::Error, "search thread panicked");
+                status = ExitCode::FAILURE;
+            }
+            None => {
+                self.controller
+                    .log(Level::Error, "search thread did not quit in time");
+                status = ExitCode::FAILURE;
+            }
+        }
+
+        // Reading stdin cannot be interrupted, so the listener usually ends with the process
+        if self.input.is_finished() && self.input.join().is_err() {
+            self.controller.log(Level::Error, "input thread panicked");
+            status = ExitCode::FAILURE;
+        }
+
+        // The log is written out when the controller drops its logger
+        drop(self.controller);
+        let _ = io::stdout().flush();
+        status
+    }
+}
+
+/// Joins a thread unless it is still running at the deadline.
+fn join(handle: JoinHandle<()>, deadline: Instant) -> Option<thread::Result<()>> {
+    while !handle.is_finished() {
+        if Instant::now() >= deadline {
+            return None;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    Some(handle.join())
+}
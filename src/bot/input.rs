@@ -1,32 +1,48 @@
+use crate::{
+    bot::command::Command,
+    session::{Direction, Recorder},
+};
 use crossbeam_channel::Sender;
-use shakmaty_uci::UciMessage;
-use std::io::{self, BufRead};
+use std::io::BufRead;
 
-/// Listens for UCI commands on stdin and forwards them to the input channel.
-pub struct InputListener {
-    input_tx: Sender<UciMessage>,
+/// Listens for UCI commands on a reader, usually stdin, and forwards them to the input channel.
+pub struct InputListener<R> {
+    input_tx: Sender<Command>,
+    reader: R,
+    recorder: Option<Recorder>,
 }
 
-impl InputListener {
-    pub fn new(input_tx: Sender<UciMessage>) -> Self {
-        Self { input_tx }
+impl<R: BufRead> InputListener<R> {
+    pub fn new(input_tx: Sender<Command>, reader: R) -> Self {
+        Self {
+            input_tx,
+            reader,
+            recorder: None,
+        }
     }
 
-    pub fn run(self) {
-        let stdin = io::stdin();
+    /// Records every line read in the session transcript.
+    pub fn with_recorder(mut self, recorder: Option<Recorder>) -> Self {
+        self.recorder = recorder;
+        self
+    }
 
-        // Listen while stdin is open
-        for line_result in stdin.lock().lines() {
+    pub fn run(self) {
+        // Listen while the reader is open
+        for line_result in self.reader.lines() {
             let line = if let Ok(l) = line_result { l } else { continue };
 
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
+            // Invalid input is passed on as well, for the controller to log
+            let Some(command) = Command::parse_line(&line) else {
                 continue;
+            };
+            if let Some(recorder) = &self.recorder {
+                recorder.record(Direction::In, line.trim());
             }
 
-            // Try to parse as UciMessage. Ignore invalid input.
-            if let Ok(msg) = trimmed.parse::<UciMessage>() {
-                self.input_tx.send(msg).unwrap();
+            // Stop listening once the controller is gone
+            if self.input_tx.send(command).is_err() {
+                break;
             }
         }
     }
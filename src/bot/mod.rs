@@ -1,2 +1,6 @@
+pub mod command;
 pub mod controller;
+pub mod engine;
 pub mod input;
+pub mod options;
+pub mod output;
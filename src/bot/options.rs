@@ -0,0 +1,164 @@
+use crate::{
+    DEFAULT_SEED,
+    book::Selection,
+    logging::{Level, LogConfig},
+};
+use shakmaty_uci::UciOptionConfig;
+
+// Network file loaded when NNUE evaluation is enabled
+const DEFAULT_EVAL_FILE: &str = "checkm8.nnue";
+
+// Polyglot book loaded when the own book is enabled
+const DEFAULT_BOOK_FILE: &str = "book.bin";
+
+// Book moves are played up to this move number
+const DEFAULT_BOOK_DEPTH: u32 = 20;
+const MAX_BOOK_DEPTH: u32 = 200;
+
+// Largest seed and softmax temperature (in centipawns) accepted by the user interface
+const MAX_SEED: u64 = i32::MAX as u64;
+const MAX_RANDOMNESS: u32 = 500;
+
+/// Engine settings that can be changed by the user interface.
+pub struct Options {
+    // Use the neural network instead of the handcrafted evaluation
+    pub use_nnue: bool,
+    // Path of the network file
+    pub eval_file: String,
+    // Directory of tablebase files, empty to disable probing
+    pub tablebase_path: String,
+    // Answer from the opening book while the position is in it
+    pub own_book: bool,
+    // Path of the Polyglot book file
+    pub book_file: String,
+    // Last move number answered from the book
+    pub book_depth: u32,
+    // How a move is picked among the book moves
+    pub book_selection: Selection,
+    // Seed of the searcher's random number generator
+    pub seed: u64,
+    // Softmax temperature of the root move selection, 0 for deterministic play
+    pub randomness: u32,
+    // Path of the log file, empty to disable the log
+    pub log_file: String,
+    // Least important level logged, `None` to log nothing
+    pub log_level: Option<Level>,
+    // Write the log as JSON lines
+    pub log_json: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        let log = LogConfig::default();
+        Options {
+            use_nnue: false,
+            eval_file: DEFAULT_EVAL_FILE.into(),
+            tablebase_path: String::new(),
+            own_book: false,
+            book_file: DEFAULT_BOOK_FILE.into(),
+            book_depth: DEFAULT_BOOK_DEPTH,
+            book_selection: Selection::default(),
+            seed: DEFAULT_SEED,
+            randomness: 0,
+            log_file: log.path,
+            log_level: log.level,
+            log_json: log.json,
+        }
+    }
+}
+
+impl Options {
+    /// Parses the value of the BookDepth option, clamped to the allowed range.
+    pub fn parse_book_depth(value: &str) -> Option<u32> {
+        value
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .map(|depth| depth.min(MAX_BOOK_DEPTH))
+    }
+
+    /// Parses the value of the Seed option, clamped to the allowed range.
+    pub fn parse_seed(value: &str) -> Option<u64> {
+        value
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .map(|seed| seed.min(MAX_SEED))
+    }
+
+    /// Parses the value of the Randomness option, clamped to the allowed range.
+    pub fn parse_randomness(value: &str) -> Option<u32> {
+        value
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .map(|randomness| randomness.min(MAX_RANDOMNESS))
+    }
+
+    /// Option declarations sent during the UCI handshake.
+    pub fn declarations() -> Vec<UciOptionConfig> {
+        let defaults = Options::default();
+
+        vec![
+            UciOptionConfig::Check {
+                name: "UseNNUE".into(),
+                default: Some(defaults.use_nnue),
+            },
+            UciOptionConfig::String {
+                name: "EvalFile".into(),
+                default: Some(defaults.eval_file),
+            },
+            UciOptionConfig::String {
+                name: "TablebasePath".into(),
+                default: Some(defaults.tablebase_path),
+            },
+            UciOptionConfig::Check {
+                name: "OwnBook".into(),
+                default: Some(defaults.own_book),
+            },
+            UciOptionConfig::String {
+                name: "BookFile".into(),
+                default: Some(defaults.book_file),
+            },
+            UciOptionConfig::Spin {
+                name: "BookDepth".into(),
+                default: Some(defaults.book_depth as i64),
+                min: Some(0),
+                max: Some(MAX_BOOK_DEPTH as i64),
+            },
+            UciOptionConfig::Combo {
+                name: "BookSelection".into(),
+                default: Some(defaults.book_selection.to_string()),
+                var: Selection::NAMES
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+            },
+            UciOptionConfig::Spin {
+                name: "Seed".into(),
+                default: Some(defaults.seed as i64),
+                min: Some(0),
+                max: Some(MAX_SEED as i64),
+            },
+            UciOptionConfig::Spin {
+                name: "Randomness".into(),
+                default: Some(defaults.randomness as i64),
+                min: Some(0),
+                max: Some(MAX_RANDOMNESS as i64),
+            },
+            UciOptionConfig::String {
+                name: "LogFile".into(),
+                default: Some(defaults.log_file),
+            },
+            UciOptionConfig::Combo {
+                name: "LogLevel".into(),
+                default: Some(Level::filter_name(defaults.log_level)),
+                var: Level::NAMES.iter().map(|name| name.to_string()).collect(),
+            },
+            UciOptionConfig::Check {
+                name: "LogJson".into(),
+                default: Some(defaults.log_json),
+            },
+        ]
+    }
+}
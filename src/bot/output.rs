@@ -0,0 +1,35 @@
+use crossbeam_channel::Sender;
+use std::io::{self, Stdout, Write};
+
+/// Destination of the lines the controller sends to the user interface.
+pub trait Output {
+    fn send_line(&mut self, line: &str);
+}
+
+/// Writes lines to a writer, flushing after each one so the user interface sees them at once.
+pub struct WriteOutput<W>(pub W);
+
+impl Default for WriteOutput<Stdout> {
+    fn default() -> Self {
+        WriteOutput(io::stdout())
+    }
+}
+
+impl<W: Write> Output for WriteOutput<W> {
+    fn send_line(&mut self, line: &str) {
+        // Nobody is left to tell when the user interface stops reading
+        let _ = writeln!(self.0, "{}", line).and_then(|()| self.0.flush());
+    }
+}
+
+impl Output for Sender<String> {
+    fn send_line(&mut self, line: &str) {
+        let _ = self.send(line.to_string());
+    }
+}
+
+impl<F: FnMut(&str)> Output for F {
+    fn send_line(&mut self, line: &str) {
+        self(line)
+    }
+}
@@ -0,0 +1,330 @@
+//! The engine as a library, searching positions without going through UCI.
+//!
+//! `Engine` owns a search thread and blocks while it searches:
+//!
+//! ```no_run
+//! use checkm8::{SearchControl, engine::Engine};
+//!
+//! let mut engine = Engine::new();
+//! engine.set_position(None, &["e2e4", "e7e5"]).unwrap();
+//! let result = engine.search(SearchControl::ToDepth(5)).unwrap();
+//! println!("{} scores {}", result.best_move, result.score);
+//! ```
+//!
+//! A search can be ended early from another thread with a `CancelHandle`, in which case it
+//! returns the best move of the last completed depth.
+
+use crate::{
+    Score, SearchCommand, SearchControl, SearchInfo,
+    scoring::Evaluator,
+    search::{Searcher, mate_distance},
+    tablebase::Tablebase,
+};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use shakmaty::{CastlingMode, Chess, Move, Position, fen::Fen, uci::UciMove};
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Error of a position which cannot be set up, from UCI or through the library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    // The FEN describes no legal position
+    InvalidPosition { fen: String, reason: String },
+    // A move which is not legal, counted from the first move
+    IllegalMove { mv: String, number: usize },
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::InvalidPosition { fen, reason } => {
+                write!(f, "invalid position '{}': {}", fen, reason)
+            }
+            PositionError::IllegalMove { mv, number } => {
+                write!(f, "illegal move '{}' (move {})", mv, number)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+/// Error of an engine call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    Position(PositionError),
+    // The search ended without a best move, with the reason
+    Search(String),
+}
+
+impl From<PositionError> for EngineError {
+    fn from(err: PositionError) -> Self {
+        EngineError::Position(err)
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Position(err) => err.fmt(f),
+            EngineError::Search(reason) => write!(f, "search failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+/// Sets up the position after the moves from the FEN, or from the start position without one.
+pub fn setup_position(fen: Option<&Fen>, moves: &[UciMove]) -> Result<Chess, PositionError> {
+    let mut position = match fen {
+        Some(fen) => fen
+            .clone()
+            .into_position(CastlingMode::Standard)
+            .map_err(|err| PositionError::InvalidPosition {
+                fen: fen.to_string(),
+                reason: err.to_string(),
+            })?,
+        None => Chess::default(),
+    };
+
+    for (index, mv) in moves.iter().enumerate() {
+        let illegal = || PositionError::IllegalMove {
+            mv: mv.to_string(),
+            number: index + 1,
+        };
+        let m = mv.to_move(&position).map_err(|_| illegal())?;
+        position = position.play(m).map_err(|_| illegal())?;
+    }
+
+    Ok(position)
+}
+
+/// Outcome of a search, or its progress after every completed depth.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub best_move: Move,
+    // Principal variation, starting with the best move
+    pub pv: Vec<Move>,
+    // Score in centipawns from the side to move's view
+    pub score: Score,
+    // Moves to mate, positive when the side to move mates
+    pub mate: Option<i32>,
+    pub depth: u8,
+    // Deepest ply from the root reached by the last completed depth
+    pub seldepth: u8,
+    // Nodes searched since the start of the search
+    pub nodes: u64,
+    // Positions whose score was taken from the tablebase
+    pub tbhits: u64,
+    pub time: Duration,
+}
+
+/// Ends the running search of an engine, from any thread.
+#[derive(Clone)]
+pub struct CancelHandle {
+    abort: Arc<AtomicBool>,
+}
+
+impl CancelHandle {
+    /// Makes the running search return its best move so far. Does nothing between searches.
+    pub fn cancel(&self) {
+        self.abort.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Searches positions on a search thread of its own, which quits when the engine is dropped.
+pub struct Engine {
+    cmd_tx: Sender<SearchCommand>,
+    info_rx: Receiver<SearchInfo>,
+    abort: Arc<AtomicBool>,
+    searcher: Option<JoinHandle<()>>,
+    position: Chess,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+impl Engine {
+    /// Starts an engine on the start position, with the default evaluation and no randomness.
+    pub fn new() -> Self {
+        let (cmd_tx, cmd_rx) = unbounded();
+        let (info_tx, info_rx) = unbounded();
+        let searcher = Searcher::new(cmd_rx, info_tx);
+        let abort = searcher.abort_flag();
+
+        Engine {
+            cmd_tx,
+            info_rx,
+            abort,
+            searcher: Some(thread::spawn(|| searcher.run())),
+            position: Chess::default(),
+        }
+    }
+
+    pub fn position(&self) -> &Chess {
+        &self.position
+    }
+
+    /// Sets up the position after the moves from the FEN, or from the start position without
+    /// one. Moves are in UCI notation. The previous position is kept if any of it is invalid.
+    pub fn set_position(&mut self, fen: Option<&str>, moves: &[&str]) -> Result<(), EngineError> {
+        let fen = fen
+            .map(|fen| {
+                fen.parse::<Fen>()
+                    .map_err(|err| PositionError::InvalidPosition {
+                        fen: fen.to_string(),
+                        reason: err.to_string(),
+                    })
+            })
+            .transpose()?;
+        let moves = moves
+            .iter()
+            .enumerate()
+            .map(|(index, mv)| {
+                mv.parse::<UciMove>()
+                    .map_err(|_| PositionError::IllegalMove {
+                        mv: mv.to_string(),
+                        number: index + 1,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.position = setup_position(fen.as_ref(), &moves)?;
+        Ok(())
+    }
+
+    /// Sets a position which is already set up.
+    pub fn set_chess(&mut self, position: Chess) {
+        self.position = position;
+    }
+
+    pub fn set_evaluator(&self, evaluator: Evaluator) {
+        self.command(SearchCommand::SetEvaluator(evaluator));
+    }
+
+    pub fn set_tablebase(&self, tablebase: Option<Arc<Tablebase>>) {
+        self.command(SearchCommand::SetTablebase(tablebase));
+    }
+
+    pub fn set_seed(&self, seed: u64) {
+        self.command(SearchCommand::SetSeed(seed));
+    }
+
+    /// Sets the softmax temperature of the root move selection, 0 to always pick the best.
+    pub fn set_randomness(&self, randomness: Score) {
+        self.command(SearchCommand::SetRandomness(randomness));
+    }
+
+    /// Handle which cancels the running search.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle {
+            abort: Arc::clone(&self.abort),
+        }
+    }
+
+    /// Searches the position within the limit.
+    pub fn search(&self, limits: SearchControl) -> Result<SearchResult, EngineError> {
+        self.search_with(limits, |_| ())
+    }
+
+    /// Searches the position within the limit, calling back with the result of every
+    /// completed depth.
+    pub fn search_with(
+        &self,
+        limits: SearchControl,
+        mut on_info: impl FnMut(&SearchResult),
+    ) -> Result<SearchResult, EngineError> {
+        // A cancel sent before this search must not end it
+        self.abort.store(false, Ordering::Relaxed);
+        let start = Instant::now();
+        self.command(SearchCommand::Start {
+            position: self.position.clone(),
+            control: limits,
+        });
+
+        let mut last: Option<SearchResult> = None;
+        loop {
+            let info = self
+                .info_rx
+                .recv()
+                .map_err(|_| EngineError::Search("search thread stopped".into()))?;
+
+            match info {
+                SearchInfo::Info {
+                    depth,
+                    seldepth,
+                    pv,
+                    score,
+                    nodes,
+                    time,
+                    tbhits,
+                    ..
+                } => {
+                    let Some(&best_move) = pv.first() else {
+                        continue;
+                    };
+                    let result = SearchResult {
+                        best_move,
+                        pv,
+                        score,
+                        mate: mate_distance(score, depth),
+                        depth,
+                        seldepth,
+                        nodes,
+                        tbhits,
+                        time,
+                    };
+                    on_info(&result);
+                    last = Some(result);
+                }
+                SearchInfo::Progress { .. } => (),
+
+                // The best move is the one of the last completed depth, unless none completed
+                SearchInfo::BestMove(best_move) => {
+                    let time = start.elapsed();
+                    return Ok(match last {
+                        Some(last) if last.best_move == best_move => SearchResult { time, ..last },
+                        last => SearchResult {
+                            best_move,
+                            pv: vec![best_move],
+                            score: 0,
+                            mate: None,
+                            depth: 0,
+                            seldepth: 0,
+                            nodes: last.as_ref().map_or(0, |last| last.nodes),
+                            tbhits: last.map_or(0, |last| last.tbhits),
+                            time,
+                        },
+                    });
+                }
+                SearchInfo::Error(reason) => return Err(EngineError::Search(reason)),
+            }
+        }
+    }
+
+    fn command(&self, command: SearchCommand) {
+        // The searcher only stops when the engine is dropped or it panicked, which the next
+        // search reports
+        let _ = self.cmd_tx.send(command);
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.abort.store(true, Ordering::Relaxed);
+        self.command(SearchCommand::Quit);
+        if let Some(searcher) = self.searcher.take() {
+            let _ = searcher.join();
+        }
+    }
+}
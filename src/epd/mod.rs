@@ -0,0 +1,322 @@
+//! Test suites in Extended Position Description.
+//!
+//! Every line of a suite holds a position and the operations which describe its solution:
+//! `bm` lists the best moves, `am` moves to avoid and `dm` the number of moves to a forced
+//! mate, while `id` names the position. A position counts as solved when the searcher's move
+//! satisfies every one of them. [`Runner`] searches the positions of a suite and measures how
+//! long it took to settle on a solution.
+
+use crate::{Score, SearchControl, engine::Engine, search::mate_distance, util::json_string};
+use shakmaty::{
+    CastlingMode, Chess, EnPassantMode, Move, Position,
+    fen::Fen,
+    san::{San, SanPlus},
+    uci::UciMove,
+};
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// A position of a test suite with its solution.
+#[derive(Clone, Debug)]
+pub struct Epd {
+    pub position: Chess,
+    pub id: Option<String>,
+    pub best_moves: Vec<Move>,
+    pub avoid_moves: Vec<Move>,
+    // Moves to a forced mate
+    pub mate: Option<u32>,
+}
+
+impl Epd {
+    /// Whether a move, with the root score of a search to the given depth, solves the position.
+    pub fn is_solution(&self, mv: Move, score: Option<Score>, depth: u8) -> bool {
+        let mates = match self.mate {
+            Some(mate) => score
+                .and_then(|score| mate_distance(score, depth))
+                .is_some_and(|moves| moves > 0 && moves as u32 <= mate),
+            None => true,
+        };
+
+        (self.best_moves.is_empty() || self.best_moves.contains(&mv))
+            && !self.avoid_moves.contains(&mv)
+            && mates
+    }
+
+    /// Describes the solution, as it is written in the suite.
+    pub fn solution(&self) -> String {
+        let san = |mv: &Move| SanPlus::from_move(self.position.clone(), *mv).to_string();
+        let mut parts = Vec::new();
+
+        if !self.best_moves.is_empty() {
+            let moves: Vec<String> = self.best_moves.iter().map(san).collect();
+            parts.push(format!("bm {}", moves.join(" ")));
+        }
+        if !self.avoid_moves.is_empty() {
+            let moves: Vec<String> = self.avoid_moves.iter().map(san).collect();
+            parts.push(format!("am {}", moves.join(" ")));
+        }
+        if let Some(mate) = self.mate {
+            parts.push(format!("dm {}", mate));
+        }
+
+        parts.join("; ")
+    }
+}
+
+impl FromStr for Epd {
+    type Err = EpdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Four fields of a FEN without move counters, followed by the operations
+        let mut board = Vec::new();
+        let mut rest = s.trim();
+        for _ in 0..4 {
+            let (field, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            board.push(field);
+            rest = tail.trim_start();
+        }
+
+        let board = board.join(" ");
+        let position: Chess = board
+            .parse::<Fen>()
+            .ok()
+            .and_then(|fen| fen.into_position(CastlingMode::Standard).ok())
+            .ok_or_else(|| EpdError::InvalidPosition(board.clone()))?;
+        if position.is_game_over() {
+            return Err(EpdError::GameOver);
+        }
+
+        let mut epd = Epd {
+            position,
+            id: None,
+            best_moves: Vec::new(),
+            avoid_moves: Vec::new(),
+            mate: None,
+        };
+
+        for operation in split_operations(rest) {
+            let Some((opcode, operands)) = operation.split_first() else {
+                continue;
+            };
+
+            match opcode.as_str() {
+                "bm" => epd.best_moves = parse_moves(&epd.position, operands)?,
+                "am" => epd.avoid_moves = parse_moves(&epd.position, operands)?,
+                "dm" => {
+                    let mate = operands.first().and_then(|mate| mate.parse().ok());
+                    epd.mate = Some(mate.ok_or_else(|| EpdError::InvalidOperation(opcode.into()))?);
+                }
+                "id" => epd.id = operands.first().cloned(),
+                // Other operations, such as comments, are not needed to run a suite
+                _ => (),
+            }
+        }
+
+        if epd.best_moves.is_empty() && epd.avoid_moves.is_empty() && epd.mate.is_none() {
+            return Err(EpdError::NoSolution);
+        }
+
+        Ok(epd)
+    }
+}
+
+/// Splits the operations of an EPD line into their opcodes and operands, unquoting strings.
+fn split_operations(text: &str) -> Vec<Vec<String>> {
+    let mut operations = Vec::new();
+    let mut operation = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => operations.push(std::mem::take(&mut operation)),
+            '"' => {
+                let mut string = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => string.extend(chars.next()),
+                        _ => string.push(c),
+                    }
+                }
+                operation.push(string);
+            }
+            c if c.is_whitespace() => (),
+            _ => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ';' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                operation.push(word);
+            }
+        }
+    }
+
+    // The last operation may lack its semicolon
+    if !operation.is_empty() {
+        operations.push(operation);
+    }
+    operations
+}
+
+/// Parses moves in SAN, or in UCI notation as some suites write them.
+fn parse_moves(position: &Chess, moves: &[String]) -> Result<Vec<Move>, EpdError> {
+    moves
+        .iter()
+        .map(|text| {
+            let san = text.parse::<SanPlus>().ok().map(|san| san.san);
+            san.and_then(|san: San| san.to_move(position).ok())
+                .or_else(|| {
+                    let uci = text.parse::<UciMove>().ok()?;
+                    uci.to_move(position).ok()
+                })
+                .ok_or_else(|| EpdError::IllegalMove(text.clone()))
+        })
+        .collect()
+}
+
+/// Reads the positions of a suite, skipping empty lines and `#` comments.
+pub fn read_suite(text: &str) -> Vec<(usize, Result<Epd, EpdError>)> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| (index + 1, line.parse()))
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EpdError {
+    InvalidPosition(String),
+    // A move which is not legal, or not a move at all
+    IllegalMove(String),
+    InvalidOperation(String),
+    // The position has no moves to search
+    GameOver,
+    // Neither best moves, moves to avoid nor a mate are given
+    NoSolution,
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpdError::InvalidPosition(fen) => write!(f, "invalid position '{}'", fen),
+            EpdError::IllegalMove(mv) => write!(f, "illegal move '{}'", mv),
+            EpdError::InvalidOperation(opcode) => write!(f, "invalid '{}' operation", opcode),
+            EpdError::GameOver => write!(f, "the game is over"),
+            EpdError::NoSolution => write!(f, "no bm, am or dm operation"),
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+/// Result of searching a position of a suite.
+#[derive(Clone, Debug)]
+pub struct Attempt {
+    pub id: Option<String>,
+    pub fen: String,
+    pub best_move: SanPlus,
+    pub solved: bool,
+    // Time and nodes after which the search kept finding a solution
+    pub solution_time: Option<Duration>,
+    pub solution_nodes: Option<u64>,
+    pub time: Duration,
+    pub nodes: u64,
+    // Last completed depth and its score
+    pub depth: u8,
+    pub score: Option<Score>,
+}
+
+impl Attempt {
+    /// The attempt as a JSON object.
+    pub fn to_json(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "null".into());
+        let mate = self
+            .score
+            .and_then(|score| mate_distance(score, self.depth));
+
+        format!(
+            "{{\"id\":{},\"fen\":{},\"move\":{},\"solved\":{},\"solution_time_ms\":{},\
+             \"solution_nodes\":{},\"time_ms\":{},\"nodes\":{},\"depth\":{},\"score\":{},\
+             \"mate\":{}}}",
+            optional(self.id.as_deref().map(json_string)),
+            json_string(&self.fen),
+            json_string(&self.best_move.to_string()),
+            self.solved,
+            optional(self.solution_time.map(|time| time.as_millis().to_string())),
+            optional(self.solution_nodes.map(|nodes| nodes.to_string())),
+            self.time.as_millis(),
+            self.nodes,
+            self.depth,
+            optional(self.score.filter(|_| mate.is_none()).map(|s| s.to_string())),
+            optional(mate.map(|mate| mate.to_string())),
+        )
+    }
+}
+
+/// Searches suite positions on a search thread of its own.
+pub struct Runner {
+    engine: Engine,
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Runner::new()
+    }
+}
+
+impl Runner {
+    pub fn new() -> Self {
+        Runner {
+            engine: Engine::new(),
+        }
+    }
+
+    /// Searches a position within the given limit.
+    pub fn run(&mut self, epd: &Epd, control: SearchControl) -> Attempt {
+        let start = Instant::now();
+        self.engine.set_chess(epd.position.clone());
+
+        // Solutions only count once later depths no longer change the mind
+        let mut solution = None;
+        let result = self
+            .engine
+            .search_with(control, |info| {
+                if epd.is_solution(info.best_move, Some(info.score), info.depth) {
+                    solution = solution.or(Some((start.elapsed(), info.nodes)));
+                } else {
+                    solution = None;
+                }
+            })
+            .unwrap_or_else(|err| panic!("{}", err));
+        let time = start.elapsed();
+
+        // Without a completed depth there is no score
+        let score = (result.depth > 0).then_some(result.score);
+        let solved = epd.is_solution(result.best_move, score, result.depth);
+        if solved {
+            solution = solution.or(Some((time, result.nodes)));
+        } else {
+            solution = None;
+        }
+
+        Attempt {
+            id: epd.id.clone(),
+            fen: Fen::from_position(&epd.position, EnPassantMode::Legal).to_string(),
+            best_move: SanPlus::from_move(epd.position.clone(), result.best_move),
+            solved,
+            solution_time: solution.map(|(time, _)| time),
+            solution_nodes: solution.map(|(_, nodes)| nodes),
+            time,
+            nodes: result.nodes,
+            depth: result.depth,
+            score,
+        }
+    }
+}
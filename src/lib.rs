@@ -1,8 +1,19 @@
 use std::i32;
 
+pub mod arena;
+pub mod bench;
+pub mod book;
 pub mod bot;
+pub mod engine;
+pub mod epd;
+pub mod logging;
+pub mod perft;
+pub mod pgn;
 pub mod scoring;
 pub mod search;
+pub mod session;
+pub mod tablebase;
+pub mod util;
 
 // Common types
 pub type Score = i32;
@@ -10,8 +21,14 @@ pub type Score = i32;
 // Parameters
 const SEARCH_TIME_MS: u64 = 2000;
 
-const MATE_SCORE: Score = i32::MIN + 1;
-const MIN_SCORE: Score = i32::MIN;
+// Seed of the searcher's random number generator until the Seed option is set
+const DEFAULT_SEED: u64 = 0;
+
+// Room below checkmates for the remaining depth, and beyond the horizon for tablebase mates (in plies)
+const MAX_MATE_DISTANCE: Score = 256;
+
+const MATE_SCORE: Score = MIN_SCORE + MAX_MATE_DISTANCE * 5;
+const MIN_SCORE: Score = -MAX_SCORE;
 const MAX_SCORE: Score = i32::MAX;
 
 /// Instructions for the search thread
@@ -20,6 +37,8 @@ pub enum SearchControl {
     ToDepth(u8),
     // Search for a approximate duration (in milliseconds)
     TimeLimit(u64),
+    // Search until a number of nodes have been visited
+    Nodes(u64),
 }
 
 /// Instructions for the search thread
@@ -28,6 +47,16 @@ pub enum SearchCommand {
         position: shakmaty::Chess,
         control: SearchControl,
     },
+    // Evaluation backend used by subsequent searches
+    SetEvaluator(scoring::Evaluator),
+    // Tablebase probed by subsequent searches
+    SetTablebase(Option<std::sync::Arc<tablebase::Tablebase>>),
+    // Reseeds the random number generator of the searcher
+    SetSeed(u64),
+    // Softmax temperature of the root move selection (in centipawns), 0 to always pick the best
+    SetRandomness(Score),
+    // Running searches only end early through `Searcher::abort_flag`, as commands are read
+    // between searches
     Stop,
     Quit,
 }
@@ -35,10 +64,33 @@ pub enum SearchCommand {
 /// Search information to be logged
 pub enum SearchInfo {
     BestMove(shakmaty::Move),
+    // The search ended without a best move, with the reason
+    Error(String),
+    // Result of a completed depth, with statistics since the start of the search. There is no
+    // hashfull, as the search keeps no hash table.
     Info {
         depth: u8,
+        // Deepest ply from the root reached at this depth. The search has no quiescence search or
+        // extensions, so this only differs from the depth when every line ends early.
+        seldepth: u8,
         pv: Vec<shakmaty::Move>,
         score: i32,
         nodes: u64,
+        time: std::time::Duration,
+        // Nodes per second
+        nps: u64,
+        // Positions whose score was taken from the tablebase
+        tbhits: u64,
+    },
+    // Sent about once a second while searching, with the root move being searched
+    Progress {
+        depth: u8,
+        currmove: shakmaty::Move,
+        // Number of the move in the root move order, from 1
+        currmovenumber: u16,
+        nodes: u64,
+        time: std::time::Duration,
+        nps: u64,
+        tbhits: u64,
     },
 }
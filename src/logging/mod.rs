@@ -0,0 +1,336 @@
+//! Engine log, written on a thread of its own so that logging never blocks the protocol loop.
+//!
+//! Every record has a level and a kind, telling input, output, search events and other engine
+//! messages apart. Records are written as plain text lines or as JSON lines with a timestamp,
+//! to a file which is rotated once it grows beyond its size limit: `engine.log` is renamed to
+//! `engine.log.1`, which is renamed to `engine.log.2` and so on, keeping a few old files.
+
+use crate::util::json_string;
+use chrono::Local;
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    str::FromStr,
+    thread::{self, JoinHandle},
+};
+
+// Log file in the working directory unless another one is configured
+const DEFAULT_LOG_FILE: &str = "engine.log";
+
+// Size at which the log file is rotated (in bytes)
+const DEFAULT_MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+// Number of rotated files kept besides the current one
+const ROTATED_FILES: u32 = 3;
+
+/// Importance of a record, records less important than the configured level are dropped.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    // Protocol messages, searches and the engine life cycle
+    #[default]
+    Info,
+    // Search progress
+    Debug,
+}
+
+impl Level {
+    /// Names used for the UCI option, where `Off` disables logging.
+    pub const NAMES: [&'static str; 5] = ["Off", "Error", "Warn", "Info", "Debug"];
+
+    /// Parses a level name, which is `None` for `Off`.
+    pub fn parse_filter(s: &str) -> Result<Option<Level>, ParseLevelError> {
+        if s.eq_ignore_ascii_case("off") {
+            Ok(None)
+        } else {
+            s.parse().map(Some)
+        }
+    }
+
+    /// Name of a level, or `Off` for `None`.
+    pub fn filter_name(level: Option<Level>) -> String {
+        level.map_or("Off".into(), |level| level.to_string())
+    }
+}
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            _ => Err(ParseLevelError),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Level::Error => write!(f, "Error"),
+            Level::Warn => write!(f, "Warn"),
+            Level::Info => write!(f, "Info"),
+            Level::Debug => write!(f, "Debug"),
+        }
+    }
+}
+
+/// Error returned for names which are no level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLevelError;
+
+impl fmt::Display for ParseLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid log level")
+    }
+}
+
+impl std::error::Error for ParseLevelError {}
+
+/// What a record is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    // Command received from the user interface
+    In,
+    // Message sent to the user interface
+    Out,
+    // Search started or finished
+    Search,
+    // Anything else the engine has to say
+    Engine,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::In => "in",
+            Kind::Out => "out",
+            Kind::Search => "search",
+            Kind::Engine => "engine",
+        }
+    }
+}
+
+/// Where and what to log.
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    // Log file, empty to log nothing
+    pub path: String,
+    // Least important level logged, `None` to log nothing
+    pub level: Option<Level>,
+    // Write JSON lines instead of plain text
+    pub json: bool,
+    // Size at which the file is rotated (in bytes), 0 to never rotate
+    pub max_size: u64,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            path: DEFAULT_LOG_FILE.into(),
+            level: Some(Level::default()),
+            json: false,
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+}
+
+/// Requests to the writer thread.
+enum Message {
+    Line(String),
+    SetPath(String),
+    Flush(Sender<()>),
+    Close,
+}
+
+/// Formats records and hands them to the writer thread, which is joined when dropped.
+pub struct Logger {
+    tx: Sender<Message>,
+    writer: Option<JoinHandle<()>>,
+    level: Option<Level>,
+    json: bool,
+}
+
+impl Logger {
+    pub fn new(config: LogConfig) -> Self {
+        let (tx, rx) = unbounded();
+        let mut writer = Writer {
+            path: config.path,
+            max_size: config.max_size,
+            file: None,
+            size: 0,
+        };
+
+        Logger {
+            tx,
+            writer: Some(thread::spawn(move || writer.run(rx))),
+            level: config.level,
+            json: config.json,
+        }
+    }
+
+    /// A logger which drops all records.
+    pub fn disabled() -> Self {
+        Logger::new(LogConfig {
+            path: String::new(),
+            level: None,
+            ..LogConfig::default()
+        })
+    }
+
+    /// Logs a record, unless its level is filtered out.
+    pub fn log(&self, level: Level, kind: Kind, message: &str) {
+        if self.level.is_none_or(|max| level > max) {
+            return;
+        }
+
+        let line = if self.json {
+            format!(
+                "{{\"time\":\"{}\",\"level\":\"{}\",\"kind\":\"{}\",\"message\":{}}}",
+                Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+                level.to_string().to_lowercase(),
+                kind.name(),
+                json_string(message)
+            )
+        } else {
+            let level = match level {
+                Level::Error => "ERROR: ",
+                Level::Warn => "WARNING: ",
+                Level::Info | Level::Debug => "",
+            };
+            match kind {
+                Kind::In => format!("{} IN: '{}'", level, message),
+                Kind::Out => format!("{}OUT: '{}'", level, message),
+                Kind::Search => format!("{}SEARCH: {}", level, message),
+                Kind::Engine => format!("{}{}", level, message),
+            }
+        };
+        let _ = self.tx.send(Message::Line(line));
+    }
+
+    /// Continues the log in another file, or stops writing it for an empty path.
+    pub fn set_path(&self, path: &str) {
+        let _ = self.tx.send(Message::SetPath(path.into()));
+    }
+
+    pub fn set_level(&mut self, level: Option<Level>) {
+        self.level = level;
+    }
+
+    pub fn set_json(&mut self, json: bool) {
+        self.json = json;
+    }
+
+    /// Waits until every record logged so far is written to the file.
+    pub fn flush(&self) {
+        let (done_tx, done_rx) = unbounded();
+        if self.tx.send(Message::Flush(done_tx)).is_ok() {
+            let _ = done_rx.recv();
+        }
+    }
+}
+
+impl Drop for Logger {
+    fn drop(&mut self) {
+        let _ = self.tx.send(Message::Close);
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Owns the log file on the writer thread.
+struct Writer {
+    path: String,
+    max_size: u64,
+    // Opened on the first line, so that nothing is created while logging is disabled
+    file: Option<BufWriter<File>>,
+    size: u64,
+}
+
+impl Writer {
+    fn run(&mut self, rx: Receiver<Message>) {
+        loop {
+            // Buffered lines are written out whenever the logger falls idle
+            let message = match rx.try_recv() {
+                Ok(message) => message,
+                Err(_) => {
+                    self.flush();
+                    match rx.recv() {
+                        Ok(message) => message,
+                        Err(_) => break,
+                    }
+                }
+            };
+
+            match message {
+                Message::Line(line) => self.write(&line),
+                Message::SetPath(path) => {
+                    self.flush();
+                    self.file = None;
+                    self.path = path;
+                }
+                Message::Flush(done_tx) => {
+                    self.flush();
+                    let _ = done_tx.send(());
+                }
+                Message::Close => break,
+            }
+        }
+        self.flush();
+    }
+
+    fn write(&mut self, line: &str) {
+        if self.path.is_empty() {
+            return;
+        }
+        if self.max_size > 0 && self.size >= self.max_size {
+            self.rotate();
+        }
+
+        if self.file.is_none() {
+            self.open();
+        }
+        if let Some(file) = &mut self.file
+            && writeln!(file, "{}", line).is_ok()
+        {
+            self.size += line.len() as u64 + 1;
+        }
+    }
+
+    fn open(&mut self) {
+        // Logging must never bring the engine down, so a log which cannot be opened is skipped
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .ok()
+            .map(BufWriter::new);
+        self.size = fs::metadata(&self.path).map_or(0, |metadata| metadata.len());
+    }
+
+    /// Moves the current file to the first rotated one, shifting the older ones.
+    fn rotate(&mut self) {
+        self.flush();
+        self.file = None;
+
+        for index in (1..ROTATED_FILES).rev() {
+            let from = format!("{}.{}", self.path, index);
+            let _ = fs::rename(&from, format!("{}.{}", self.path, index + 1));
+        }
+        let _ = fs::rename(&self.path, format!("{}.1", self.path));
+        self.size = 0;
+    }
+
+    fn flush(&mut self) {
+        if let Some(file) = &mut self.file {
+            let _ = file.flush();
+        }
+    }
+}
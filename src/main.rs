@@ -1,20 +1,129 @@
-use checkm8::bot::{controller::Controller, input::InputListener};
-use checkm8::search::Searcher;
-use crossbeam_channel::unbounded;
-use std::thread;
+use checkm8::bench;
+use checkm8::bot::engine::UciEngine;
+use checkm8::logging::{Level, LogConfig};
+use checkm8::scoring::Evaluator;
+use checkm8::session::Recorder;
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader, Cursor, Read},
+    process::{self, ExitCode},
+};
 
-fn main() {
-    // Initialize channels
-    let (input_tx, input_rx) = unbounded();
-    let (cmd_tx, cmd_rx) = unbounded();
-    let (info_tx, info_rx) = unbounded();
+/// Command line settings.
+struct Config {
+    script: Option<String>,
+    // Continue with stdin after the script
+    interactive: bool,
+    log: LogConfig,
+    // Transcript of the session to write
+    record: Option<String>,
+}
 
-    // Spawn input listener thread
-    thread::spawn(|| InputListener::new(input_tx).run());
+fn main() -> ExitCode {
+    // `checkm8 bench [depth]` prints the node count signature and exits
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "bench") {
+        let depth = match args.get(1).map(|depth| depth.parse()) {
+            Some(Ok(depth)) => depth,
+            Some(Err(_)) => usage(),
+            None => bench::DEFAULT_DEPTH,
+        };
+        println!("{}", bench::run(depth, Evaluator::default()));
+        return ExitCode::SUCCESS;
+    }
 
-    // Spawn search thread
-    thread::spawn(|| Searcher::new(cmd_rx, info_tx).run());
+    let config = parse_args(args);
+    let input = input(&config);
+    let recorder = config
+        .record
+        .as_ref()
+        .map(|path| match Recorder::create(path) {
+            Ok(recorder) => recorder,
+            Err(e) => {
+                eprintln!("Failed to create transcript '{}': {}", path, e);
+                process::exit(1);
+            }
+        });
 
     // Run controller on main thread
-    Controller::new(input_rx, cmd_tx, info_rx, "engine.log").run();
+    UciEngine::new(config.log, input, recorder).run()
+}
+
+/// Source of the commands: stdin, or a script which may be followed by stdin.
+fn input(config: &Config) -> Box<dyn BufRead + Send> {
+    let stdin = || BufReader::new(io::stdin());
+    let Some(script) = &config.script else {
+        return Box::new(stdin());
+    };
+
+    let mut text = match fs::read_to_string(script) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Failed to read script '{}': {}", script, e);
+            process::exit(1);
+        }
+    };
+
+    // The last line of the script must not run into the first one typed
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    if config.interactive {
+        Box::new(Cursor::new(text).chain(stdin()))
+    } else {
+        Box::new(Cursor::new(text))
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Config {
+    let mut config = Config {
+        script: None,
+        interactive: false,
+        log: LogConfig::default(),
+        record: None,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut next = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--script" => config.script = Some(next()),
+            "--interactive" => config.interactive = true,
+            "--log" => config.log.path = next(),
+            "--log-level" => {
+                config.log.level = Level::parse_filter(&next()).unwrap_or_else(|_| usage())
+            }
+            "--log-json" => config.log.json = true,
+            "--log-max-size" => config.log.max_size = parse_value(next()),
+            "--record" => config.record = Some(next()),
+            _ => usage(),
+        }
+    }
+
+    if config.interactive && config.script.is_none() {
+        usage();
+    }
+
+    config
+}
+
+fn parse_value<T: std::str::FromStr>(value: String) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: checkm8 [--script FILE [--interactive]] [--log FILE] [--log-level LEVEL]");
+    eprintln!("               [--log-json] [--log-max-size BYTES] [--record TRANSCRIPT]");
+    eprintln!("       checkm8 bench [DEPTH]");
+    eprintln!();
+    eprintln!("Reads UCI commands from stdin, or from FILE and then from stdin with");
+    eprintln!("--interactive. The engine quits at the end of its input, after finishing the");
+    eprintln!("running search.");
+    eprintln!();
+    eprintln!("Logs to engine.log unless another FILE is given, or none if it is empty. LEVEL");
+    eprintln!("is off, error, warn, info (default) or debug. The log is rotated when it grows");
+    eprintln!("beyond BYTES (10 MB, 0 to never rotate).");
+    eprintln!();
+    eprintln!("The session is recorded to TRANSCRIPT, to be checked with the replay tool.");
+    process::exit(2);
 }
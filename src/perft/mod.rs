@@ -0,0 +1,152 @@
+//! Move generation validation by counting the leaf nodes of the game tree.
+//!
+//! Perft plays every legal move to a fixed depth and counts the positions reached, which can
+//! be compared against published node counts. `divide` splits the count by root move, to
+//! narrow a mismatch down to the move whose subtree is wrong.
+//!
+//! Two optional shortcuts make deep counts feasible: bulk counting uses the number of legal
+//! moves one ply above the leaves instead of playing them, and hashing remembers the counts of
+//! subtrees reached by transposition.
+
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Move, Position, fen::Fen, zobrist::Zobrist64};
+use std::{fmt, mem::size_of, str::FromStr};
+
+/// Counts leaf nodes, optionally with bulk counting and a hash table.
+pub struct Perft {
+    bulk: bool,
+    table: Vec<Entry>,
+}
+
+/// Count of a subtree stored in the hash table.
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    key: u64,
+    depth: u32,
+    nodes: u64,
+}
+
+impl Perft {
+    /// Creates a counter, using a hash table of the given size in megabytes unless it is zero.
+    pub fn new(bulk: bool, hash_mb: usize) -> Self {
+        let entries = hash_mb * 1024 * 1024 / size_of::<Entry>();
+        Perft {
+            bulk,
+            table: vec![Entry::default(); entries],
+        }
+    }
+
+    /// Counts the leaf nodes at the given depth.
+    pub fn count(&mut self, position: &Chess, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = position.legal_moves();
+        if depth == 1 && self.bulk {
+            return moves.len() as u64;
+        }
+
+        let key = self.hash(position);
+        if let Some(nodes) = self.probe(key, depth) {
+            return nodes;
+        }
+
+        let mut nodes = 0;
+        for mv in moves {
+            let mut child = position.clone();
+            child.play_unchecked(mv);
+            nodes += self.count(&child, depth - 1);
+        }
+
+        self.store(key, depth, nodes);
+        nodes
+    }
+
+    /// Counts the leaf nodes at the given depth below every legal move.
+    pub fn divide(&mut self, position: &Chess, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        position
+            .legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let mut child = position.clone();
+                child.play_unchecked(mv);
+                (mv, self.count(&child, depth - 1))
+            })
+            .collect()
+    }
+
+    fn hash(&self, position: &Chess) -> Option<u64> {
+        if self.table.is_empty() {
+            return None;
+        }
+        Some(position.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0)
+    }
+
+    fn probe(&self, key: Option<u64>, depth: u32) -> Option<u64> {
+        let key = key?;
+        let entry = self.table[key as usize % self.table.len()];
+        (entry.key == key && entry.depth == depth).then_some(entry.nodes)
+    }
+
+    fn store(&mut self, key: Option<u64>, depth: u32, nodes: u64) {
+        if let Some(key) = key {
+            let index = key as usize % self.table.len();
+            self.table[index] = Entry { key, depth, nodes };
+        }
+    }
+}
+
+/// Counts the leaf nodes at the given depth without shortcuts.
+pub fn perft(position: &Chess, depth: u32) -> u64 {
+    Perft::new(false, 0).count(position, depth)
+}
+
+/// A position with its known node counts, as a line of a perft suite:
+/// `<fen> ;D1 20 ;D2 400 ...`
+#[derive(Clone, Debug)]
+pub struct PerftPosition {
+    pub position: Chess,
+    // Node counts by depth
+    pub counts: Vec<(u32, u64)>,
+}
+
+impl FromStr for PerftPosition {
+    type Err = ParsePerftError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split(';');
+        let fen = fields.next().unwrap_or_default().trim();
+        let position = fen
+            .parse::<Fen>()
+            .ok()
+            .and_then(|fen| fen.into_position(CastlingMode::Standard).ok())
+            .ok_or(ParsePerftError)?;
+
+        let counts = fields
+            .map(|field| {
+                let (depth, nodes) = field.trim().split_once(char::is_whitespace)?;
+                let depth = depth.strip_prefix('D')?.parse().ok()?;
+                Some((depth, nodes.trim().parse().ok()?))
+            })
+            .collect::<Option<_>>()
+            .ok_or(ParsePerftError)?;
+
+        Ok(PerftPosition { position, counts })
+    }
+}
+
+/// Error returned for lines which are not a FEN followed by `;D<depth> <nodes>` counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsePerftError;
+
+impl fmt::Display for ParsePerftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid perft position")
+    }
+}
+
+impl std::error::Error for ParsePerftError {}
@@ -0,0 +1,323 @@
+//! Game records in Portable Game Notation.
+//!
+//! A game holds its tag pairs, the starting position and a tree of moves. Every move of a line
+//! may carry comments, numeric annotation glyphs (NAGs) and alternative lines replacing it,
+//! which mirrors how variations are written in PGN. Games are read with [`read_games`] or
+//! [`Game::from_str`] and written back with their [`fmt::Display`] implementation.
+//!
+//! Moves are validated while reading, so every move in the tree is legal in its position.
+
+use shakmaty::{
+    CastlingMode, Chess, Color, EnPassantMode, Move, Outcome, Position, fen::Fen, san::SanPlus,
+};
+use std::{fmt, str::FromStr, time::Duration};
+
+mod read;
+
+pub use read::read_games;
+
+// Movetext lines are wrapped at this width
+const LINE_WIDTH: usize = 80;
+
+// Tags every game starts with, in the order of the Seven Tag Roster
+const ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// A move of a line together with its annotations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub mv: Move,
+    // Comment written before the move, only used at the start of a line
+    pub starting_comment: Option<String>,
+    // Comment written after the move
+    pub comment: Option<String>,
+    pub nags: Vec<u8>,
+    // Alternatives to this move, each starting from the position before it
+    pub variations: Vec<Vec<Node>>,
+}
+
+impl Node {
+    pub fn new(mv: Move) -> Self {
+        Node {
+            mv,
+            starting_comment: None,
+            comment: None,
+            nags: Vec::new(),
+            variations: Vec::new(),
+        }
+    }
+}
+
+/// A game record.
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    initial: Chess,
+    // Main line of the game
+    pub moves: Vec<Node>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
+}
+
+impl Game {
+    /// Creates a game from the standard starting position, with the Seven Tag Roster unknown.
+    pub fn new() -> Self {
+        Game {
+            tags: ROSTER
+                .iter()
+                .map(|&name| {
+                    let value = if name == "Result" { "*" } else { "?" };
+                    (name.to_string(), value.to_string())
+                })
+                .collect(),
+            initial: Chess::default(),
+            moves: Vec::new(),
+        }
+    }
+
+    /// Creates a game starting from a position, recorded in the `FEN` and `SetUp` tags.
+    pub fn from_position(position: Chess) -> Self {
+        let mut game = Game::new();
+        if position != Chess::default() {
+            let fen = Fen::from_position(&position, EnPassantMode::Legal);
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &fen.to_string());
+        }
+        game.initial = position;
+        game
+    }
+
+    /// Value of a tag.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a tag, adding it after the existing tags if it is missing.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.into(),
+            None => self.tags.push((name.into(), value.into())),
+        }
+    }
+
+    /// Result of the game, taken from the `Result` tag.
+    pub fn result(&self) -> Outcome {
+        self.tag("Result")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(Outcome::Unknown)
+    }
+
+    pub fn set_result(&mut self, outcome: Outcome) {
+        self.set_tag("Result", &outcome.to_string());
+    }
+
+    /// Position the game starts from.
+    pub fn initial_position(&self) -> &Chess {
+        &self.initial
+    }
+
+    /// Position at the end of the main line.
+    pub fn position(&self) -> Chess {
+        let mut position = self.initial.clone();
+        for node in &self.moves {
+            position.play_unchecked(node.mv);
+        }
+        position
+    }
+
+    /// Moves of the main line.
+    pub fn mainline(&self) -> impl Iterator<Item = Move> + '_ {
+        self.moves.iter().map(|node| node.mv)
+    }
+
+    /// Appends a move to the main line, which must be legal at its end.
+    pub fn push(&mut self, mv: Move, comment: Option<String>) {
+        let mut node = Node::new(mv);
+        node.comment = comment;
+        self.moves.push(node);
+    }
+}
+
+/// Writes a line of moves with its variations as movetext tokens.
+fn write_line(line: &[Node], position: &Chess, tokens: &mut Vec<String>) {
+    let mut position = position.clone();
+    // Black moves need their number after anything interrupting the move sequence
+    let mut interrupted = true;
+
+    for node in line {
+        if let Some(comment) = &node.starting_comment {
+            tokens.push(format!("{{{}}}", comment));
+            interrupted = true;
+        }
+
+        // Move numbers are kept on the same line as their move
+        let number = match position.turn() {
+            Color::White => format!("{}. ", position.fullmoves()),
+            Color::Black if interrupted => format!("{}... ", position.fullmoves()),
+            Color::Black => String::new(),
+        };
+        interrupted = false;
+
+        let before = position.clone();
+        let san = SanPlus::from_move_and_play_unchecked(&mut position, node.mv);
+        tokens.push(format!("{}{}", number, san));
+
+        for nag in &node.nags {
+            tokens.push(format!("${}", nag));
+        }
+        if let Some(comment) = &node.comment {
+            tokens.push(format!("{{{}}}", comment));
+            interrupted = true;
+        }
+        for variation in &node.variations {
+            tokens.push("(".into());
+            write_line(variation, &before, tokens);
+            tokens.push(")".into());
+            interrupted = true;
+        }
+    }
+}
+
+impl FromStr for Game {
+    type Err = PgnError;
+
+    /// Parses the first game of a PGN text.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        read_games(s)
+            .into_iter()
+            .next()
+            .unwrap_or(Err(PgnError::Empty))
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+        write_line(&self.moves, &self.initial, &mut tokens);
+        tokens.push(self.result().to_string());
+
+        // Tokens are joined by spaces, but variations hug their parentheses
+        let mut line = String::new();
+        let mut previous = "";
+        for token in &tokens {
+            let separate = !line.is_empty() && previous != "(" && token != ")";
+            if separate && line.len() + 1 + token.len() > LINE_WIDTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            } else if separate {
+                line.push(' ');
+            }
+            line.push_str(token);
+            previous = token;
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+/// Error returned when reading an invalid game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    // The text contains no game
+    Empty,
+    InvalidFen(String),
+    // A move which is not legal, or not a move at all
+    IllegalMove(String),
+    InvalidNag(String),
+    UnbalancedVariation,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Empty => write!(f, "no game found"),
+            PgnError::InvalidFen(fen) => write!(f, "invalid FEN '{}'", fen),
+            PgnError::IllegalMove(san) => write!(f, "illegal move '{}'", san),
+            PgnError::InvalidNag(nag) => write!(f, "invalid annotation '{}'", nag),
+            PgnError::UnbalancedVariation => write!(f, "unbalanced variation parentheses"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+/// Engine evaluation of a move, as written in comments by engine matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineScore {
+    Centipawns(i32),
+    // Moves until checkmate, negative when being mated
+    Mate(i32),
+}
+
+/// Engine output attached to a move, such as `+0.35/12 1.2s`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EngineComment {
+    pub score: EngineScore,
+    pub depth: u8,
+    pub time: Duration,
+}
+
+impl fmt::Display for EngineComment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.score {
+            EngineScore::Centipawns(cp) => write!(f, "{:+.2}", cp as f64 / 100.0)?,
+            EngineScore::Mate(moves) if moves < 0 => write!(f, "-M{}", -moves)?,
+            EngineScore::Mate(moves) => write!(f, "+M{}", moves)?,
+        }
+        write!(f, "/{} {:.1}s", self.depth, self.time.as_secs_f64())
+    }
+}
+
+impl FromStr for EngineComment {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (score, rest) = s.trim().split_once('/').ok_or(())?;
+        let (depth, time) = rest.split_once(' ').ok_or(())?;
+
+        let score = match score.split_once('M') {
+            Some((sign, moves)) => {
+                let moves: i32 = moves.parse().map_err(|_| ())?;
+                EngineScore::Mate(if sign == "-" { -moves } else { moves })
+            }
+            None => {
+                let pawns: f64 = score.parse().map_err(|_| ())?;
+                EngineScore::Centipawns((pawns * 100.0).round() as i32)
+            }
+        };
+        let seconds: f64 = time.trim_end_matches('s').parse().map_err(|_| ())?;
+
+        Ok(EngineComment {
+            score,
+            depth: depth.parse().map_err(|_| ())?,
+            time: Duration::try_from_secs_f64(seconds).map_err(|_| ())?,
+        })
+    }
+}
+
+/// Parses a SAN move and checks that it is legal in the position.
+fn parse_move(position: &Chess, san: &str) -> Result<Move, PgnError> {
+    san.parse::<SanPlus>()
+        .ok()
+        .and_then(|san| san.san.to_move(position).ok())
+        .ok_or_else(|| PgnError::IllegalMove(san.into()))
+}
+
+/// Starting position described by a FEN tag.
+fn parse_fen(fen: &str) -> Result<Chess, PgnError> {
+    fen.parse::<Fen>()
+        .ok()
+        .and_then(|fen| fen.into_position(CastlingMode::Standard).ok())
+        .ok_or_else(|| PgnError::InvalidFen(fen.into()))
+}
@@ -0,0 +1,273 @@
+use super::{Game, Node, PgnError, parse_fen, parse_move};
+use shakmaty::{Chess, Outcome, Position};
+use std::iter::{self, Peekable};
+use std::str::Chars;
+
+// Suffix annotations and the NAGs they stand for
+const SUFFIXES: [(&str, u8); 6] = [
+    ("!", 1),
+    ("?", 2),
+    ("!!", 3),
+    ("??", 4),
+    ("!?", 5),
+    ("?!", 6),
+];
+
+/// A lexical element of PGN text.
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(String),
+    StartVariation,
+    EndVariation,
+    Termination(Outcome),
+    Move(String),
+}
+
+/// A line being read, with the positions needed to continue it or to branch off it.
+struct Frame {
+    line: Vec<Node>,
+    position: Chess,
+    // Position before the last move of the line, where its variations start
+    before: Chess,
+    // Comment waiting for the first move of the line
+    comment: Option<String>,
+}
+
+impl Frame {
+    fn new(position: Chess) -> Self {
+        Frame {
+            line: Vec::new(),
+            before: position.clone(),
+            position,
+            comment: None,
+        }
+    }
+}
+
+/// Reads every game of a PGN text. Games are read independently, so an invalid game does not
+/// affect the following ones.
+pub fn read_games(text: &str) -> Vec<Result<Game, PgnError>> {
+    let mut games = Vec::new();
+    let mut tokens = Vec::new();
+    let mut movetext = false;
+
+    for token in tokenize(text) {
+        match token {
+            // A tag after the moves starts the next game, if the termination marker is missing
+            Token::Tag(..) if movetext => {
+                games.push(build_game(std::mem::take(&mut tokens)));
+                movetext = false;
+            }
+            Token::Tag(..) => (),
+            _ => movetext = true,
+        }
+
+        let terminated = matches!(token, Token::Termination(_));
+        tokens.push(token);
+        if terminated {
+            games.push(build_game(std::mem::take(&mut tokens)));
+            movetext = false;
+        }
+    }
+
+    if !tokens.is_empty() {
+        games.push(build_game(tokens));
+    }
+
+    games
+}
+
+/// Builds the move tree of a game from its tokens.
+fn build_game(tokens: Vec<Token>) -> Result<Game, PgnError> {
+    let mut game = Game {
+        tags: Vec::new(),
+        initial: Chess::default(),
+        moves: Vec::new(),
+    };
+    // Lines being read, from the main line to the innermost variation
+    let mut stack = vec![Frame::new(Chess::default())];
+
+    for token in tokens {
+        let frame = stack.last_mut().unwrap();
+
+        match token {
+            Token::Tag(name, value) => {
+                if name == "FEN" {
+                    game.initial = parse_fen(&value)?;
+                    *frame = Frame::new(game.initial.clone());
+                }
+                game.set_tag(&name, &value);
+            }
+            Token::Move(san) => {
+                let mv = parse_move(&frame.position, &san)?;
+                let mut node = Node::new(mv);
+                node.starting_comment = frame.comment.take();
+
+                frame.before = frame.position.clone();
+                frame.position.play_unchecked(mv);
+                frame.line.push(node);
+            }
+            Token::Comment(text) => {
+                let comment = match frame.line.last_mut() {
+                    Some(node) => &mut node.comment,
+                    None => &mut frame.comment,
+                };
+                append_comment(comment, &text);
+            }
+            Token::Nag(nag) => {
+                let value = nag.parse().map_err(|_| PgnError::InvalidNag(nag.clone()))?;
+                let node = frame.line.last_mut().ok_or(PgnError::InvalidNag(nag))?;
+                node.nags.push(value);
+            }
+            Token::StartVariation => {
+                // Variations replace the last move, so one must have been played
+                if frame.line.is_empty() {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+                let before = frame.before.clone();
+                stack.push(Frame::new(before));
+            }
+            Token::EndVariation => {
+                if stack.len() == 1 {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+                let variation = stack.pop().unwrap().line;
+                if !variation.is_empty() {
+                    let parent = stack.last_mut().unwrap();
+                    parent.line.last_mut().unwrap().variations.push(variation);
+                }
+            }
+            Token::Termination(outcome) => {
+                if game.tag("Result").is_none() {
+                    game.set_result(outcome);
+                }
+            }
+        }
+    }
+
+    if stack.len() > 1 {
+        return Err(PgnError::UnbalancedVariation);
+    }
+    if game.tags.is_empty() && stack[0].line.is_empty() {
+        return Err(PgnError::Empty);
+    }
+
+    game.moves = stack.pop().unwrap().line;
+    Ok(game)
+}
+
+fn append_comment(comment: &mut Option<String>, text: &str) {
+    match comment {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(text);
+        }
+        None => *comment = Some(text.into()),
+    }
+}
+
+/// Splits PGN text into tokens, skipping move numbers and escaped lines.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            // Lines starting with a percent sign are escaped from parsing
+            '%' if line_start => {
+                chars.by_ref().find(|&c| c == '\n');
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => (),
+            '{' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                tokens.push(Token::Comment(comment.trim().into()));
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().into()));
+                line_start = true;
+                continue;
+            }
+            '(' => tokens.push(Token::StartVariation),
+            ')' => tokens.push(Token::EndVariation),
+            '[' => tokens.push(read_tag(&mut chars)),
+            '$' => {
+                let nag = iter::from_fn(|| chars.next_if(char::is_ascii_digit)).collect();
+                tokens.push(Token::Nag(nag));
+            }
+            c => {
+                let symbol: String = iter::once(c)
+                    .chain(iter::from_fn(|| {
+                        chars.next_if(|&c| !c.is_whitespace() && !"{};()[]$".contains(c))
+                    }))
+                    .collect();
+                read_symbol(&symbol, &mut tokens);
+            }
+        }
+
+        line_start = c == '\n';
+    }
+
+    tokens
+}
+
+/// Reads a tag pair such as `[Event "Casual game"]`, after its opening bracket.
+fn read_tag(chars: &mut Peekable<Chars>) -> Token {
+    let name =
+        iter::from_fn(|| chars.next_if(|&c| !c.is_whitespace() && !"\"]".contains(c))).collect();
+    while chars.next_if(|&c| c != '"' && c != ']').is_some() {}
+
+    let mut value = String::new();
+    if chars.next_if_eq(&'"').is_some() {
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => value.extend(chars.next()),
+                '"' => break,
+                c => value.push(c),
+            }
+        }
+    }
+    chars.by_ref().find(|&c| c == ']');
+
+    Token::Tag(name, value)
+}
+
+/// Reads a move, move number or game termination marker.
+fn read_symbol(symbol: &str, tokens: &mut Vec<Token>) {
+    if let Ok(outcome) = symbol.parse::<Outcome>() {
+        tokens.push(Token::Termination(outcome));
+        return;
+    }
+
+    // Move numbers may be attached to the move, as in 1.e4 or 12...Nf6, while leading digits
+    // without a period are castling written with zeros, as in 0-0
+    let unnumbered = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    let symbol = if unnumbered.is_empty() || unnumbered.starts_with('.') {
+        unnumbered.trim_start_matches('.')
+    } else {
+        symbol
+    };
+    if symbol.is_empty() {
+        return;
+    }
+
+    let san = symbol.trim_end_matches(['!', '?']);
+    if san.starts_with("0-0") {
+        tokens.push(Token::Move(san.replace('0', "O")));
+    } else {
+        tokens.push(Token::Move(san.into()));
+    }
+
+    let suffix = &symbol[san.len()..];
+    if !suffix.is_empty() {
+        let nag = SUFFIXES
+            .iter()
+            .find(|&&(text, _)| text == suffix)
+            .map_or(suffix.to_string(), |(_, nag)| nag.to_string());
+        tokens.push(Token::Nag(nag));
+    }
+}
@@ -0,0 +1,149 @@
+//! Generation of the king and pawn versus king bitbase.
+//!
+//! This module is only compiled into the build script, which embeds the bitbase into the
+//! binary. It only depends on shakmaty and the index module next to it.
+
+use super::index::{MAX_INDEX, index};
+use shakmaty::{
+    Bitboard, Color, File, Rank, Square,
+    attacks::{king_attacks, pawn_attacks},
+};
+
+// Classification of a position during generation
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+/// Generates the bitbase by retrograde analysis, one bit per index.
+///
+/// Positions are first classified by simple rules, after which unknown positions are resolved
+/// from their successors until nothing changes. Positions still unknown at that point are draws.
+pub fn generate() -> Vec<u8> {
+    let mut db: Vec<u8> = (0..MAX_INDEX).map(initial_classification).collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for idx in 0..MAX_INDEX {
+            if db[idx] == UNKNOWN {
+                let result = classify(&db, idx);
+                if result != UNKNOWN {
+                    db[idx] = result;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let mut bits = vec![0u8; MAX_INDEX / 8];
+    for (idx, &result) in db.iter().enumerate() {
+        if result == WIN {
+            bits[idx / 8] |= 1 << (idx % 8);
+        }
+    }
+    bits
+}
+
+/// Decodes an index into side to move, black king, white king and pawn squares.
+fn decode(idx: usize) -> (Color, Square, Square, Square) {
+    let white_king = Square::new((idx & 0x3f) as u32);
+    let black_king = Square::new(((idx >> 6) & 0x3f) as u32);
+    let turn = if (idx >> 12) & 1 == 0 {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let file = File::new(((idx >> 13) & 0x3) as u32);
+    let rank = Rank::new(Rank::Seventh as u32 - ((idx >> 15) & 0x7) as u32);
+
+    (
+        turn,
+        black_king,
+        white_king,
+        Square::from_coords(file, rank),
+    )
+}
+
+fn initial_classification(idx: usize) -> u8 {
+    let (turn, black_king, white_king, pawn) = decode(idx);
+    let push = pawn.offset(8).unwrap();
+
+    // Kings touching, pieces on the same square or black in check with white to move
+    if white_king.distance(black_king) <= 1
+        || white_king == pawn
+        || black_king == pawn
+        || (turn == Color::White && pawn_attacks(Color::White, pawn).contains(black_king))
+    {
+        return INVALID;
+    }
+
+    // Safe promotion
+    if turn == Color::White
+        && pawn.rank() == Rank::Seventh
+        && white_king != push
+        && black_king != push
+        && (black_king.distance(push) > 1 || white_king.distance(push) == 1)
+    {
+        return WIN;
+    }
+
+    // Stalemate, or the pawn can be captured
+    if turn == Color::Black {
+        let guarded = king_attacks(white_king) | pawn_attacks(Color::White, pawn);
+        let escapes = king_attacks(black_king) & !guarded;
+        let capture = king_attacks(black_king) & !king_attacks(white_king) & Bitboard::from(pawn);
+        if escapes.is_empty() || capture.any() {
+            return DRAW;
+        }
+    }
+
+    UNKNOWN
+}
+
+fn classify(db: &[u8], idx: usize) -> u8 {
+    let (turn, black_king, white_king, pawn) = decode(idx);
+    let mut results = 0;
+
+    match turn {
+        Color::White => {
+            for to in king_attacks(white_king) {
+                results |= db[index(Color::Black, black_king, to, pawn)];
+            }
+
+            // Single and double pawn pushes
+            let push = pawn.offset(8).unwrap();
+            if pawn.rank() < Rank::Seventh && push != white_king && push != black_king {
+                results |= db[index(Color::Black, black_king, white_king, push)];
+
+                let double = push.offset(8).unwrap();
+                if pawn.rank() == Rank::Second && double != white_king && double != black_king {
+                    results |= db[index(Color::Black, black_king, white_king, double)];
+                }
+            }
+
+            // White needs a single winning move, a draw requires every move to draw
+            if results & WIN != 0 {
+                WIN
+            } else if results & UNKNOWN != 0 {
+                UNKNOWN
+            } else {
+                DRAW
+            }
+        }
+        Color::Black => {
+            for to in king_attacks(black_king) {
+                results |= db[index(Color::White, to, white_king, pawn)];
+            }
+
+            // Black needs a single drawing move, a win requires every move to lose
+            if results & DRAW != 0 {
+                DRAW
+            } else if results & UNKNOWN != 0 {
+                UNKNOWN
+            } else {
+                WIN
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+//! Indexing of normalised king and pawn versus king positions, shared by the library and the
+//! bitbase generator in the build script.
+
+use shakmaty::{Color, Rank, Square};
+
+/// Number of normalised positions: side to move x pawn (24 squares) x black king x white king.
+pub const MAX_INDEX: usize = 2 * 24 * 64 * 64;
+
+/// Index of a normalised position, with the pawn on files a-d and ranks 2-7.
+pub fn index(turn: Color, black_king: Square, white_king: Square, pawn: Square) -> usize {
+    let side = turn.fold_wb(0, 1);
+    let file = pawn.file() as usize;
+    let rank = Rank::Seventh as usize - pawn.rank() as usize;
+
+    white_king as usize | (black_king as usize) << 6 | side << 12 | file << 13 | rank << 15
+}
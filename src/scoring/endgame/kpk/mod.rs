@@ -0,0 +1,20 @@
+//! King and pawn versus king bitbase.
+//!
+//! Positions are normalised so the pawn is white and on files a-d. Every position is then
+//! indexed by the side to move, both king squares and the pawn square, and stored as a single
+//! bit telling whether white wins. The bitbase is generated by the build script.
+
+use shakmaty::{Color, Square};
+
+mod index;
+
+pub use index::{MAX_INDEX, index};
+
+// Bitbase generated at build time, one bit per index
+static BITBASE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/kpk.bin"));
+
+/// Whether white wins a normalised position.
+pub fn probe(turn: Color, black_king: Square, white_king: Square, pawn: Square) -> bool {
+    let idx = index(turn, black_king, white_king, pawn);
+    BITBASE[idx / 8] & (1 << (idx % 8)) != 0
+}
@@ -0,0 +1,357 @@
+//! Specialised evaluation of endgames.
+//!
+//! Some material configurations are known wins or draws which the generic evaluation gets
+//! wrong. These are handled by dedicated evaluators, looked up by the material on the board.
+//! Other configurations are merely drawish, and have the generic score scaled down instead.
+
+use super::piece_value;
+use crate::Score;
+use shakmaty::{Bitboard, Chess, Color, File, Position, Rank, Role, Square, attacks};
+use std::{collections::HashMap, sync::OnceLock};
+
+pub mod kpk;
+
+/// Base score of a position the strong side is known to win.
+pub const KNOWN_WIN: Score = 10_000;
+
+/// Scale factor leaving the score unchanged, scale factors are expressed in 64ths.
+pub const SCALE_NORMAL: i32 = 64;
+
+// Most pieces on the board for any registered endgame
+const MAX_ENDGAME_PIECES: usize = 4;
+
+/// Score of a position from the strong side's perspective, None if the generic evaluation
+/// should be used.
+type EndgameFn = fn(&Chess, Color) -> Option<Score>;
+
+/// A dedicated evaluator for a single material configuration.
+pub struct Endgame {
+    pub name: &'static str,
+    // Side with the extra material
+    pub strong: Color,
+    eval: EndgameFn,
+}
+
+impl Endgame {
+    /// Evaluates a position from the perspective of the side to move.
+    pub fn evaluate(&self, position: &Chess) -> Option<Score> {
+        let score = (self.eval)(position, self.strong)?;
+        Some(if position.turn() == self.strong {
+            score
+        } else {
+            -score
+        })
+    }
+}
+
+/// Looks up the specialised evaluator of the material in a position.
+pub fn probe(position: &Chess) -> Option<&'static Endgame> {
+    if position.board().occupied().count() > MAX_ENDGAME_PIECES {
+        return None;
+    }
+
+    registry().get(&material_key(position))
+}
+
+/// Specialised score of a position from the perspective of the side to move.
+pub fn evaluate(position: &Chess) -> Option<Score> {
+    probe(position)?.evaluate(position)
+}
+
+/// Scales a generic score, given from the perspective of the side to move, by the drawishness
+/// of the material on the board.
+pub fn scale(position: &Chess, score: Score) -> Score {
+    score * scale_factor(position, score) / SCALE_NORMAL
+}
+
+/// Scale factor of a position, in 64ths of the generic score.
+///
+/// The side ahead according to the score is the one whose winning chances are judged.
+pub fn scale_factor(position: &Chess, score: Score) -> i32 {
+    let board = position.board();
+    let strong = if score >= 0 {
+        position.turn()
+    } else {
+        !position.turn()
+    };
+    let weak = !strong;
+
+    // Without pawns, a small material advantage is rarely enough to win
+    let strong_material = non_pawn_material(position, strong);
+    let weak_material = non_pawn_material(position, weak);
+    let strong_pawns = (board.pawns() & board.by_color(strong)).count();
+    if strong_pawns == 0 && strong_material - weak_material <= piece_value(Role::Bishop) {
+        if strong_material < piece_value(Role::Rook) {
+            return 0;
+        }
+        if weak_material <= piece_value(Role::Bishop) {
+            return 4;
+        }
+        return 14;
+    }
+
+    // Bishops of opposite colours without other pieces
+    let bishops = board.bishops();
+    let others = board.knights() | board.rooks() | board.queens();
+    if others.is_empty()
+        && (bishops & board.white()).count() == 1
+        && (bishops & board.black()).count() == 1
+        && (bishops & Bitboard::DARK_SQUARES).count() == 1
+    {
+        return SCALE_NORMAL / 2;
+    }
+
+    SCALE_NORMAL
+}
+
+/// Registered endgames keyed by material.
+fn registry() -> &'static HashMap<u64, Endgame> {
+    static REGISTRY: OnceLock<HashMap<u64, Endgame>> = OnceLock::new();
+
+    REGISTRY.get_or_init(|| {
+        let endgames: [(&'static str, EndgameFn); 6] = [
+            ("KQK", mate_lone_king),
+            ("KRK", mate_lone_king),
+            ("KBNK", bishop_knight_mate),
+            ("KPK", king_pawn_king),
+            ("KNNK", draw),
+            ("KBPK", wrong_rook_pawn),
+        ];
+
+        let mut registry = HashMap::new();
+        for (name, eval) in endgames {
+            for strong in Color::ALL {
+                registry.insert(code_key(name, strong), Endgame { name, strong, eval });
+            }
+        }
+        registry
+    })
+}
+
+/// Material key of a position, packing the number of each non-king piece into four bits.
+fn material_key(position: &Chess) -> u64 {
+    let board = position.board();
+    let mut key = 0;
+
+    for color in Color::ALL {
+        for role in Role::ALL {
+            let count = (board.by_color(color) & board.by_role(role)).count() as u64;
+            key += count << key_shift(color, role);
+        }
+    }
+
+    key
+}
+
+/// Material key of an endgame code such as "KBNK", where the first king starts the pieces of
+/// the strong side.
+fn code_key(code: &str, strong: Color) -> u64 {
+    let mut key = 0;
+    let mut color = !strong;
+
+    for c in code.chars() {
+        // Each king switches to the pieces of the other side
+        if c == 'K' {
+            color = !color;
+        }
+        // Codes are written by hand, so unknown characters are a programming error
+        let role = Role::from_char(c.to_ascii_lowercase()).unwrap();
+        key += 1 << key_shift(color, role);
+    }
+
+    key
+}
+
+fn key_shift(color: Color, role: Role) -> u32 {
+    (role as u32 - 1) * 4 + color.fold_wb(0, 24)
+}
+
+fn non_pawn_material(position: &Chess, color: Color) -> Score {
+    let board = position.board();
+    [Role::Knight, Role::Bishop, Role::Rook, Role::Queen]
+        .into_iter()
+        .map(|role| (board.by_piece(role.of(color))).count() as Score * piece_value(role))
+        .sum()
+}
+
+/// Material of one side, excluding the king.
+fn material(position: &Chess, color: Color) -> Score {
+    non_pawn_material(position, color)
+        + position.board().by_piece(color.pawn()).count() as Score * piece_value(Role::Pawn)
+}
+
+fn kings(position: &Chess, strong: Color) -> (Square, Square) {
+    // Legal positions always have exactly one king per side
+    let board = position.board();
+    (
+        board.king_of(strong).unwrap(),
+        board.king_of(!strong).unwrap(),
+    )
+}
+
+/// Bonus for keeping the weak king on the edge of the board.
+fn push_to_edge(square: Square) -> Score {
+    let file = square.file() as i32;
+    let rank = square.rank() as i32;
+    let edge_distance = file.min(7 - file) + rank.min(7 - rank);
+    (6 - edge_distance) * 20
+}
+
+/// Bonus for bringing the kings close together.
+fn push_close(a: Square, b: Square) -> Score {
+    (7 - a.distance(b) as Score) * 10
+}
+
+/// Whether the weak king is to move and can capture an undefended piece, which leaves the
+/// position to the search.
+fn hangs_piece(position: &Chess, strong: Color) -> bool {
+    if position.turn() == strong {
+        return false;
+    }
+
+    let board = position.board();
+    let (_, weak_king) = kings(position, strong);
+    // Defenders may stand behind the weak king, which leaves its square when capturing
+    let occupied = board.occupied().without(weak_king);
+    let targets = attacks::king_attacks(weak_king) & board.by_color(strong);
+    targets
+        .into_iter()
+        .any(|square| board.attacks_to(square, strong, occupied).is_empty())
+}
+
+/// King and a major piece against a lone king: drive the king to the edge and mate it there.
+fn mate_lone_king(position: &Chess, strong: Color) -> Option<Score> {
+    if hangs_piece(position, strong) {
+        return None;
+    }
+    let (strong_king, weak_king) = kings(position, strong);
+
+    Some(
+        KNOWN_WIN
+            + material(position, strong)
+            + push_to_edge(weak_king)
+            + push_close(strong_king, weak_king),
+    )
+}
+
+/// King, bishop and knight against a lone king: mate is only possible in a corner of the
+/// bishop's colour.
+fn bishop_knight_mate(position: &Chess, strong: Color) -> Option<Score> {
+    if hangs_piece(position, strong) {
+        return None;
+    }
+    let (strong_king, weak_king) = kings(position, strong);
+    let bishop = position.board().bishops().first()?;
+
+    let corners = if bishop.is_dark() {
+        [Square::A1, Square::H8]
+    } else {
+        [Square::A8, Square::H1]
+    };
+    let corner_distance = corners
+        .into_iter()
+        .map(|corner| manhattan(weak_king, corner))
+        .min()
+        .unwrap_or_default();
+
+    Some(
+        KNOWN_WIN
+            + material(position, strong)
+            + (14 - corner_distance) * 20
+            + push_close(strong_king, weak_king),
+    )
+}
+
+/// King and pawn against king, decided by the bitbase.
+fn king_pawn_king(position: &Chess, strong: Color) -> Option<Score> {
+    let pawn = position.board().pawns().first()?;
+
+    if kpk_probe(position)? == Wdl::Draw {
+        return Some(0);
+    }
+
+    // Prefer advancing the pawn once the win is certain
+    let relative_rank = strong.fold_wb(pawn.rank() as Score, 7 - pawn.rank() as Score);
+    Some(KNOWN_WIN + piece_value(Role::Pawn) + relative_rank * 10)
+}
+
+/// Exact result of a game, from the perspective of the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wdl {
+    Loss,
+    Draw,
+    Win,
+}
+
+/// Probes the KPK bitbase, returning None unless the position is king and pawn versus king.
+pub fn kpk_probe(position: &Chess) -> Option<Wdl> {
+    let board = position.board();
+    if board.occupied().count() != 3 || board.pawns().count() != 1 {
+        return None;
+    }
+
+    let pawn = board.pawns().first()?;
+    let strong = board.color_at(pawn)?;
+    let (strong_king, weak_king) = kings(position, strong);
+
+    // Normalise so the strong side is white with the pawn on files a-d
+    let flip_file = strong.fold_wb(pawn, pawn.flip_vertical()).file() > File::D;
+    let normalise = |square: Square| {
+        let square = strong.fold_wb(square, square.flip_vertical());
+        if flip_file {
+            square.flip_horizontal()
+        } else {
+            square
+        }
+    };
+    let strong_to_move = position.turn() == strong;
+    let turn = if strong_to_move {
+        Color::White
+    } else {
+        Color::Black
+    };
+
+    let wins = kpk::probe(
+        turn,
+        normalise(weak_king),
+        normalise(strong_king),
+        normalise(pawn),
+    );
+
+    Some(match (wins, strong_to_move) {
+        (false, _) => Wdl::Draw,
+        (true, true) => Wdl::Win,
+        (true, false) => Wdl::Loss,
+    })
+}
+
+/// Material configurations which can not be won.
+fn draw(_position: &Chess, _strong: Color) -> Option<Score> {
+    Some(0)
+}
+
+/// King, bishop and rook pawn against king is a draw if the bishop does not control the
+/// promotion square and the defending king reaches the corner.
+fn wrong_rook_pawn(position: &Chess, strong: Color) -> Option<Score> {
+    let board = position.board();
+    let (_, weak_king) = kings(position, strong);
+    let pawn = board.pawns().first()?;
+    let bishop = board.bishops().first()?;
+
+    if pawn.file() != File::A && pawn.file() != File::H {
+        return None;
+    }
+
+    let queening = Square::from_coords(pawn.file(), strong.fold_wb(Rank::Eighth, Rank::First));
+    if bishop.is_dark() != queening.is_dark() && weak_king.distance(queening) <= 1 {
+        return Some(0);
+    }
+
+    None
+}
+
+fn manhattan(a: Square, b: Square) -> Score {
+    let files = (a.file() as Score - b.file() as Score).abs();
+    let ranks = (a.rank() as Score - b.rank() as Score).abs();
+    files + ranks
+}
@@ -0,0 +1,93 @@
+use super::{BoardChanges, board_changes, phase_value, piece_value, tapered_pst};
+use crate::Score;
+use shakmaty::{Board, Chess, Color, Move, Piece, Position, Square};
+
+/// Material, tapered PST sum and game phase of a position, updated move by move.
+///
+/// All sums are from white's perspective. Building the state from scratch and updating it
+/// along a line of moves must always give the same result.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IncrementalEval {
+    material: Score,
+    pst: Score,
+    phase: i32,
+}
+
+impl IncrementalEval {
+    /// Computes the state by scanning the full board.
+    pub fn new(position: &Chess) -> Self {
+        let mut state = IncrementalEval::default();
+        for (_, piece) in position.board() {
+            state.material += piece.color.fold_wb(1, -1) * piece_value(piece.role);
+            state.phase += phase_value(piece.role);
+        }
+        state.pst = pst_sum(position.board(), state.phase);
+        state
+    }
+
+    /// Returns the state after playing a legal move in the given position.
+    pub fn play(&self, position: &Chess, mv: Move) -> Self {
+        let mut state = *self;
+        state.update(position, mv);
+        state
+    }
+
+    /// Updates the state in place for a legal move played in the given position.
+    pub fn update(&mut self, position: &Chess, mv: Move) {
+        let changes = board_changes(position, mv);
+        if self.apply(&changes, 1) {
+            self.pst = pst_sum(&changes.apply(position.board()), self.phase);
+        }
+    }
+
+    /// Reverts `update` with the same move and position.
+    pub fn undo(&mut self, position: &Chess, mv: Move) {
+        let changes = board_changes(position, mv);
+        if self.apply(&changes, -1) {
+            self.pst = pst_sum(position.board(), self.phase);
+        }
+    }
+
+    /// Material and tapered PST score from the perspective of the side to move.
+    pub fn score(&self, turn: Color) -> Score {
+        let score = self.material + self.pst;
+        turn.fold_wb(score, -score)
+    }
+
+    /// Game phase, ranging from 0 (bare kings and pawns) to 24 (all pieces on the board).
+    pub fn phase(&self) -> i32 {
+        self.phase
+    }
+
+    /// Applies the changes of a move forwards (`direction` 1) or backwards (-1).
+    ///
+    /// Every piece is tapered on its own, so a change of phase changes the PST value of all
+    /// pieces. Returns whether the phase changed, in which case the PST sum must be rebuilt.
+    fn apply(&mut self, changes: &BoardChanges, direction: i32) -> bool {
+        let phase = self.phase;
+        for &(piece, square) in changes.removed.iter().flatten() {
+            self.change(piece, square, -direction, phase);
+        }
+        for &(piece, square) in changes.added.iter().flatten() {
+            self.change(piece, square, direction, phase);
+        }
+        self.phase != phase
+    }
+
+    /// Adds (`sign` 1) or removes (-1) a piece, with its PST value tapered for `phase`.
+    fn change(&mut self, piece: Piece, square: Square, sign: i32, phase: i32) {
+        let color_sign = sign * piece.color.fold_wb(1, -1);
+
+        self.material += color_sign * piece_value(piece.role);
+        self.pst += color_sign * tapered_pst(piece, square, phase);
+        self.phase += sign * phase_value(piece.role);
+    }
+}
+
+/// Sum of the PST values of all pieces from white's perspective, each tapered on its own.
+fn pst_sum(board: &Board, phase: i32) -> Score {
+    board
+        .iter()
+        .map(|(square, piece)| piece.color.fold_wb(1, -1) * tapered_pst(piece, square, phase))
+        .sum()
+}
@@ -1,5 +1,14 @@
 use crate::{MATE_SCORE, Score};
-use shakmaty::{Chess, Color, Move, Position, Role};
+use shakmaty::{Board, Chess, Color, Move, Piece, Position, Role, Square};
+use std::sync::Arc;
+
+pub mod endgame;
+pub mod incremental;
+pub mod nnue;
+pub mod trace;
+
+use incremental::IncrementalEval;
+use nnue::{Accumulator, Network};
 
 // Constants
 const CAPTURE_BASE: i32 = 1_000_000;
@@ -10,55 +19,168 @@ const QUIET_BASE: i32 = 0;
 // Mobility weighting
 const MOBILITY_FACTOR: Score = 2;
 
+// Phase of the starting position (all pieces on the board)
+const MAX_PHASE: i32 = 24;
+
+/// Evaluation backend used by the search.
+#[derive(Clone, Default)]
+pub enum Evaluator {
+    // Handcrafted evaluation
+    #[default]
+    Classical,
+    // Neural network evaluation
+    Nnue(Arc<Network>),
+}
+
+/// Incrementally updated evaluation state of a search node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvalState {
+    Classical(IncrementalEval),
+    Nnue(Accumulator),
+}
+
+impl Evaluator {
+    /// Computes the evaluation state of a position from scratch.
+    pub fn state(&self, position: &Chess) -> EvalState {
+        match self {
+            Evaluator::Classical => EvalState::Classical(IncrementalEval::new(position)),
+            Evaluator::Nnue(network) => EvalState::Nnue(Accumulator::new(position, network)),
+        }
+    }
+
+    /// Updates the evaluation state in place for a legal move played in the given position.
+    pub fn make(&self, state: &mut EvalState, position: &Chess, mv: Move) {
+        match (self, state) {
+            (_, EvalState::Classical(eval)) => eval.update(position, mv),
+            (Evaluator::Nnue(network), EvalState::Nnue(acc)) => acc.update(position, mv, network),
+            (Evaluator::Classical, EvalState::Nnue(_)) => unreachable!("no network for state"),
+        }
+    }
+
+    /// Reverts `make` with the same move and position.
+    pub fn unmake(&self, state: &mut EvalState, position: &Chess, mv: Move) {
+        match (self, state) {
+            (_, EvalState::Classical(eval)) => eval.undo(position, mv),
+            (Evaluator::Nnue(network), EvalState::Nnue(acc)) => acc.undo(position, mv, network),
+            (Evaluator::Classical, EvalState::Nnue(_)) => unreachable!("no network for state"),
+        }
+    }
+
+    /// Evaluates a position from the perspective of the side to move.
+    pub fn evaluate(&self, position: &Chess, depth: u8, state: &EvalState) -> Score {
+        match (self, state) {
+            (_, EvalState::Classical(eval)) => evaluate_with(position, depth, eval),
+            (Evaluator::Nnue(network), EvalState::Nnue(acc)) => {
+                evaluate_common(position, depth, || network.evaluate(acc, position.turn()))
+            }
+            (Evaluator::Classical, EvalState::Nnue(_)) => unreachable!("no network for state"),
+        }
+    }
+}
+
 pub fn evaluate(position: &Chess, depth: u8) -> Score {
-    // Death -> Score quicker checkmates higher
+    evaluate_with(position, depth, &IncrementalEval::new(position))
+}
+
+/// Evaluates a position using incrementally maintained material and PST sums.
+pub fn evaluate_with(position: &Chess, depth: u8, state: &IncrementalEval) -> Score {
+    evaluate_common(position, depth, || {
+        // Material and PST blended term (midgame/endgame)
+        let mut score = state.score(position.turn());
+
+        // Mobility bonus
+        score += position.legal_moves().len() as Score * MOBILITY_FACTOR;
+        score
+    })
+}
+
+/// Handles checkmates, draws and specialised endgames before falling back to the scaled
+/// score of the evaluation backend.
+fn evaluate_common(position: &Chess, depth: u8, generic: impl FnOnce() -> Score) -> Score {
+    if let Some(score) = terminal_score(position, depth) {
+        return score;
+    }
+
+    if let Some(score) = endgame::evaluate(position) {
+        return score;
+    }
+
+    endgame::scale(position, generic())
+}
+
+/// Score of checkmates and draws, which is independent of the evaluation backend.
+fn terminal_score(position: &Chess, depth: u8) -> Option<Score> {
+    // Death -> Checkmates closer to the root (more depth remaining) score lower
     if position.is_checkmate() {
-        return MATE_SCORE + depth as i32 * 5;
+        return Some(MATE_SCORE - depth as i32 * 5);
     }
 
     // Draw
     if position.is_stalemate() || position.is_insufficient_material() {
-        return 0;
+        return Some(0);
     }
 
-    // Determine phase
-    let phase = game_phase(position);
-    let max_phase = 24;
-
-    // Score each piece
-    let mut score = 0;
-
-    for (square, piece) in position.board() {
-        let idx = match piece.color {
-            Color::White => square as usize,
-            Color::Black => mirror_idx(square as usize),
-        };
-
-        // Material term
-        let mat = piece_value(piece.role);
-
-        // PST blended term (midgame/endgame)
-        let pst = match piece.role {
-            Role::Pawn => blend_pst(PAWN_PST_MG[idx], PAWN_PST_EG[idx], phase, max_phase),
-            Role::Knight => blend_pst(KNIGHT_PST_MG[idx], KNIGHT_PST_EG[idx], phase, max_phase),
-            Role::Bishop => blend_pst(BISHOP_PST_MG[idx], BISHOP_PST_EG[idx], phase, max_phase),
-            Role::Rook => blend_pst(ROOK_PST_MG[idx], ROOK_PST_EG[idx], phase, max_phase),
-            Role::Queen => blend_pst(QUEEN_PST_MG[idx], QUEEN_PST_EG[idx], phase, max_phase),
-            Role::King => blend_pst(KING_PST_MG[idx], KING_PST_EG[idx], phase, max_phase),
-        };
-
-        // Add score
-        if piece.color == position.turn() {
-            score += mat + pst;
-        } else {
-            score -= mat + pst;
+    None
+}
+
+/// Pieces taken off and put on the board by a move.
+pub(crate) struct BoardChanges {
+    pub removed: [Option<(Piece, Square)>; 2],
+    pub added: [Option<(Piece, Square)>; 2],
+}
+
+impl BoardChanges {
+    /// Returns a copy of the board with the changes applied.
+    pub fn apply(&self, board: &Board) -> Board {
+        let mut board = board.clone();
+        for &(_, square) in self.removed.iter().flatten() {
+            board.discard_piece_at(square);
+        }
+        for &(piece, square) in self.added.iter().flatten() {
+            board.set_piece_at(square, piece);
         }
+        board
     }
+}
 
-    // Mobility bonus
-    score += position.legal_moves().len() as Score * MOBILITY_FACTOR;
-
-    return score;
+/// Determines which pieces a legal move removes from and adds to the board.
+pub(crate) fn board_changes(position: &Chess, mv: Move) -> BoardChanges {
+    let us = position.turn();
+
+    match mv {
+        Move::Normal {
+            role,
+            from,
+            capture,
+            to,
+            promotion,
+        } => BoardChanges {
+            removed: [Some((role.of(us), from)), capture.map(|c| (c.of(!us), to))],
+            added: [Some((promotion.unwrap_or(role).of(us), to)), None],
+        },
+        Move::EnPassant { from, to } => BoardChanges {
+            removed: [
+                Some((us.pawn(), from)),
+                Some(((!us).pawn(), Square::from_coords(to.file(), from.rank()))),
+            ],
+            added: [Some((us.pawn(), to)), None],
+        },
+        Move::Castle { king, rook } => {
+            // Castling side is always known for castling moves
+            let side = mv.castling_side().unwrap();
+            BoardChanges {
+                removed: [Some((us.king(), king)), Some((us.rook(), rook))],
+                added: [
+                    Some((us.king(), side.king_to(us))),
+                    Some((us.rook(), side.rook_to(us))),
+                ],
+            }
+        }
+        Move::Put { role, to } => BoardChanges {
+            removed: [None, None],
+            added: [Some((role.of(us), to)), None],
+        },
+    }
 }
 
 /// Best scoring move = lowest value to sort to start!
@@ -92,6 +214,29 @@ pub fn score_move(position: &Chess, mv: &Move) -> Score {
     return -QUIET_BASE;
 }
 
+/// Midgame and endgame PST values of a piece on a square.
+fn pst(piece: Piece, square: Square) -> (Score, Score) {
+    let idx = match piece.color {
+        Color::White => square as usize,
+        Color::Black => mirror_idx(square as usize),
+    };
+
+    match piece.role {
+        Role::Pawn => (PAWN_PST_MG[idx], PAWN_PST_EG[idx]),
+        Role::Knight => (KNIGHT_PST_MG[idx], KNIGHT_PST_EG[idx]),
+        Role::Bishop => (BISHOP_PST_MG[idx], BISHOP_PST_EG[idx]),
+        Role::Rook => (ROOK_PST_MG[idx], ROOK_PST_EG[idx]),
+        Role::Queen => (QUEEN_PST_MG[idx], QUEEN_PST_EG[idx]),
+        Role::King => (KING_PST_MG[idx], KING_PST_EG[idx]),
+    }
+}
+
+/// PST value of a piece on a square, tapered on its own for the given phase.
+fn tapered_pst(piece: Piece, square: Square, phase: i32) -> Score {
+    let (mg, eg) = pst(piece, square);
+    blend_pst(mg, eg, phase, MAX_PHASE)
+}
+
 fn piece_value(role: Role) -> Score {
     return match role {
         Role::Pawn => 100,
@@ -114,19 +259,23 @@ fn mirror_idx(idx: usize) -> usize {
 fn game_phase(position: &Chess) -> i32 {
     let mut phase = 0;
     for (_, piece) in position.board() {
-        phase += match piece.role {
-            Role::Pawn => 0,
-            Role::King => 0,
-            Role::Knight => 1,
-            Role::Bishop => 1,
-            Role::Rook => 2,
-            Role::Queen => 4,
-        };
+        phase += phase_value(piece.role);
     }
 
     return phase;
 }
 
+fn phase_value(role: Role) -> i32 {
+    return match role {
+        Role::Pawn => 0,
+        Role::King => 0,
+        Role::Knight => 1,
+        Role::Bishop => 1,
+        Role::Rook => 2,
+        Role::Queen => 4,
+    };
+}
+
 fn blend_pst(mg: Score, eg: Score, phase: i32, max_phase: i32) -> Score {
     return (mg * phase + eg * (max_phase - phase)) / max_phase;
 }
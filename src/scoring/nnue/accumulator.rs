@@ -0,0 +1,106 @@
+use super::{Network, feature_index};
+use crate::scoring::board_changes;
+use shakmaty::{Board, ByColor, Chess, Color, Move, Position, Role, Square};
+
+/// Hidden layer values of both perspectives, updated move by move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Accumulator {
+    values: ByColor<Vec<i16>>,
+}
+
+impl Accumulator {
+    /// Computes the accumulator of a position from scratch.
+    pub fn new(position: &Chess, network: &Network) -> Self {
+        let mut values = ByColor::new_with(|_| vec![0; network.hidden_size()]);
+        for color in Color::ALL {
+            refresh(&mut values[color], position.board(), color, network);
+        }
+        Accumulator { values }
+    }
+
+    /// Returns the accumulator after playing a legal move in the given position.
+    pub fn play(&self, position: &Chess, mv: Move, network: &Network) -> Self {
+        let mut acc = self.clone();
+        acc.update(position, mv, network);
+        acc
+    }
+
+    /// Updates the accumulator in place for a legal move played in the given position.
+    pub fn update(&mut self, position: &Chess, mv: Move, network: &Network) {
+        self.apply(position, mv, network, true);
+    }
+
+    /// Reverts `update` with the same move and position.
+    pub fn undo(&mut self, position: &Chess, mv: Move, network: &Network) {
+        self.apply(position, mv, network, false);
+    }
+
+    /// Hidden layer values seen from one side.
+    pub fn values(&self, perspective: Color) -> &[i16] {
+        &self.values[perspective]
+    }
+
+    fn apply(&mut self, position: &Chess, mv: Move, network: &Network, forward: bool) {
+        let us = position.turn();
+        let changes = board_changes(position, mv);
+        let (removed, added) = if forward {
+            (&changes.removed, &changes.added)
+        } else {
+            (&changes.added, &changes.removed)
+        };
+
+        for perspective in Color::ALL {
+            let values = &mut self.values[perspective];
+
+            // All features are relative to the king, so a king move changes every feature
+            if perspective == us && mv.role() == Role::King {
+                let board = if forward {
+                    changes.apply(position.board())
+                } else {
+                    position.board().clone()
+                };
+                refresh(values, &board, perspective, network);
+                continue;
+            }
+
+            // Otherwise the king of this perspective stays where it is
+            let king = own_king(position.board(), perspective);
+            for &(piece, square) in removed.iter().flatten() {
+                let weights = network.feature(feature_index(perspective, king, piece, square));
+                sub_weights(values, weights);
+            }
+            for &(piece, square) in added.iter().flatten() {
+                let weights = network.feature(feature_index(perspective, king, piece, square));
+                add_weights(values, weights);
+            }
+        }
+    }
+}
+
+/// Computes the hidden layer values of one perspective from scratch.
+fn refresh(values: &mut [i16], board: &Board, perspective: Color, network: &Network) {
+    let king = own_king(board, perspective);
+    values.copy_from_slice(&network.feature_bias);
+
+    for (square, piece) in board {
+        let weights = network.feature(feature_index(perspective, king, piece, square));
+        add_weights(values, weights);
+    }
+}
+
+fn own_king(board: &Board, color: Color) -> Square {
+    // Legal positions always have exactly one king per side
+    board.king_of(color).unwrap()
+}
+
+fn add_weights(values: &mut [i16], weights: &[i16]) {
+    for (value, &weight) in values.iter_mut().zip(weights) {
+        *value = value.wrapping_add(weight);
+    }
+}
+
+fn sub_weights(values: &mut [i16], weights: &[i16]) {
+    for (value, &weight) in values.iter_mut().zip(weights) {
+        *value = value.wrapping_sub(weight);
+    }
+}
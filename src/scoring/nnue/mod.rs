@@ -0,0 +1,153 @@
+//! Efficiently updatable neural network (NNUE) evaluation.
+//!
+//! The network is a single hidden layer with HalfKA-style input features: every piece on the
+//! board is encoded relative to the king of the viewing side, once from each side's
+//! perspective. The two hidden accumulators are kept up to date move by move, so inference
+//! only has to run the small output layer.
+//!
+//! Network files use the following little-endian layout:
+//!
+//! | Field           | Type  | Count              |
+//! |-----------------|-------|--------------------|
+//! | Magic `CM8N`    | u8    | 4                  |
+//! | Version (1)     | u32   | 1                  |
+//! | Hidden size `H` | u32   | 1                  |
+//! | Feature weights | i16   | `INPUTS * H`       |
+//! | Feature biases  | i16   | `H`                |
+//! | Output weights  | i16   | `2 * H`            |
+//! | Output bias     | i32   | 1                  |
+//!
+//! Feature weights are stored feature by feature, and the output weights for the side to
+//! move come before those of the other side. Hidden weights are quantised by 255, output
+//! weights by 64 and the output bias by both.
+
+use crate::Score;
+use shakmaty::{Color, Piece, Square};
+use std::{fs, io, path::Path};
+
+mod accumulator;
+
+pub use accumulator::Accumulator;
+
+/// Number of input features per perspective: king square x piece x square.
+pub const INPUTS: usize = 64 * 12 * 64;
+
+// Quantisation of the hidden layer and output layer
+const QA: i32 = 255;
+const QB: i32 = 64;
+
+// Conversion from network output to centipawns
+const SCALE: i64 = 400;
+
+// File format
+const MAGIC: &[u8; 4] = b"CM8N";
+const VERSION: u32 = 1;
+const MAX_HIDDEN: usize = 4096;
+
+/// Quantised network weights.
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_bias: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+impl Network {
+    /// Loads a network from a file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Network::from_bytes(&fs::read(path)?)
+    }
+
+    /// Parses a network from its file contents.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        if bytes.len() < 12 || &bytes[0..4] != MAGIC {
+            return Err(invalid("not a checkm8 network file"));
+        }
+        if read_u32(&bytes[4..8]) != VERSION {
+            return Err(invalid("unsupported network version"));
+        }
+
+        let hidden = read_u32(&bytes[8..12]) as usize;
+        if hidden == 0 || hidden > MAX_HIDDEN {
+            return Err(invalid("invalid hidden layer size"));
+        }
+
+        let weight_count = INPUTS * hidden + hidden + 2 * hidden;
+        if bytes.len() != 12 + 2 * weight_count + 4 {
+            return Err(invalid("network file has the wrong size"));
+        }
+
+        let mut weights = bytes[12..12 + 2 * weight_count]
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]));
+
+        let feature_weights = weights.by_ref().take(INPUTS * hidden).collect();
+        let feature_bias = weights.by_ref().take(hidden).collect();
+        let output_weights = weights.collect();
+        let output_bias = read_u32(&bytes[12 + 2 * weight_count..]) as i32;
+
+        Ok(Network {
+            hidden,
+            feature_weights,
+            feature_bias,
+            output_weights,
+            output_bias,
+        })
+    }
+
+    /// Size of the hidden layer of each perspective.
+    pub fn hidden_size(&self) -> usize {
+        self.hidden
+    }
+
+    /// Evaluates the accumulated hidden layer from the perspective of the side to move.
+    pub fn evaluate(&self, acc: &Accumulator, turn: Color) -> Score {
+        let (us_weights, them_weights) = self.output_weights.split_at(self.hidden);
+
+        let sum = dot_crelu(acc.values(turn), us_weights)
+            + dot_crelu(acc.values(!turn), them_weights)
+            + self.output_bias;
+
+        (sum as i64 * SCALE / (QA * QB) as i64) as Score
+    }
+
+    /// Weights of a single input feature.
+    fn feature(&self, index: usize) -> &[i16] {
+        &self.feature_weights[index * self.hidden..(index + 1) * self.hidden]
+    }
+}
+
+/// Index of the input feature for a piece, seen from one side's perspective.
+///
+/// Squares are mirrored for black so both sides see the board from their own first rank.
+fn feature_index(perspective: Color, king: Square, piece: Piece, square: Square) -> usize {
+    let orient = |square: Square| match perspective {
+        Color::White => square as usize,
+        Color::Black => square.flip_vertical() as usize,
+    };
+    let side = if piece.color == perspective { 0 } else { 6 };
+    let piece_idx = side + piece.role as usize - 1;
+
+    (orient(king) * 12 + piece_idx) * 64 + orient(square)
+}
+
+/// Dot product of clipped ReLU activations with output weights.
+///
+/// Written as a plain loop over equally sized slices so it is auto-vectorised.
+fn dot_crelu(values: &[i16], weights: &[i16]) -> i32 {
+    values
+        .iter()
+        .zip(weights)
+        .map(|(&v, &w)| {
+            let activation = (v as i32).clamp(0, QA);
+            activation * w as i32
+        })
+        .sum()
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
@@ -0,0 +1,218 @@
+use super::{
+    MAX_PHASE, MOBILITY_FACTOR,
+    endgame::{self, SCALE_NORMAL},
+    evaluate, game_phase, piece_value, pst, tapered_pst,
+};
+use crate::Score;
+use shakmaty::{ByColor, ByRole, Chess, Color, Position, Role};
+use std::fmt;
+
+/// A single evaluation term, split into its midgame and endgame parts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Term {
+    pub mg: Score,
+    pub eg: Score,
+    /// Value for the phase of the position, tapered piece by piece like in [`evaluate`]
+    pub blended: Score,
+}
+
+impl Term {
+    /// A term which does not depend on the game phase.
+    fn flat(value: Score) -> Self {
+        Term {
+            mg: value,
+            eg: value,
+            blended: value,
+        }
+    }
+
+    fn add(&mut self, other: Term) {
+        self.mg += other.mg;
+        self.eg += other.eg;
+        self.blended += other.blended;
+    }
+
+    fn sub(&mut self, other: Term) {
+        self.mg -= other.mg;
+        self.eg -= other.eg;
+        self.blended -= other.blended;
+    }
+}
+
+/// Breakdown of the static evaluation of a position, term by term.
+///
+/// All terms are stored from the perspective of the side they belong to.
+#[derive(Clone, Debug)]
+pub struct EvalTrace {
+    pub turn: Color,
+    pub phase: i32,
+    pub material: ByColor<Term>,
+    pub pst: ByColor<ByRole<Term>>,
+    pub mobility: ByColor<Term>,
+    /// Score of a checkmate or draw, which replaces all other terms
+    pub terminal: Option<Score>,
+    /// Name and score of a specialised endgame evaluator, which replaces all other terms
+    pub endgame: Option<(&'static str, Score)>,
+    /// Scale factor applied to the score, in 64ths
+    pub scale: i32,
+}
+
+impl EvalTrace {
+    pub fn new(position: &Chess) -> Self {
+        let mut trace = EvalTrace {
+            turn: position.turn(),
+            phase: game_phase(position),
+            material: ByColor::default(),
+            pst: ByColor::default(),
+            mobility: ByColor::default(),
+            terminal: None,
+            endgame: None,
+            scale: SCALE_NORMAL,
+        };
+
+        // Checkmates and draws are scored without looking at the pieces
+        if position.is_game_over() {
+            trace.terminal = Some(evaluate(position, 0));
+        }
+
+        for (square, piece) in position.board() {
+            trace.material[piece.color].add(Term::flat(piece_value(piece.role)));
+
+            let (mg, eg) = pst(piece, square);
+            let blended = tapered_pst(piece, square, trace.phase);
+            trace.pst[piece].add(Term { mg, eg, blended });
+        }
+
+        // Mobility is only counted for the side to move
+        trace.mobility[trace.turn] =
+            Term::flat(position.legal_moves().len() as Score * MOBILITY_FACTOR);
+
+        if let Some(specialised) = endgame::probe(position) {
+            trace.endgame = specialised
+                .evaluate(position)
+                .map(|score| (specialised.name, score));
+        }
+
+        let score = trace.white_score();
+        trace.scale = endgame::scale_factor(position, trace.turn.fold_wb(score, -score));
+
+        trace
+    }
+
+    /// Sum of all terms of one side.
+    pub fn side_total(&self, color: Color) -> Term {
+        let mut total = self.material[color];
+        for term in self.pst[color].iter() {
+            total.add(*term);
+        }
+        total.add(self.mobility[color]);
+        total
+    }
+
+    /// The tapered score from white's perspective, ignoring terminal positions.
+    pub fn white_score(&self) -> Score {
+        self.side_total(Color::White).blended - self.side_total(Color::Black).blended
+    }
+
+    /// The final score from the perspective of the side to move, as returned by [`evaluate`].
+    pub fn score(&self) -> Score {
+        if let Some(score) = self.terminal {
+            return score;
+        }
+        if let Some((_, score)) = self.endgame {
+            return score;
+        }
+        let score = self.white_score();
+        self.turn.fold_wb(score, -score) * self.scale / SCALE_NORMAL
+    }
+}
+
+/// Renders a single table row.
+fn row(f: &mut fmt::Formatter<'_>, name: &str, white: Term, black: Term) -> fmt::Result {
+    let mut total = white;
+    total.sub(black);
+
+    writeln!(
+        f,
+        "{:>12} | {} {} | {} {} | {} {} {}",
+        name,
+        pawns(white.mg),
+        pawns(white.eg),
+        pawns(black.mg),
+        pawns(black.eg),
+        pawns(total.mg),
+        pawns(total.eg),
+        pawns(total.blended),
+    )
+}
+
+/// Formats a score in pawns.
+fn pawns(score: Score) -> String {
+    format!("{:6.2}", score as f64 / 100.0)
+}
+
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = "-------------+---------------+---------------+----------------------";
+
+        writeln!(
+            f,
+            "{:>12} | {:^13} | {:^13} | {:^20}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(
+            f,
+            "{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6} {:>6}",
+            "", "MG", "EG", "MG", "EG", "MG", "EG", "Blend"
+        )?;
+        writeln!(f, "{}", separator)?;
+
+        let (white, black) = (self.material.white, self.material.black);
+        row(f, "Material", white, black)?;
+        for role in Role::ALL {
+            let name = match role {
+                Role::Pawn => "Pawns",
+                Role::Knight => "Knights",
+                Role::Bishop => "Bishops",
+                Role::Rook => "Rooks",
+                Role::Queen => "Queens",
+                Role::King => "Kings",
+            };
+            let (white, black) = (self.pst.white[role], self.pst.black[role]);
+            row(f, name, white, black)?;
+        }
+        let (white, black) = (self.mobility.white, self.mobility.black);
+        row(f, "Mobility", white, black)?;
+
+        writeln!(f, "{}", separator)?;
+        row(
+            f,
+            "Total",
+            self.side_total(Color::White),
+            self.side_total(Color::Black),
+        )?;
+        writeln!(f)?;
+
+        writeln!(f, "Phase: {}/{}", self.phase, MAX_PHASE)?;
+        writeln!(f, "Scale factor: {}/{}", self.scale, SCALE_NORMAL)?;
+        if let Some(score) = self.terminal {
+            let outcome = if score == 0 { "draw" } else { "checkmate" };
+            write!(f, "Final evaluation: none ({})", outcome)
+        } else if let Some((name, score)) = self.endgame {
+            writeln!(f, "Endgame: {}", name)?;
+            write!(f, "Final evaluation: {} (side to move)", pawns(score))
+        } else {
+            let score = self.score();
+            writeln!(
+                f,
+                "Final evaluation: {} (white side)",
+                pawns(self.turn.fold_wb(score, -score))
+            )?;
+            write!(
+                f,
+                "Final evaluation: {} (side to move)",
+                pawns(self.score())
+            )
+        }
+    }
+}
@@ -1,27 +1,140 @@
-use crate::{MAX_SCORE, MIN_SCORE, SearchCommand, SearchControl, SearchInfo, scoring::score_move};
+use crate::{
+    DEFAULT_SEED, MATE_SCORE, MAX_MATE_DISTANCE, MAX_SCORE, MIN_SCORE, Score, SearchCommand,
+    SearchControl, SearchInfo,
+    scoring::{Evaluator, score_move},
+    tablebase::Tablebase,
+};
 use crossbeam_channel::{Receiver, Sender};
+use rand::{RngExt, SeedableRng, rngs::Xoshiro256PlusPlus};
 use shakmaty::{Chess, Move, Position};
-use std::time::{Duration, Instant};
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
 mod negamax;
 
 use negamax::NegaMax;
 
+// Root moves scoring more than this many temperatures below the best move are never picked
+const SOFTMAX_WINDOW: Score = 4;
+
+// Time between progress infos of a running search
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct Timer {
     time_limit: Duration,
     start_time: Instant,
+    // Set from outside the search thread to end the search early
+    abort: Arc<AtomicBool>,
 }
 
 impl Timer {
-    pub fn new(time_limit: Duration) -> Self {
+    pub fn new(time_limit: Duration, abort: Arc<AtomicBool>) -> Self {
         Timer {
             time_limit,
             start_time: Instant::now(),
+            abort,
         }
     }
 
+    /// Whether the search has to end, because its time is up or it was aborted.
     pub fn limit_exceeded(&self) -> bool {
-        self.start_time.elapsed() >= self.time_limit
+        self.start_time.elapsed() >= self.time_limit || self.abort.load(Ordering::Relaxed)
+    }
+
+    /// Time since the start of the search.
+    pub fn elapsed(&self) -> Duration {
+        self.start_time.elapsed()
+    }
+}
+
+/// Sends progress infos of a running search, at most once per `PROGRESS_INTERVAL`.
+pub struct Progress {
+    info_tx: Sender<SearchInfo>,
+    next: Duration,
+    depth: u8,
+    // Root move being searched, with its number in the move order from 1
+    currmove: Option<(Move, u16)>,
+    // Nodes and tablebase hits of the depths searched before
+    nodes: u64,
+    tbhits: u64,
+}
+
+impl Progress {
+    pub fn new(info_tx: Sender<SearchInfo>) -> Self {
+        Progress {
+            info_tx,
+            next: PROGRESS_INTERVAL,
+            depth: 0,
+            currmove: None,
+            nodes: 0,
+            tbhits: 0,
+        }
+    }
+
+    /// Starts a depth, after the given nodes and tablebase hits of the previous ones.
+    pub fn start_depth(&mut self, depth: u8, nodes: u64, tbhits: u64) {
+        self.depth = depth;
+        self.currmove = None;
+        self.nodes = nodes;
+        self.tbhits = tbhits;
+    }
+
+    /// Starts searching a root move.
+    pub fn start_move(&mut self, mv: Move, number: u16) {
+        self.currmove = Some((mv, number));
+    }
+
+    /// Sends an info if the interval has passed, with the nodes and tablebase hits of the
+    /// running depth so far.
+    pub fn report(&mut self, timer: &Timer, nodes: u64, tbhits: u64) {
+        let time = timer.elapsed();
+        let Some((currmove, currmovenumber)) = self.currmove else {
+            return;
+        };
+        if time < self.next {
+            return;
+        }
+
+        let nodes = self.nodes + nodes;
+        // The search ends anyway when nobody is listening
+        let _ = self.info_tx.send(SearchInfo::Progress {
+            depth: self.depth,
+            currmove,
+            currmovenumber,
+            nodes,
+            time,
+            nps: nodes_per_second(nodes, time),
+            tbhits: self.tbhits + tbhits,
+        });
+        self.next = time + PROGRESS_INTERVAL;
+    }
+}
+
+/// Nodes per second of a search which visited the nodes in the given time.
+pub fn nodes_per_second(nodes: u64, time: Duration) -> u64 {
+    (nodes as u128 * 1_000_000 / time.as_micros().max(1)) as u64
+}
+
+/// Moves until checkmate for a root score of a search to the given depth, positive when the
+/// side to move delivers the mate and negative when it is mated.
+pub fn mate_distance(score: Score, depth: u8) -> Option<i32> {
+    // Checkmates score lower the closer they are to the root, see `scoring::terminal_score`
+    let depth = depth as Score;
+    if score >= -MATE_SCORE - MAX_MATE_DISTANCE * 5 {
+        let plies = depth - (score + MATE_SCORE) / 5;
+        Some((plies + 1) / 2)
+    } else if score <= MATE_SCORE + MAX_MATE_DISTANCE * 5 {
+        let plies = depth + (score - MATE_SCORE) / 5;
+        Some(-plies / 2)
+    } else {
+        None
     }
 }
 
@@ -29,18 +142,53 @@ impl Timer {
 pub struct Searcher {
     cmd_rx: Receiver<SearchCommand>,
     info_tx: Sender<SearchInfo>,
+    evaluator: Evaluator,
+    tablebase: Option<Arc<Tablebase>>,
+    // Source of all randomness in the search, so that searches can be reproduced from the seed
+    rng: Xoshiro256PlusPlus,
+    // Softmax temperature of the root move selection in centipawns, 0 to always pick the best
+    randomness: Score,
+    // Ends the running search with the best move found so far when set
+    abort: Arc<AtomicBool>,
 }
 
 impl Searcher {
     pub fn new(cmd_rx: Receiver<SearchCommand>, info_tx: Sender<SearchInfo>) -> Self {
-        Searcher { cmd_rx, info_tx }
+        Searcher {
+            cmd_rx,
+            info_tx,
+            evaluator: Evaluator::default(),
+            tablebase: None,
+            rng: Xoshiro256PlusPlus::seed_from_u64(DEFAULT_SEED),
+            randomness: 0,
+            abort: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Flag which aborts the running search when set, and has to be cleared before starting
+    /// the next one. Searches end early as soon as they notice it, still sending a best move.
+    pub fn abort_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.abort)
     }
 
     /// Run the searcher
     pub fn run(mut self) {
         loop {
             match self.cmd_rx.recv() {
-                Ok(SearchCommand::Start { position, control }) => self.search(position, control),
+                Ok(SearchCommand::Start { position, control }) => {
+                    // A panicking search is reported instead of leaving the caller waiting
+                    let search = AssertUnwindSafe(|| self.search(position, control));
+                    if let Err(payload) = panic::catch_unwind(search) {
+                        let reason = format!("search panicked: {}", panic_message(&*payload));
+                        let _ = self.info_tx.send(SearchInfo::Error(reason));
+                    }
+                }
+                Ok(SearchCommand::SetEvaluator(evaluator)) => self.evaluator = evaluator,
+                Ok(SearchCommand::SetTablebase(tablebase)) => self.tablebase = tablebase,
+                Ok(SearchCommand::SetSeed(seed)) => {
+                    self.rng = Xoshiro256PlusPlus::seed_from_u64(seed)
+                }
+                Ok(SearchCommand::SetRandomness(randomness)) => self.randomness = randomness,
                 Ok(SearchCommand::Stop) => (),
                 Ok(SearchCommand::Quit) | Err(_) => break,
             }
@@ -49,66 +197,112 @@ impl Searcher {
 
     fn search(&mut self, position: Chess, control: SearchControl) {
         // Determine search constraints
-        let (max_depth, time_limit) = match control {
-            SearchControl::ToDepth(depth) => (depth, Duration::from_millis(u64::MAX)),
-            SearchControl::TimeLimit(time_limit) => (u8::MAX, Duration::from_millis(time_limit)),
+        let no_time_limit = Duration::from_millis(u64::MAX);
+        let (max_depth, time_limit, node_limit) = match control {
+            SearchControl::ToDepth(depth) => (depth, no_time_limit, u64::MAX),
+            SearchControl::TimeLimit(time_limit) => {
+                (u8::MAX, Duration::from_millis(time_limit), u64::MAX)
+            }
+            SearchControl::Nodes(nodes) => (u8::MAX, no_time_limit, nodes),
         };
         // Log start time:
-        let timer = Timer::new(time_limit);
+        let timer = Timer::new(time_limit, self.abort_flag());
+
+        // Positions without moves have no best move
+        let Some(&first_move) = position.legal_moves().first() else {
+            let reason = "the position has no legal moves".to_string();
+            self.info_tx.send(SearchInfo::Error(reason)).unwrap();
+            return;
+        };
 
         // Initial values
-        let mut selected_move = position.legal_moves()[0];
+        let mut selected_move = first_move;
+        let mut eval = self.evaluator.state(&position);
         let mut running_depth: u8 = 1;
+        let mut total_nodes: u64 = 0;
+        let mut tbhits: u64 = 0;
+        let mut progress = Progress::new(self.info_tx.clone());
 
-        while running_depth <= max_depth && !timer.limit_exceeded() {
+        while running_depth <= max_depth && !timer.limit_exceeded() && total_nodes < node_limit {
             // Init alpha beta
             let mut alpha = MIN_SCORE;
             let beta = MAX_SCORE;
-            let mut best_move = position.legal_moves()[0];
+            let mut best_move = first_move;
+
+            // With randomness, moves close to the best need exact scores rather than bounds
+            let window = self.randomness.saturating_mul(SOFTMAX_WINDOW);
+            let mut scores = Vec::new();
 
             // Init searcher
-            let mut negamax = NegaMax::new();
+            progress.start_depth(running_depth, total_nodes, tbhits);
+            let mut negamax = NegaMax::new(
+                self.evaluator.clone(),
+                self.tablebase.clone(),
+                node_limit - total_nodes,
+                progress,
+            );
 
             // Move ordering
             let mut sorted_moves = position.legal_moves();
             sorted_moves.sort_unstable_by_key(|mv| score_move(&position, mv));
 
-            for mv in sorted_moves {
+            for (index, mv) in sorted_moves.into_iter().enumerate() {
+                // The search reports the root move it is in once in a while
+                negamax.progress.start_move(mv, index as u16 + 1);
+
                 // Score this move (By searching)
+                self.evaluator.make(&mut eval, &position, mv);
                 let new_position = position.clone().play(mv).unwrap();
 
-                let score =
-                    match negamax.search(&new_position, running_depth - 1, -beta, -alpha, &timer) {
-                        Some(score) => -score,
-                        None => break,
-                    };
+                let score = negamax.search(
+                    &new_position,
+                    &mut eval,
+                    running_depth - 1,
+                    -beta,
+                    -alpha.saturating_sub(window).max(MIN_SCORE),
+                    &timer,
+                );
+                self.evaluator.unmake(&mut eval, &position, mv);
+                let score = match score {
+                    Some(score) => -score,
+                    None => break,
+                };
 
                 // Update appropriately
                 if score > alpha {
                     alpha = score;
                     best_move = mv;
                 }
-
-                // Add some randomisation - update based on coinflip if equal
-                if score == alpha && rand::random() {
-                    best_move = mv;
-                }
+                scores.push((mv, score));
             }
 
-            if !timer.limit_exceeded() {
+            total_nodes += negamax.nodes_searched;
+            tbhits += negamax.tbhits;
+
+            if !timer.limit_exceeded() && !negamax.out_of_nodes() {
+                let (mv, score) = self.select_move(&scores, (best_move, alpha), window);
+
                 // Send info
-                self.send_info(
-                    running_depth,
-                    vec![best_move],
-                    alpha,
-                    negamax.nodes_searched,
-                );
+                let time = timer.elapsed();
+                self.info_tx
+                    .send(SearchInfo::Info {
+                        depth: running_depth,
+                        seldepth: negamax.seldepth(running_depth),
+                        pv: vec![mv],
+                        score,
+                        nodes: total_nodes,
+                        time,
+                        nps: nodes_per_second(total_nodes, time),
+                        tbhits,
+                    })
+                    .unwrap();
 
                 // Update best move
-                selected_move = best_move;
+                selected_move = mv;
             }
 
             // Run another depth if we can!
+            progress = negamax.progress;
             running_depth += 1;
         }
 
@@ -118,14 +312,47 @@ impl Searcher {
             .unwrap();
     }
 
-    fn send_info(&self, depth: u8, pv: Vec<Move>, score: i32, nodes: u64) {
-        self.info_tx
-            .send(SearchInfo::Info {
-                depth,
-                pv,
-                score,
-                nodes,
+    /// Picks a root move by a softmax over the scores within the window below the best move,
+    /// or the best move when randomness is disabled.
+    fn select_move(
+        &mut self,
+        scores: &[(Move, Score)],
+        best: (Move, Score),
+        window: Score,
+    ) -> (Move, Score) {
+        if self.randomness == 0 {
+            return best;
+        }
+
+        // Scores above the window are exact, as they were searched with a lower bound below it
+        let candidates: Vec<(Move, Score, f64)> = scores
+            .iter()
+            .filter(|(_, score)| *score > best.1.saturating_sub(window))
+            .map(|&(mv, score)| {
+                let weight = ((score - best.1) as f64 / self.randomness as f64).exp();
+                (mv, score, weight)
             })
-            .unwrap();
+            .collect();
+
+        let total: f64 = candidates.iter().map(|(_, _, weight)| weight).sum();
+        let mut pick = self.rng.random::<f64>() * total;
+        for &(mv, score, weight) in &candidates {
+            if pick < weight {
+                return (mv, score);
+            }
+            pick -= weight;
+        }
+        best
+    }
+}
+
+/// Text of a panic payload, which is a string unless the panic was raised with another value.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
     }
 }
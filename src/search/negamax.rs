@@ -1,22 +1,66 @@
 use crate::{
     Score,
-    scoring::{evaluate, score_move},
-    search::Timer,
+    scoring::{
+        EvalState, Evaluator,
+        endgame::{Wdl, kpk_probe},
+        score_move,
+    },
+    search::{Progress, Timer},
+    tablebase::{MAX_PIECES, Tablebase},
 };
 use shakmaty::{Chess, Position};
+use std::sync::Arc;
+
+// Nodes between checks whether a progress info is due
+const PROGRESS_NODES: u64 = 1024;
 
 pub struct NegaMax {
     pub nodes_searched: u64,
+    // Positions whose score was taken from the tablebase
+    pub tbhits: u64,
+    node_limit: u64,
+    // Least remaining depth of a visited node, from which the selective depth follows
+    min_depth: u8,
+    evaluator: Evaluator,
+    tablebase: Option<Arc<Tablebase>>,
+    pub progress: Progress,
 }
 
 impl NegaMax {
-    pub fn new() -> Self {
-        Self { nodes_searched: 0 }
+    pub fn new(
+        evaluator: Evaluator,
+        tablebase: Option<Arc<Tablebase>>,
+        node_limit: u64,
+        progress: Progress,
+    ) -> Self {
+        Self {
+            nodes_searched: 0,
+            tbhits: 0,
+            node_limit,
+            min_depth: u8::MAX,
+            evaluator,
+            tablebase,
+            progress,
+        }
+    }
+
+    /// Whether the node budget of this search has been used up.
+    pub fn out_of_nodes(&self) -> bool {
+        self.nodes_searched >= self.node_limit
+    }
+
+    /// Deepest ply from the root reached by a search to the given depth, which falls short of
+    /// it when every line ends early in the tablebase or a finished game.
+    ///
+    /// Without a quiescence search or extensions, no line goes beyond the given depth.
+    pub fn seldepth(&self, depth: u8) -> u8 {
+        depth.saturating_sub(self.min_depth)
     }
 
     pub fn search(
         &mut self,
         position: &Chess,
+        eval: &mut EvalState,
         depth: u8,
         mut alpha: Score,
         beta: Score,
@@ -24,10 +68,29 @@ impl NegaMax {
     ) -> Option<Score> {
         // Update state
         self.nodes_searched += 1;
+        self.min_depth = self.min_depth.min(depth);
+        if self.nodes_searched.is_multiple_of(PROGRESS_NODES) {
+            self.progress
+                .report(timer, self.nodes_searched, self.tbhits);
+        }
+
+        // Exact result from the tablebase
+        if let Some(tablebase) = &self.tablebase
+            && position.board().occupied().count() <= MAX_PIECES
+            && let Some(dtm) = tablebase.probe(position)
+        {
+            self.tbhits += 1;
+            return Some(dtm.score(depth));
+        }
 
         // Depth limit reached / Terminal state
         if depth == 0 || position.is_game_over() {
-            return Some(evaluate(&position, depth));
+            return Some(self.evaluator.evaluate(position, depth, eval));
+        }
+
+        // Drawn KPK positions need no further search, won ones are searched to make progress
+        if kpk_probe(position) == Some(Wdl::Draw) {
+            return Some(0);
         }
 
         // Move ordering
@@ -36,15 +99,19 @@ impl NegaMax {
 
         // Try each legal move
         for mv in sorted_moves {
-            // Break if time limit exeeded
-            if timer.limit_exceeded() {
+            // Break if time limit or node budget exeeded
+            if timer.limit_exceeded() || self.out_of_nodes() {
                 break;
             }
 
+            self.evaluator.make(eval, position, mv);
             let new_position = position.clone().play(mv).unwrap();
+            debug_assert_eq!(*eval, self.evaluator.state(&new_position));
 
             // Recursive call - negate the score from opponent's perspective
-            let score = -self.search(&new_position, depth - 1, -beta, -alpha, timer)?;
+            let score = self.search(&new_position, eval, depth - 1, -beta, -alpha, timer);
+            self.evaluator.unmake(eval, position, mv);
+            let score = -score?;
 
             // Update alpha -> New best move
             if score > alpha {
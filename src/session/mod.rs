@@ -0,0 +1,280 @@
+//! Transcripts of UCI sessions, recorded by the engine and replayed against it.
+//!
+//! A transcript has one line per message, with the milliseconds since the session started,
+//! the direction and the message as it was read or written:
+//!
+//! ```text
+//! # checkm8 session started 2026-01-01 12:00:00
+//! 0 in uci
+//! 1 out id name checkm8
+//! ```
+//!
+//! A replay feeds the input of a transcript to a fresh controller and searcher, sending every
+//! input once the output which preceded it in the transcript has been received, so that `stop`
+//! and other commands arrive at the same point of the session however fast the machine is. The
+//! output of the replay is then compared with the recorded one. Progress infos are not counted,
+//! as searches send one every second and their number depends on how fast the machine is.
+
+use crate::{
+    bot::{command::Command, controller::Controller},
+    logging::Logger,
+    search::Searcher,
+};
+use chrono::Local;
+use crossbeam_channel::unbounded;
+use std::{
+    fmt,
+    fs::File,
+    io::{self, LineWriter, Write},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Whether a message was received or sent by the engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::In => write!(f, "in"),
+            Direction::Out => write!(f, "out"),
+        }
+    }
+}
+
+/// Writes a transcript of the session, shared by the input listener and the controller.
+#[derive(Clone)]
+pub struct Recorder {
+    start: Instant,
+    file: Arc<Mutex<LineWriter<File>>>,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> io::Result<Self> {
+        let mut file = LineWriter::new(File::create(path)?);
+        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
+        writeln!(file, "# checkm8 session started {}", timestamp)?;
+
+        Ok(Recorder {
+            start: Instant::now(),
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    pub fn record(&self, direction: Direction, line: &str) {
+        let time = self.start.elapsed().as_millis();
+        if let Ok(mut file) = self.file.lock() {
+            // A transcript which cannot be written must not stop the session
+            let _ = writeln!(file, "{} {} {}", time, direction, line);
+        }
+    }
+}
+
+/// A message of a transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    // Time since the session started
+    pub time: Duration,
+    pub direction: Direction,
+    pub line: String,
+}
+
+/// A recorded session.
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub entries: Vec<Entry>,
+}
+
+impl Transcript {
+    /// Lines the engine sent.
+    pub fn output(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.direction == Direction::Out)
+            .map(|entry| entry.line.clone())
+            .collect()
+    }
+}
+
+impl FromStr for Transcript {
+    type Err = ParseTranscriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = Vec::new();
+        for (index, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = ParseTranscriptError { line: index + 1 };
+            let mut fields = line.splitn(3, ' ');
+            let time = fields
+                .next()
+                .and_then(|time| time.parse().ok())
+                .ok_or(error)?;
+            let direction = match fields.next() {
+                Some("in") => Direction::In,
+                Some("out") => Direction::Out,
+                _ => return Err(error),
+            };
+
+            entries.push(Entry {
+                time: Duration::from_millis(time),
+                direction,
+                line: fields.next().unwrap_or_default().to_string(),
+            });
+        }
+
+        Ok(Transcript { entries })
+    }
+}
+
+/// Error returned for transcript lines which are not a time, a direction and a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseTranscriptError {
+    // Line number, counted from 1
+    pub line: usize,
+}
+
+impl fmt::Display for ParseTranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid transcript entry on line {}", self.line)
+    }
+}
+
+impl std::error::Error for ParseTranscriptError {}
+
+/// Feeds the input of a transcript to a new engine, returning its output.
+///
+/// Waits at most `timeout` for each output line the transcript expects before an input.
+pub fn replay(transcript: &Transcript, timeout: Duration) -> Vec<String> {
+    let (input_tx, input_rx) = unbounded();
+    let (cmd_tx, cmd_rx) = unbounded();
+    let (info_tx, info_rx) = unbounded();
+    let (output_tx, output_rx) = unbounded();
+
+    let searcher = Searcher::new(cmd_rx, info_tx);
+    let abort = searcher.abort_flag();
+    thread::spawn(|| searcher.run());
+    let controller = thread::spawn(move || {
+        let logger = Logger::disabled();
+        let mut controller =
+            Controller::with_output(input_rx, cmd_tx, info_rx, abort, logger, output_tx);
+        let _ = controller.run();
+    });
+
+    let mut output: Vec<String> = Vec::new();
+    // Output lines without progress infos, recorded and received so far
+    let mut expected = 0;
+    let mut received = 0;
+    for entry in &transcript.entries {
+        if entry.direction == Direction::Out {
+            if !is_progress(&entry.line) {
+                expected += 1;
+            }
+            continue;
+        }
+
+        while received < expected {
+            match output_rx.recv_timeout(timeout) {
+                Ok(line) => {
+                    if !is_progress(&line) {
+                        received += 1;
+                    }
+                    output.push(line);
+                }
+                Err(_) => break,
+            }
+        }
+        if let Some(command) = Command::parse_line(&entry.line) {
+            let _ = input_tx.send(command);
+        }
+    }
+
+    // The end of the input finishes the running search and quits
+    drop(input_tx);
+    let _ = controller.join();
+    output.extend(output_rx.try_iter());
+    output
+}
+
+/// Whether a line is a progress info of a running search, which names the move it is in.
+pub fn is_progress(line: &str) -> bool {
+    line.starts_with("info ") && line.split_whitespace().any(|word| word == "currmove")
+}
+
+/// Drops the values of the given `info` fields, such as `nodes` or `time`, which vary between
+/// runs.
+pub fn normalize(line: &str, ignore: &[String]) -> String {
+    if !line.starts_with("info ") || line.starts_with("info string ") || ignore.is_empty() {
+        return line.to_string();
+    }
+
+    let mut tokens = Vec::new();
+    let mut words = line.split_whitespace();
+    while let Some(word) = words.next() {
+        if ignore.iter().any(|field| field == word) {
+            // Scores have a unit besides their value
+            let values = if word == "score" { 2 } else { 1 };
+            words.nth(values - 1);
+        } else {
+            tokens.push(word);
+        }
+    }
+    tokens.join(" ")
+}
+
+/// Line of a diff between the recorded and the replayed output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Missing(String),
+    Added(String),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Missing(line) => write!(f, "- {}", line),
+            Change::Added(line) => write!(f, "+ {}", line),
+        }
+    }
+}
+
+/// Lines which have to be removed from and added to the expected output to get the actual
+/// one, by their longest common subsequence.
+pub fn diff(expected: &[String], actual: &[String]) -> Vec<Change> {
+    // common[i][j] is the length of the common subsequence of expected[i..] and actual[j..]
+    let mut common = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::new();
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            i += 1;
+            j += 1;
+        } else if j == actual.len() || (i < expected.len() && common[i + 1][j] >= common[i][j + 1])
+        {
+            changes.push(Change::Missing(expected[i].clone()));
+            i += 1;
+        } else {
+            changes.push(Change::Added(actual[j].clone()));
+            j += 1;
+        }
+    }
+    changes
+}
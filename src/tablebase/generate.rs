@@ -0,0 +1,338 @@
+//! Retrograde generation of distance to mate tables.
+//!
+//! Every legal position of the table is first expanded once with the move generator. Moves
+//! which capture or promote leave the table and are resolved from smaller tables, while the
+//! remaining moves are counted. Results are then propagated backwards ply by ply from the
+//! checkmates: the predecessors of a lost position are won, and a position becomes lost once
+//! every move has been shown to lose. Positions never resolved are draws.
+
+use super::{Dtm, Encoding, MAX_PIECES, MAX_PLIES, Material, Table, Tablebase};
+use shakmaty::{
+    Bitboard, Board, CastlingMode, Chess, Color, Position, Rank, Role, Setup, Square,
+    attacks::attacks,
+};
+
+// Resolution state of a position during generation
+const UNRESOLVED: u8 = 0;
+const WON: u8 = 1;
+const LOST: u8 = 2;
+const INVALID: u8 = 3;
+
+// Marker for positions without a winning move leaving the table
+const NO_EXIT: u8 = u8::MAX;
+
+/// Generates a table together with all smaller tables it depends on, which are not yet in
+/// the tablebase.
+///
+/// Every finished table is passed to the callback before being added to the tablebase.
+pub fn generate_with_dependencies(
+    material: &Material,
+    tablebase: &mut Tablebase,
+    on_table: &mut impl FnMut(&Table),
+) {
+    let material = material.canonical();
+    if tablebase.contains(&material) {
+        return;
+    }
+
+    for dependency in dependencies(&material) {
+        generate_with_dependencies(&dependency, tablebase, on_table);
+    }
+
+    let table = generate(&material, tablebase);
+    on_table(&table);
+    tablebase.insert(table);
+}
+
+/// Materials reached by captures and promotions, excluding those without mating material.
+pub fn dependencies(material: &Material) -> Vec<Material> {
+    let mut materials = Vec::new();
+
+    for (own, other, white) in [
+        (&material.white, &material.black, true),
+        (&material.black, &material.white, false),
+    ] {
+        let with = |roles: Vec<Role>| {
+            let mut roles = roles;
+            roles.sort_unstable_by(|a, b| b.cmp(a));
+            let (white_roles, black_roles) = if white {
+                (roles, other.clone())
+            } else {
+                (other.clone(), roles)
+            };
+            Material {
+                white: white_roles,
+                black: black_roles,
+            }
+        };
+
+        for i in 0..own.len() {
+            // Captures of this piece
+            let mut captured = own.clone();
+            captured.remove(i);
+            materials.push(with(captured));
+
+            // Promotions of this pawn
+            if own[i] == Role::Pawn {
+                for role in [Role::Queen, Role::Rook, Role::Bishop, Role::Knight] {
+                    let mut promoted = own.clone();
+                    promoted[i] = role;
+                    materials.push(with(promoted));
+                }
+            }
+        }
+    }
+
+    let mut dependencies: Vec<Material> = Vec::new();
+    for material in materials {
+        let material = material.canonical();
+        if can_mate(&material) && !dependencies.contains(&material) {
+            dependencies.push(material);
+        }
+    }
+    dependencies
+}
+
+/// Whether any position with the material could end in checkmate.
+pub fn can_mate(material: &Material) -> bool {
+    let minor = |role: &Role| matches!(role, Role::Knight | Role::Bishop);
+    material.count() > 3 || !material.white.iter().chain(&material.black).all(minor)
+}
+
+/// Generates a single table. All tables reached by captures and promotions must already be in
+/// the tablebase.
+pub fn generate(material: &Material, tablebase: &Tablebase) -> Table {
+    let encoding = Encoding::new(material);
+    let size = encoding.size();
+
+    let mut state = vec![INVALID; size];
+    let mut plies = vec![0u8; size];
+    // Moves staying in the table whose result is still unknown
+    let mut remaining = vec![0u8; size];
+    // Longest loss among the moves leaving the table
+    let mut exit_loss = vec![0u8; size];
+    // Whether a move leaving the table draws or wins
+    let mut exit_draw = vec![false; size];
+    let mut exit_win = vec![NO_EXIT; size];
+    // Whether the predecessors of a resolved position have been visited
+    let mut processed = vec![false; size];
+
+    // Positions to process per ply, together with winning exits to check
+    let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); MAX_PLIES as usize + 2];
+
+    // Expand every legal position once
+    for index in 0..size {
+        let Some(position) = position_at(&encoding, index) else {
+            continue;
+        };
+        state[index] = UNRESOLVED;
+
+        let moves = position.legal_moves();
+        if moves.is_empty() {
+            if position.is_check() {
+                state[index] = LOST;
+                buckets[0].push(index);
+            }
+            continue;
+        }
+
+        let mut children = Vec::with_capacity(moves.len());
+        for mv in moves {
+            let child = position.clone().play(mv).unwrap();
+
+            if mv.is_capture() || mv.is_promotion() {
+                match exit_result(&child, tablebase) {
+                    Dtm::Win(n) => exit_loss[index] = exit_loss[index].max(n as u8 + 1),
+                    Dtm::Loss(n) => exit_win[index] = exit_win[index].min(n as u8 + 1),
+                    Dtm::Draw => exit_draw[index] = true,
+                }
+            } else {
+                let squares = encoding.squares(child.board());
+                children.push(encoding.index(&squares[..encoding.len()], child.turn()));
+            }
+        }
+
+        // Moves reaching the same position up to symmetry are counted once
+        children.sort_unstable();
+        children.dedup();
+        remaining[index] = children.len() as u8;
+
+        if exit_win[index] != NO_EXIT {
+            buckets[exit_win[index] as usize].push(index);
+        } else if remaining[index] == 0 && !exit_draw[index] {
+            state[index] = LOST;
+            plies[index] = exit_loss[index];
+            buckets[exit_loss[index] as usize].push(index);
+        }
+    }
+
+    // Propagate results backwards, one ply at a time
+    let mut predecessors = Vec::new();
+    for ply in 0..=MAX_PLIES as usize {
+        let bucket = std::mem::take(&mut buckets[ply]);
+
+        for index in bucket {
+            if processed[index] {
+                continue;
+            }
+            // Winning exit not beaten by a faster win inside the table
+            if state[index] == UNRESOLVED {
+                state[index] = WON;
+                plies[index] = ply as u8;
+            }
+            processed[index] = true;
+
+            unmoves(&encoding, index, &state, &mut predecessors);
+            let next = ply + 1;
+
+            for &predecessor in &predecessors {
+                if state[predecessor] != UNRESOLVED {
+                    continue;
+                }
+
+                if state[index] == LOST {
+                    // Moving into a lost position wins
+                    state[predecessor] = WON;
+                    plies[predecessor] = next as u8;
+                    buckets[next].push(predecessor);
+                } else {
+                    // Lost once every move loses
+                    remaining[predecessor] -= 1;
+                    if remaining[predecessor] == 0
+                        && !exit_draw[predecessor]
+                        && exit_win[predecessor] == NO_EXIT
+                    {
+                        let lost_in = exit_loss[predecessor].max(next as u8);
+                        state[predecessor] = LOST;
+                        plies[predecessor] = lost_in;
+                        buckets[lost_in as usize].push(predecessor);
+                    }
+                }
+            }
+        }
+    }
+
+    let values = (0..size)
+        .map(|index| match state[index] {
+            WON => Dtm::Win(plies[index] as u32).encode(),
+            LOST => Dtm::Loss(plies[index] as u32).encode(),
+            _ => Dtm::Draw.encode(),
+        })
+        .collect();
+
+    Table {
+        material: material.clone(),
+        encoding,
+        values,
+    }
+}
+
+/// Result of a position reached by leaving the table, from its side to move's perspective.
+fn exit_result(child: &Chess, tablebase: &Tablebase) -> Dtm {
+    if child.is_checkmate() {
+        return Dtm::Loss(0);
+    }
+    if child.is_stalemate() || child.is_insufficient_material() {
+        return Dtm::Draw;
+    }
+    if !can_mate(&Material::of(child.board())) {
+        return Dtm::Draw;
+    }
+
+    tablebase
+        .probe(child)
+        .expect("tables reached by captures and promotions are generated first")
+}
+
+/// The legal position of an index, if it is the canonical index of that position.
+fn position_at(encoding: &Encoding, index: usize) -> Option<Chess> {
+    let (squares, turn) = encoding.decode(index);
+    let squares = &squares[..encoding.len()];
+
+    let mut board = Board::empty();
+    for (&square, &piece) in squares.iter().zip(&encoding.pieces) {
+        if board.piece_at(square).is_some() {
+            return None;
+        }
+        if piece.role == Role::Pawn && matches!(square.rank(), Rank::First | Rank::Eighth) {
+            return None;
+        }
+        board.set_piece_at(square, piece);
+    }
+
+    if encoding.index(squares, turn) != index {
+        return None;
+    }
+
+    let mut setup = Setup::empty();
+    setup.board = board;
+    setup.turn = turn;
+    match setup.position(CastlingMode::Standard) {
+        Ok(position) => Some(position),
+        Err(err) => err.ignore_impossible_check().ok(),
+    }
+}
+
+/// Collects the distinct legal positions from which a non-capturing move of the side which is
+/// not to move leads to the position of an index.
+fn unmoves(encoding: &Encoding, index: usize, state: &[u8], predecessors: &mut Vec<usize>) {
+    predecessors.clear();
+
+    let (squares, turn) = encoding.decode(index);
+    let squares = &squares[..encoding.len()];
+    let mover = !turn;
+
+    let mut occupied = Bitboard::EMPTY;
+    for &square in squares {
+        occupied.add(square);
+    }
+
+    for (i, &piece) in encoding.pieces.iter().enumerate() {
+        if piece.color != mover {
+            continue;
+        }
+
+        let from = squares[i];
+        let targets = if piece.role == Role::Pawn {
+            pawn_unpushes(from, mover, occupied)
+        } else {
+            // Piece moves are symmetric, so the squares it attacks are where it came from
+            attacks(from, piece, occupied) & !occupied
+        };
+
+        for to in targets {
+            let mut previous = [Square::A1; MAX_PIECES];
+            previous[..squares.len()].copy_from_slice(squares);
+            previous[i] = to;
+
+            let predecessor = encoding.index(&previous[..squares.len()], mover);
+            if state[predecessor] != INVALID {
+                predecessors.push(predecessor);
+            }
+        }
+    }
+
+    predecessors.sort_unstable();
+    predecessors.dedup();
+}
+
+/// Squares a pawn could have been pushed from.
+fn pawn_unpushes(square: Square, color: Color, occupied: Bitboard) -> Bitboard {
+    let back = color.fold_wb(-8, 8);
+    let mut squares = Bitboard::EMPTY;
+
+    let Some(single) = square.offset(back).filter(|&s| !occupied.contains(s)) else {
+        return squares;
+    };
+    if single.rank() != color.fold_wb(Rank::First, Rank::Eighth) {
+        squares.add(single);
+    }
+
+    if square.rank() == color.fold_wb(Rank::Fourth, Rank::Fifth)
+        && let Some(double) = single.offset(back).filter(|&s| !occupied.contains(s))
+    {
+        squares.add(double);
+    }
+
+    squares
+}
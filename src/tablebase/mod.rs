@@ -0,0 +1,586 @@
+//! Distance to mate tablebases for endings with few pieces.
+//!
+//! Every table covers a single material signature such as `KQKR`, with the pieces before the
+//! second king belonging to white. Signatures are stored with the stronger side as white, and
+//! positions with the colours the other way around are mirrored before probing. Tables are
+//! built by retrograde analysis in [`generate`].
+//!
+//! Positions are indexed by the side to move and the squares of all pieces. Symmetry is used
+//! to restrict the white king to the a1-d1-d4 triangle in pawnless tables, and to files a-d
+//! otherwise. Castling rights and en passant captures are ignored, so positions with either
+//! are not probed.
+//!
+//! Table files use the following little-endian layout:
+//!
+//! | Field          | Type | Count            |
+//! |----------------|------|------------------|
+//! | Magic `CM8T`   | u8   | 4                |
+//! | Version (1)    | u32  | 1                |
+//! | Name length    | u8   | 1                |
+//! | Name           | u8   | name length      |
+//! | Entries        | u32  | 1                |
+//! | Packed entries | u8   | rest of the file |
+//!
+//! Entries are packed in blocks starting with a header byte `n`. Headers below 128 are
+//! followed by `n + 1` literal entries, other headers by a single entry repeated `n - 126`
+//! times. An entry of 0 is a draw, values 1-127 win in that many moves and values 128-255 lose in the value minus 128
+//! moves, where 128 itself means checkmated.
+
+use crate::{MATE_SCORE, Score};
+use shakmaty::{Bitboard, Board, Chess, Color, EnPassantMode, Piece, Position, Role, Square};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+};
+
+pub mod generate;
+
+/// Most pieces, kings included, covered by a table.
+pub const MAX_PIECES: usize = 4;
+
+/// File extension of table files.
+pub const FILE_EXTENSION: &str = "cm8t";
+
+// File format
+const MAGIC: &[u8; 4] = b"CM8T";
+const VERSION: u32 = 1;
+
+// Longest distance to mate which can be stored, in plies
+const MAX_PLIES: u32 = 253;
+
+/// Exact result of a position from the perspective of the side to move, with the number of
+/// plies until checkmate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dtm {
+    Win(u32),
+    Loss(u32),
+    Draw,
+}
+
+impl Dtm {
+    /// Score of the result at a search node with the given remaining depth.
+    ///
+    /// Matches the score of a checkmate found by the search, extended to mates beyond the
+    /// horizon, so faster mates are preferred by the winning side.
+    pub fn score(self, depth: u8) -> Score {
+        let mate_at = |plies: u32| MATE_SCORE + (plies as Score - depth as Score) * 5;
+
+        match self {
+            Dtm::Win(plies) => -mate_at(plies),
+            Dtm::Loss(plies) => mate_at(plies),
+            Dtm::Draw => 0,
+        }
+    }
+
+    fn encode(self) -> u8 {
+        match self {
+            Dtm::Win(plies) => plies.div_ceil(2) as u8,
+            Dtm::Loss(plies) => 128 + (plies / 2) as u8,
+            Dtm::Draw => 0,
+        }
+    }
+
+    fn decode(value: u8) -> Self {
+        match value {
+            0 => Dtm::Draw,
+            1..=127 => Dtm::Win(value as u32 * 2 - 1),
+            _ => Dtm::Loss((value as u32 - 128) * 2),
+        }
+    }
+}
+
+/// Pieces of both sides, kings excluded, ordered from the most to the least valuable.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Material {
+    white: Vec<Role>,
+    black: Vec<Role>,
+}
+
+/// Error returned when parsing an invalid material signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMaterialError;
+
+impl fmt::Display for ParseMaterialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid material signature")
+    }
+}
+
+impl std::error::Error for ParseMaterialError {}
+
+impl Material {
+    /// Material on a board.
+    pub fn of(board: &Board) -> Self {
+        let side = |color: Color| {
+            let mut roles: Vec<Role> = board
+                .by_color(color)
+                .into_iter()
+                .filter_map(|square| board.role_at(square))
+                .filter(|&role| role != Role::King)
+                .collect();
+            roles.sort_unstable_by(|a, b| b.cmp(a));
+            roles
+        };
+
+        Material {
+            white: side(Color::White),
+            black: side(Color::Black),
+        }
+    }
+
+    /// The same material with the colours swapped.
+    pub fn mirrored(&self) -> Self {
+        Material {
+            white: self.black.clone(),
+            black: self.white.clone(),
+        }
+    }
+
+    /// Whether white is at least as strong as black, which is how tables are stored.
+    pub fn is_canonical(&self) -> bool {
+        let value = |roles: &[Role]| roles.iter().map(|&role| role_value(role)).sum::<u32>();
+        (value(&self.white), &self.white) >= (value(&self.black), &self.black)
+    }
+
+    /// The orientation of the material used for tables.
+    pub fn canonical(&self) -> Self {
+        if self.is_canonical() {
+            self.clone()
+        } else {
+            self.mirrored()
+        }
+    }
+
+    /// Number of pieces, kings included.
+    pub fn count(&self) -> usize {
+        2 + self.white.len() + self.black.len()
+    }
+
+    pub fn has_pawns(&self) -> bool {
+        self.white.contains(&Role::Pawn) || self.black.contains(&Role::Pawn)
+    }
+
+    /// Pieces in table order: both kings, then the white and the black pieces.
+    fn pieces(&self) -> Vec<Piece> {
+        let mut pieces = vec![Color::White.king(), Color::Black.king()];
+        pieces.extend(self.white.iter().map(|role| role.of(Color::White)));
+        pieces.extend(self.black.iter().map(|role| role.of(Color::Black)));
+        pieces
+    }
+}
+
+impl FromStr for Material {
+    type Err = ParseMaterialError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_uppercase();
+        let (white, black) = s
+            .strip_prefix('K')
+            .and_then(|rest| rest.split_once('K'))
+            .ok_or(ParseMaterialError)?;
+
+        let side = |pieces: &str| -> Result<Vec<Role>, ParseMaterialError> {
+            let mut roles = pieces
+                .chars()
+                .map(|c| match Role::from_char(c.to_ascii_lowercase()) {
+                    Some(Role::King) | None => Err(ParseMaterialError),
+                    Some(role) => Ok(role),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            roles.sort_unstable_by(|a, b| b.cmp(a));
+            Ok(roles)
+        };
+
+        let material = Material {
+            white: side(white)?,
+            black: side(black)?,
+        };
+        if material.count() > MAX_PIECES {
+            return Err(ParseMaterialError);
+        }
+
+        Ok(material)
+    }
+}
+
+impl fmt::Display for Material {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "K")?;
+        for role in &self.white {
+            write!(f, "{}", role.upper_char())?;
+        }
+        write!(f, "K")?;
+        for role in &self.black {
+            write!(f, "{}", role.upper_char())?;
+        }
+        Ok(())
+    }
+}
+
+/// Rough piece values deciding which side of a signature is the stronger one.
+fn role_value(role: Role) -> u32 {
+    match role {
+        Role::Pawn => 1,
+        Role::Knight | Role::Bishop => 3,
+        Role::Rook => 5,
+        Role::Queen => 9,
+        Role::King => 0,
+    }
+}
+
+// Symmetries of the board, of which only the first two keep pawns moving the right way
+const SYMMETRIES: [fn(Square) -> Square; 8] = [
+    |square| square,
+    Square::flip_horizontal,
+    Square::flip_vertical,
+    Square::rotate_180,
+    Square::flip_diagonal,
+    Square::flip_anti_diagonal,
+    Square::rotate_90,
+    Square::rotate_270,
+];
+
+// Squares of the white king in pawnless tables
+const TRIANGLE: [Square; 10] = [
+    Square::A1,
+    Square::B1,
+    Square::C1,
+    Square::D1,
+    Square::B2,
+    Square::C2,
+    Square::D2,
+    Square::C3,
+    Square::D3,
+    Square::D4,
+];
+
+/// Mapping between positions of a material signature and table indices.
+pub(crate) struct Encoding {
+    pieces: Vec<Piece>,
+    pawns: bool,
+}
+
+impl Encoding {
+    fn new(material: &Material) -> Self {
+        Encoding {
+            pieces: material.pieces(),
+            pawns: material.has_pawns(),
+        }
+    }
+
+    /// Number of pieces, kings included.
+    fn len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    fn king_slots(&self) -> usize {
+        if self.pawns { 32 } else { TRIANGLE.len() }
+    }
+
+    /// Number of indices in the table.
+    fn size(&self) -> usize {
+        2 * self.king_slots() * 64usize.pow(self.len() as u32 - 1)
+    }
+
+    fn king_slot(&self, square: Square) -> Option<usize> {
+        if self.pawns {
+            let file = square.file() as usize;
+            (file < 4).then(|| square.rank() as usize * 4 + file)
+        } else {
+            TRIANGLE.iter().position(|&s| s == square)
+        }
+    }
+
+    fn king_square(&self, slot: usize) -> Square {
+        if self.pawns {
+            Square::new((slot / 4 * 8 + slot % 4) as u32)
+        } else {
+            TRIANGLE[slot]
+        }
+    }
+
+    /// Squares of the pieces on a board in table order, if the board has this material.
+    fn squares(&self, board: &Board) -> [Square; MAX_PIECES] {
+        let mut squares = [Square::A1; MAX_PIECES];
+        let mut taken = Bitboard::EMPTY;
+
+        for (i, &piece) in self.pieces.iter().enumerate() {
+            // Identical pieces are assigned in square order
+            let square = (board.by_piece(piece) & !taken)
+                .first()
+                .unwrap_or(Square::A1);
+            taken.add(square);
+            squares[i] = square;
+        }
+
+        squares
+    }
+
+    /// Index of a position, the same for all positions equal up to symmetry.
+    fn index(&self, squares: &[Square], turn: Color) -> usize {
+        let symmetries = if self.pawns { 2 } else { SYMMETRIES.len() };
+        let mut best = usize::MAX;
+
+        for symmetry in &SYMMETRIES[..symmetries] {
+            let mut transformed = [Square::A1; MAX_PIECES];
+            for (t, &square) in transformed.iter_mut().zip(squares) {
+                *t = symmetry(square);
+            }
+            let transformed = &mut transformed[..self.len()];
+
+            let Some(slot) = self.king_slot(transformed[0]) else {
+                continue;
+            };
+
+            // Identical pieces are interchangeable, so keep them sorted
+            let mut start = 2;
+            while start < self.len() {
+                let mut end = start + 1;
+                while end < self.len() && self.pieces[end] == self.pieces[start] {
+                    end += 1;
+                }
+                transformed[start..end].sort_unstable();
+                start = end;
+            }
+
+            let mut index = slot;
+            for &square in &transformed[1..] {
+                index = index * 64 + square as usize;
+            }
+            best = best.min(index * 2 + turn.fold_wb(0, 1));
+        }
+
+        best
+    }
+
+    /// Squares and side to move of an index.
+    fn decode(&self, mut index: usize) -> ([Square; MAX_PIECES], Color) {
+        let turn = if index.is_multiple_of(2) {
+            Color::White
+        } else {
+            Color::Black
+        };
+        index /= 2;
+
+        let mut squares = [Square::A1; MAX_PIECES];
+        for i in (1..self.len()).rev() {
+            squares[i] = Square::new((index % 64) as u32);
+            index /= 64;
+        }
+        squares[0] = self.king_square(index);
+
+        (squares, turn)
+    }
+}
+
+/// Distance to mate of every position with a single material signature.
+pub struct Table {
+    material: Material,
+    encoding: Encoding,
+    values: Vec<u8>,
+}
+
+impl Table {
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Probes a position whose material matches the table, colours included.
+    pub fn probe(&self, board: &Board, turn: Color) -> Dtm {
+        let squares = self.encoding.squares(board);
+        let index = self.encoding.index(&squares[..self.encoding.len()], turn);
+        Dtm::decode(self.values[index])
+    }
+
+    /// Loads a table from a file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Table::from_bytes(&fs::read(path)?)
+    }
+
+    /// Saves the table into a directory, named after its material.
+    pub fn save(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let path = dir
+            .as_ref()
+            .join(format!("{}.{}", self.material, FILE_EXTENSION));
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        file.write_all(&self.to_bytes())?;
+        file.flush()
+    }
+
+    /// Parses a table from its file contents.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        if bytes.len() < 9 || &bytes[0..4] != MAGIC {
+            return Err(invalid("not a checkm8 tablebase file"));
+        }
+        if read_u32(&bytes[4..8]) != VERSION {
+            return Err(invalid("unsupported tablebase version"));
+        }
+
+        let name_end = 9 + bytes[8] as usize;
+        let material = bytes
+            .get(9..name_end)
+            .and_then(|name| std::str::from_utf8(name).ok())
+            .and_then(|name| name.parse::<Material>().ok())
+            .ok_or_else(|| invalid("invalid material signature"))?;
+        let encoding = Encoding::new(&material);
+        let entries = bytes
+            .get(name_end..name_end + 4)
+            .map(read_u32)
+            .ok_or_else(|| invalid("missing entry count"))? as usize;
+        if entries != encoding.size() {
+            return Err(invalid("tablebase has the wrong size"));
+        }
+
+        let values = unpack(&bytes[name_end + 4..], entries)
+            .ok_or_else(|| invalid("corrupt tablebase entries"))?;
+        if values.len() != entries {
+            return Err(invalid("tablebase has the wrong size"));
+        }
+
+        Ok(Table {
+            material,
+            encoding,
+            values,
+        })
+    }
+
+    /// Serialises the table into its file contents.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let name = self.material.to_string();
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.push(name.len() as u8);
+        bytes.extend(name.as_bytes());
+        bytes.extend((self.values.len() as u32).to_le_bytes());
+
+        pack(&self.values, &mut bytes);
+
+        bytes
+    }
+}
+
+// Longest block of literal or repeated entries
+const MAX_LITERALS: usize = 128;
+const MAX_REPEATS: usize = 129;
+
+/// Packs entries into blocks of literals and repeated entries.
+fn pack(values: &[u8], bytes: &mut Vec<u8>) {
+    let run_length = |start: usize| {
+        values[start..]
+            .iter()
+            .take(MAX_REPEATS)
+            .take_while(|&&value| value == values[start])
+            .count()
+    };
+
+    let mut i = 0;
+    while i < values.len() {
+        let run = run_length(i);
+        if run >= 2 {
+            bytes.push((run + 126) as u8);
+            bytes.push(values[i]);
+            i += run;
+            continue;
+        }
+
+        // Literals up to the next repeated entry
+        let start = i;
+        while i < values.len() && i - start < MAX_LITERALS && run_length(i) < 2 {
+            i += 1;
+        }
+        bytes.push((i - start - 1) as u8);
+        bytes.extend(&values[start..i]);
+    }
+}
+
+/// Unpacks entries, returning None if a block is cut off.
+fn unpack(bytes: &[u8], capacity: usize) -> Option<Vec<u8>> {
+    let mut values = Vec::with_capacity(capacity);
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let header = bytes[i] as usize;
+        if header < MAX_LITERALS {
+            values.extend(bytes.get(i + 1..i + 2 + header)?);
+            i += 2 + header;
+        } else {
+            values.extend(std::iter::repeat_n(*bytes.get(i + 1)?, header - 126));
+            i += 2;
+        }
+    }
+
+    Some(values)
+}
+
+/// A collection of tables, probed by the material of a position.
+#[derive(Default)]
+pub struct Tablebase {
+    tables: HashMap<Material, Table>,
+}
+
+impl Tablebase {
+    pub fn new() -> Self {
+        Tablebase::default()
+    }
+
+    /// Loads all table files in a directory.
+    pub fn load(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let mut tablebase = Tablebase::new();
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == FILE_EXTENSION) {
+                tablebase.insert(Table::load(&path)?);
+            }
+        }
+
+        Ok(tablebase)
+    }
+
+    pub fn insert(&mut self, table: Table) {
+        self.tables.insert(table.material.clone(), table);
+    }
+
+    pub fn contains(&self, material: &Material) -> bool {
+        self.tables.contains_key(&material.canonical())
+    }
+
+    /// Number of tables.
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Probes a position, returning None if no table covers it.
+    pub fn probe(&self, position: &Chess) -> Option<Dtm> {
+        let board = position.board();
+        if board.occupied().count() > MAX_PIECES
+            || position.castles().any()
+            || position.ep_square(EnPassantMode::Legal).is_some()
+        {
+            return None;
+        }
+
+        let material = Material::of(board);
+        if let Some(table) = self.tables.get(&material) {
+            return Some(table.probe(board, position.turn()));
+        }
+
+        // Tables are stored with the stronger side as white
+        let table = self.tables.get(&material.mirrored())?;
+        Some(table.probe(&board.clone().into_mirrored(), !position.turn()))
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
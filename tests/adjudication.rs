@@ -0,0 +1,85 @@
+mod common;
+
+use checkm8::arena::game::{Adjudication, adjudicate};
+use common::position;
+use shakmaty::{Chess, Color, KnownOutcome};
+
+#[test]
+fn decisive_scores_end_games_on_resignation() {
+    let adjudication = Adjudication::default();
+    let position = Chess::default();
+
+    let mut scores = vec![Some(0); 10];
+    scores.extend([Some(1200); 6]);
+    let (outcome, reason) = adjudicate(&scores, &position, &adjudication).unwrap();
+    assert_eq!(
+        outcome,
+        KnownOutcome::Decisive {
+            winner: Color::White
+        }
+    );
+    assert_eq!(reason, "black resigns");
+
+    let scores = vec![Some(-1000); 6];
+    let (outcome, _) = adjudicate(&scores, &position, &adjudication).unwrap();
+    assert_eq!(
+        outcome,
+        KnownOutcome::Decisive {
+            winner: Color::Black
+        }
+    );
+
+    // Too few plies, a missing score or a score below the threshold keep the game going
+    assert_eq!(adjudicate(&[Some(1200); 5], &position, &adjudication), None);
+    let mut scores = vec![Some(1200); 6];
+    scores[2] = None;
+    assert_eq!(adjudicate(&scores, &position, &adjudication), None);
+    scores[2] = Some(999);
+    assert_eq!(adjudicate(&scores, &position, &adjudication), None);
+}
+
+#[test]
+fn drawish_scores_end_games_late_enough() {
+    let adjudication = Adjudication::default();
+    let scores = [
+        Some(10),
+        Some(-10),
+        Some(0),
+        Some(5),
+        Some(0),
+        Some(0),
+        Some(-3),
+        Some(1),
+    ];
+
+    let late = position("4k3/8/8/8/8/8/4P3/4K3 w - - 0 40");
+    let (outcome, reason) = adjudicate(&scores, &late, &adjudication).unwrap();
+    assert_eq!(outcome, KnownOutcome::Draw);
+    assert_eq!(reason, "draw by adjudication");
+
+    let early = position("4k3/8/8/8/8/8/4P3/4K3 w - - 0 39");
+    assert_eq!(adjudicate(&scores, &early, &adjudication), None);
+    assert_eq!(adjudicate(&scores[1..], &late, &adjudication), None);
+
+    let mut scores = scores;
+    scores[0] = Some(11);
+    assert_eq!(adjudicate(&scores, &late, &adjudication), None);
+}
+
+#[test]
+fn disabled_rules_never_end_games() {
+    let adjudication = Adjudication {
+        draw_plies: 0,
+        resign_plies: 0,
+        max_plies: 0,
+        ..Adjudication::default()
+    };
+    let late = position("4k3/8/8/8/8/8/4P3/4K3 w - - 0 40");
+
+    assert_eq!(adjudicate(&[Some(5000); 500], &late, &adjudication), None);
+    assert_eq!(adjudicate(&[Some(0); 500], &late, &adjudication), None);
+
+    let (outcome, reason) = adjudicate(&[None; 400], &late, &Adjudication::default()).unwrap();
+    assert_eq!(outcome, KnownOutcome::Draw);
+    assert_eq!(reason, "maximum game length");
+}
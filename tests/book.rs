@@ -0,0 +1,197 @@
+use checkm8::book::{
+    builder::{BookBuilder, BuildSettings, UnknownResult},
+    decode_move, encode_move, key,
+};
+use checkm8::pgn::{Game, read_games};
+use shakmaty::{
+    CastlingMode, Chess, Move, Position, Role, Square, fen::Fen, san::San, uci::UciMove,
+};
+
+const GAMES: &str = r#"[Result "1-0"]
+
+1. e4 e5 2. Nf3 1-0
+
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 1/2-1/2
+
+[Result "0-1"]
+
+1. e4 c5 0-1
+
+[Result "1-0"]
+
+1. d4 d5 1-0
+
+[Result "*"]
+
+1. e4 e5 *
+"#;
+
+fn play(moves: &[&str]) -> Chess {
+    let mut position = Chess::default();
+    for mv in moves {
+        let mv = mv.parse::<UciMove>().unwrap().to_move(&position).unwrap();
+        position = position.play(mv).unwrap();
+    }
+    position
+}
+
+fn position(fen: &str) -> Chess {
+    let fen: Fen = fen.parse().unwrap();
+    fen.into_position(CastlingMode::Standard).unwrap()
+}
+
+#[test]
+fn keys_match_the_polyglot_reference() {
+    // Test vectors from the Polyglot book format specification
+    for (moves, expected) in [
+        (&[][..], 0x463b96181691fc9c),
+        (&["e2e4"][..], 0x823c9b50fd114196),
+        (&["e2e4", "d7d5"][..], 0x0756b94461c50fb0),
+        (&["e2e4", "d7d5", "e4e5"][..], 0x662fafb965db29d4),
+        // En passant on f6 is possible, so the square is hashed
+        (&["e2e4", "d7d5", "e4e5", "f7f5"][..], 0x22a48b5a8e47ff78),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"][..],
+            0x652a607ca3f242c1,
+        ),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"][..],
+            0x00fdd303c946bdd9,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"][..],
+            0x3c8123ea7b067637,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"][..],
+            0x5c3f9b829b279560,
+        ),
+    ] {
+        assert_eq!(key(&play(moves)), expected, "{:?}", moves);
+    }
+}
+
+#[test]
+fn castling_is_encoded_as_the_king_taking_its_rook() {
+    let position = position("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    let castle = |rook| Move::Castle {
+        king: Square::E1,
+        rook,
+    };
+
+    // e1h1 and e1a1
+    assert_eq!(encode_move(castle(Square::H1)), 7 | 4 << 6);
+    assert_eq!(encode_move(castle(Square::A1)), 4 << 6);
+    for rook in [Square::H1, Square::A1] {
+        let mv = castle(rook);
+        assert_eq!(decode_move(&position, encode_move(mv)), Some(mv));
+    }
+
+    // The king move to g1 is not castling in the Polyglot format
+    assert_eq!(decode_move(&position, 6 | 4 << 6), None);
+}
+
+#[test]
+fn moves_round_trip_through_the_encoding() {
+    let position = position("r3k3/1P6/8/8/8/8/8/4K2R w K - 0 1");
+
+    // b7a8q, with the queen as promotion piece 4
+    let promotion = "b7a8q"
+        .parse::<UciMove>()
+        .unwrap()
+        .to_move(&position)
+        .unwrap();
+    assert_eq!(promotion.promotion(), Some(Role::Queen));
+    assert_eq!(encode_move(promotion), 56 | 49 << 6 | 4 << 12);
+
+    for mv in position.legal_moves() {
+        assert_eq!(
+            decode_move(&position, encode_move(mv)),
+            Some(mv),
+            "{:?}",
+            mv
+        );
+    }
+}
+
+/// Book moves of the position after the moves, in SAN with their weights.
+fn book_moves(builder: &BookBuilder, moves: &[&str]) -> Vec<(String, u16)> {
+    let position = play(moves);
+    builder
+        .build()
+        .moves(&position)
+        .into_iter()
+        .map(|(mv, weight)| (San::from_move(&position, mv).to_string(), weight))
+        .collect()
+}
+
+/// Builder with all games added, skipping the unfinished one.
+fn sample_builder(settings: BuildSettings) -> BookBuilder {
+    let mut builder = BookBuilder::new(settings);
+    for game in read_games(GAMES) {
+        let _ = builder.add_game(&game.unwrap());
+    }
+    builder
+}
+
+#[test]
+fn built_books_keep_moves_passing_the_filters() {
+    let games: Vec<Game> = read_games(GAMES).into_iter().map(Result::unwrap).collect();
+    let mut builder = BookBuilder::new(BuildSettings::default());
+    let added: Vec<_> = games.iter().map(|game| builder.add_game(game)).collect();
+    assert_eq!(added, [Ok(()), Ok(()), Ok(()), Ok(()), Err(UnknownResult)]);
+
+    // 1. e4 scored a win, a draw and a loss, while 1. d4 was only played once
+    let settings = BuildSettings {
+        min_games: 2,
+        ..BuildSettings::default()
+    };
+    assert_eq!(
+        book_moves(&sample_builder(settings), &[]),
+        [("e4".into(), 3)]
+    );
+
+    // 1... e5 scored a loss and a draw for black, 25 percent
+    assert_eq!(
+        book_moves(&sample_builder(settings), &["e2e4"]),
+        [("e5".into(), 1)]
+    );
+    let strict = BuildSettings {
+        min_score: 30,
+        ..settings
+    };
+    assert!(book_moves(&sample_builder(strict), &["e2e4"]).is_empty());
+
+    // Moves beyond the maximum ply are left out
+    let short = BuildSettings {
+        max_ply: 2,
+        min_games: 1,
+        ..settings
+    };
+    assert_eq!(
+        book_moves(&sample_builder(short), &[]),
+        [("e4".into(), 3), ("d4".into(), 2)]
+    );
+    assert!(book_moves(&sample_builder(short), &["e2e4", "e7e5"]).is_empty());
+}
+
+#[test]
+fn built_weights_are_scaled_to_fit() {
+    let games: Vec<Game> = read_games(GAMES).into_iter().map(Result::unwrap).collect();
+    let mut builder = BookBuilder::new(BuildSettings::default());
+
+    // 80000 half points for 1. e4 and 20000 for 1. d4 are halved to fit in a u16
+    for _ in 0..20_000 {
+        builder.add_game(&games[0]).unwrap();
+        builder.add_game(&games[3]).unwrap();
+    }
+    for _ in 0..20_000 {
+        builder.add_game(&games[0]).unwrap();
+    }
+    assert_eq!(
+        book_moves(&builder, &[]),
+        [("e4".into(), 40_000), ("d4".into(), 20_000)]
+    );
+}
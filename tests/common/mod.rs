@@ -0,0 +1,22 @@
+//! Fixtures shared by the integration tests.
+
+// Every test crate includes this module, but not all of them use every fixture
+#![allow(dead_code)]
+
+use shakmaty::{CastlingMode, Chess, fen::Fen};
+use std::fs;
+
+/// Position of a FEN, which must be legal.
+pub fn position(fen: &str) -> Chess {
+    let fen: Fen = fen.parse().unwrap();
+    fen.into_position(CastlingMode::Standard).unwrap()
+}
+
+/// Positions of `assets/fens.txt`.
+pub fn positions() -> Vec<Chess> {
+    let text = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fens.txt")).unwrap();
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(position)
+        .collect()
+}
@@ -0,0 +1,326 @@
+use checkm8::SearchCommand;
+use checkm8::book::{self, Book, Entry};
+use checkm8::bot::{
+    command::Command,
+    controller::{Controller, ControllerError},
+    input::InputListener,
+};
+use checkm8::logging::{LogConfig, Logger};
+use checkm8::scoring::evaluate;
+use checkm8::search::Searcher;
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use shakmaty::{CastlingMode, Chess, EnPassantMode, Position, fen::Fen, uci::UciMove};
+use std::{
+    sync::{Arc, atomic::AtomicBool},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+// Commands which must leave the previous position in place
+const MALFORMED: [&str; 11] = [
+    "position startpos moves e2e5",
+    "position startpos moves e7e5",
+    "position startpos moves e2e4 e7e5 e1e3",
+    "position startpos moves e2e4 e2e4",
+    "position fen 8/8/8/8/8/8/8/8 w - - 0 1",
+    "position fen 4k3/8/8/8/8/8/8/P3K3 w - - 0 1",
+    "position fen 4k3/8/8/8/8/8/4R3/4K3 b - - 0 1 moves e8e7",
+    "position fen 4k3/8/8/8/8/8/4R3/4K3 w - - 0 1",
+    "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 moves a1a8",
+    "position fen not a fen",
+    "position",
+];
+
+fn logger() -> Logger {
+    Logger::new(LogConfig {
+        path: concat!(env!("CARGO_TARGET_TMPDIR"), "/controller.log").into(),
+        ..LogConfig::default()
+    })
+}
+
+/// Runs the controller on a list of commands, returning the positions of its searches.
+fn searched_positions(commands: &[&str]) -> Vec<Chess> {
+    let (input_tx, input_rx) = unbounded();
+    let (cmd_tx, cmd_rx) = unbounded();
+    let (_info_tx, info_rx) = unbounded();
+
+    // The commands are read like a script
+    let script: String = commands
+        .iter()
+        .chain(&["quit"])
+        .map(|line| format!("{}\n", line))
+        .collect();
+    InputListener::new(input_tx, script.as_bytes()).run();
+
+    let abort = Arc::new(AtomicBool::new(false));
+    let discard = |_: &str| ();
+    Controller::with_output(input_rx, cmd_tx, info_rx, abort, logger(), discard)
+        .run()
+        .unwrap();
+
+    cmd_rx
+        .try_iter()
+        .filter_map(|command| match command {
+            SearchCommand::Start { position, .. } => Some(position),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A controller and a searcher on their own threads, driven like a user interface would.
+struct Session {
+    input_tx: Sender<Command>,
+    output_rx: Receiver<String>,
+    controller: JoinHandle<()>,
+}
+
+impl Session {
+    fn start() -> Self {
+        let (input_tx, input_rx) = unbounded();
+        let (cmd_tx, cmd_rx) = unbounded();
+        let (info_tx, info_rx) = unbounded();
+        let (output_tx, output_rx) = unbounded();
+
+        let searcher = Searcher::new(cmd_rx, info_tx);
+        let abort = searcher.abort_flag();
+        thread::spawn(|| searcher.run());
+
+        let controller = thread::spawn(move || {
+            Controller::with_output(input_rx, cmd_tx, info_rx, abort, logger(), output_tx)
+                .run()
+                .unwrap()
+        });
+
+        Session {
+            input_tx,
+            output_rx,
+            controller,
+        }
+    }
+
+    fn send(&self, line: &str) {
+        self.input_tx.send(line.parse().unwrap()).unwrap();
+    }
+
+    /// Output lines up to and including the first one starting with the prefix.
+    fn read_until(&self, prefix: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            let line = self
+                .output_rx
+                .recv_timeout(Duration::from_secs(10))
+                .unwrap_or_else(|_| panic!("no '{}' after {:?}", prefix, lines));
+            let done = line.starts_with(prefix);
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+    }
+
+    /// Quits the controller, returning the output which was not read yet.
+    fn quit(self) -> Vec<String> {
+        self.send("quit");
+        self.controller.join().unwrap();
+        self.output_rx.try_iter().collect()
+    }
+}
+
+fn fen(position: &Chess) -> String {
+    Fen::from_position(position, EnPassantMode::Legal).to_string()
+}
+
+#[test]
+fn malformed_position_commands_keep_the_previous_position() {
+    let mut expected = Chess::default();
+    for mv in ["e2e4", "c7c5"] {
+        let mv = mv.parse::<UciMove>().unwrap().to_move(&expected).unwrap();
+        expected = expected.play(mv).unwrap();
+    }
+
+    for malformed in MALFORMED {
+        let positions =
+            searched_positions(&["position startpos moves e2e4 c7c5", malformed, "go depth 1"]);
+        assert_eq!(positions.len(), 1, "{}", malformed);
+        assert_eq!(fen(&positions[0]), fen(&expected), "{}", malformed);
+    }
+}
+
+#[test]
+fn valid_position_commands_replace_the_position() {
+    let fen_text = "4k3/8/8/8/8/8/4R3/4K3 b - - 0 1";
+    let positions = searched_positions(&[
+        MALFORMED[0],
+        "not a command",
+        &format!("position fen {} moves e8d8", fen_text),
+        "go depth 1",
+    ]);
+
+    let expected: Chess = fen_text
+        .parse::<Fen>()
+        .unwrap()
+        .into_position(CastlingMode::Standard)
+        .unwrap();
+    let mv = "e8d8"
+        .parse::<UciMove>()
+        .unwrap()
+        .to_move(&expected)
+        .unwrap();
+    assert_eq!(fen(&positions[0]), fen(&expected.play(mv).unwrap()));
+}
+
+#[test]
+fn session_answers_the_handshake() {
+    let session = Session::start();
+    session.send("uci");
+    let lines = session.read_until("uciok");
+    assert_eq!(lines[0], "id name checkm8");
+    assert!(lines[1].starts_with("id author "));
+    assert!(
+        lines[2..lines.len() - 1]
+            .iter()
+            .all(|line| line.starts_with("option name "))
+    );
+
+    session.send("isready");
+    assert_eq!(session.read_until("readyok"), ["readyok"]);
+    assert!(session.quit().is_empty());
+}
+
+#[test]
+fn session_searches_the_position() {
+    let session = Session::start();
+    session.send("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    session.send("go depth 3");
+
+    let lines = session.read_until("bestmove");
+    assert_eq!(lines.len(), 4);
+    for (depth, line) in lines[..3].iter().enumerate() {
+        assert!(
+            line.starts_with(&format!("info depth {} ", depth + 1)),
+            "{}",
+            line
+        );
+        // Found checkmates are reported in moves rather than centipawns
+        assert!(line.contains(" score mate 1 "), "{}", line);
+    }
+    assert_eq!(lines[3], "bestmove a1a8");
+
+    session.send("position startpos");
+    session.send("go depth 1");
+    let lines = session.read_until("bestmove");
+    assert!(lines[0].contains(" score cp "), "{}", lines[0]);
+    assert!(session.quit().is_empty());
+}
+
+#[test]
+fn session_prints_the_evaluation_breakdown() {
+    let session = Session::start();
+    session.send("position startpos moves e2e4");
+    session.send("eval");
+
+    let lines = session.read_until("Final evaluation: ");
+    assert!(lines[0].contains("Term"), "{}", lines[0]);
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.trim_start().starts_with("Total |"))
+    );
+
+    // Black is to move, and the final evaluation is the static evaluation
+    let start = Chess::default();
+    let mv = "e2e4".parse::<UciMove>().unwrap().to_move(&start).unwrap();
+    let score = evaluate(&start.play(mv).unwrap(), 0);
+    let pawns = |score: i32| format!("{:6.2}", score as f64 / 100.0);
+    assert_eq!(
+        lines.last().unwrap(),
+        &format!("Final evaluation: {} (white side)", pawns(-score))
+    );
+    assert_eq!(
+        session.quit(),
+        [format!("Final evaluation: {} (side to move)", pawns(score))]
+    );
+}
+
+#[test]
+fn session_answers_game_over_positions_and_errors() {
+    let session = Session::start();
+    session.send("position fen 7k/6Q1/6K1/8/8/8/8/8 b - - 0 1");
+    session.send("go depth 3");
+    assert_eq!(
+        session.read_until("bestmove"),
+        ["info depth 0 score mate 0", "bestmove 0000"]
+    );
+
+    session.send("position startpos moves e2e5");
+    assert_eq!(
+        session.quit(),
+        ["info string Error: illegal move 'e2e5' (move 1), command ignored"]
+    );
+}
+
+#[test]
+fn commands_to_a_stopped_searcher_end_the_session() {
+    for command in ["go depth 1", "stop", "setoption name Seed value 3"] {
+        let (input_tx, input_rx) = unbounded();
+        let (cmd_tx, cmd_rx) = unbounded();
+        // The info channel stays open, so only the failed command tells that the searcher is gone
+        let (_info_tx, info_rx) = unbounded();
+        let (output_tx, output_rx) = unbounded();
+        drop(cmd_rx);
+
+        let script = format!("{}\nisready\nquit\n", command);
+        InputListener::new(input_tx, script.as_bytes()).run();
+        let abort = Arc::new(AtomicBool::new(false));
+        let result =
+            Controller::with_output(input_rx, cmd_tx, info_rx, abort, logger(), output_tx).run();
+
+        assert_eq!(result, Err(ControllerError::SearchStopped), "{}", command);
+        let output: Vec<String> = output_rx.try_iter().collect();
+        assert_eq!(
+            output,
+            ["info string Error: search thread stopped"],
+            "{}",
+            command
+        );
+    }
+}
+
+/// Book moves picked from the start position in a session, seeded unless `seed` is `None`.
+fn book_moves(seed: Option<u64>) -> Vec<String> {
+    let position = Chess::default();
+    let entries = ["e2e4", "d2d4", "c2c4", "g1f3"]
+        .iter()
+        .map(|mv| Entry {
+            key: book::key(&position),
+            mv: book::encode_move(mv.parse::<UciMove>().unwrap().to_move(&position).unwrap()),
+            weight: 1,
+            learn: 0,
+        })
+        .collect();
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/controller.bin");
+    Book::from_entries(entries).save(path).unwrap();
+
+    let session = Session::start();
+    session.send(&format!("setoption name BookFile value {}", path));
+    session.send("setoption name OwnBook value true");
+    if let Some(seed) = seed {
+        session.send(&format!("setoption name Seed value {}", seed));
+    }
+
+    let mut moves = Vec::new();
+    for _ in 0..12 {
+        session.send("position startpos");
+        session.send("go");
+        moves.push(session.read_until("bestmove").pop().unwrap());
+    }
+    session.quit();
+    moves
+}
+
+#[test]
+fn book_moves_are_reproduced_from_the_seed() {
+    assert_eq!(book_moves(None), book_moves(None));
+    assert_eq!(book_moves(Some(7)), book_moves(Some(7)));
+    assert_ne!(book_moves(Some(7)), book_moves(Some(8)));
+}
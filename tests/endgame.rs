@@ -0,0 +1,96 @@
+mod common;
+
+use checkm8::scoring::endgame::{KNOWN_WIN, SCALE_NORMAL, evaluate, probe, scale, scale_factor};
+use common::position;
+
+/// Specialised score from the perspective of the side to move.
+fn score(fen: &str) -> Option<i32> {
+    evaluate(&position(fen))
+}
+
+#[test]
+fn endgames_are_found_for_either_side() {
+    for (fen, name) in [
+        ("8/8/8/3k4/8/8/8/Q3K3 w - - 0 1", "KQK"),
+        ("3qk3/8/8/8/3K4/8/8/8 w - - 0 1", "KQK"),
+        ("8/8/8/3k4/8/8/8/R3K3 b - - 0 1", "KRK"),
+        ("8/8/8/3k4/8/8/8/1N2KB2 w - - 0 1", "KBNK"),
+        ("8/8/8/3k4/8/8/8/1N2K1N1 w - - 0 1", "KNNK"),
+        ("8/8/8/3k4/8/8/P7/4KB2 w - - 0 1", "KBPK"),
+    ] {
+        assert_eq!(
+            probe(&position(fen)).map(|endgame| endgame.name),
+            Some(name)
+        );
+    }
+
+    assert!(probe(&position("8/8/8/3k4/8/8/3P4/3QK3 w - - 0 1")).is_none());
+    assert!(probe(&position("8/8/8/3k4/8/8/8/3BK3 w - - 0 1")).is_none());
+}
+
+#[test]
+fn lone_kings_are_driven_to_the_edge() {
+    for (centre, edge) in [
+        (
+            "8/8/8/3k4/8/8/8/Q3K3 w - - 0 1",
+            "3k4/8/8/8/8/8/8/Q3K3 w - - 0 1",
+        ),
+        (
+            "8/8/8/3k4/8/8/8/R3K3 w - - 0 1",
+            "3k4/8/8/8/8/8/8/R3K3 w - - 0 1",
+        ),
+    ] {
+        let centre = score(centre).unwrap();
+        assert!(centre > KNOWN_WIN);
+        assert!(score(edge).unwrap() > centre);
+    }
+
+    // The same position with the weak side to move
+    assert!(score("8/8/8/3k4/8/8/8/Q3K3 b - - 0 1").unwrap() < -KNOWN_WIN);
+}
+
+#[test]
+fn bishop_and_knight_mate_in_the_bishops_corner() {
+    // Light squared bishop, which mates on a8 and h1
+    let right = score("k7/8/8/8/8/8/8/1N2KB2 w - - 0 1").unwrap();
+    let wrong = score("7k/8/8/8/8/8/8/1N2KB2 w - - 0 1").unwrap();
+    assert!(wrong > KNOWN_WIN);
+    assert!(right > wrong);
+}
+
+#[test]
+fn drawn_endgames_score_zero() {
+    assert_eq!(score("8/8/8/3k4/8/8/8/1N2K1N1 w - - 0 1"), Some(0));
+    assert_eq!(score("8/8/8/3k4/8/8/8/1N2K1N1 b - - 0 1"), Some(0));
+    // Rook pawn with the wrong bishop and the defending king in the corner
+    assert_eq!(score("k7/8/8/8/8/8/P7/2B1K3 w - - 0 1"), Some(0));
+    assert_eq!(score("k7/8/8/8/8/8/P7/4KB2 w - - 0 1"), None);
+}
+
+#[test]
+fn hanging_pieces_leave_the_position_to_the_search() {
+    // The weak king takes the undefended queen or rook
+    assert_eq!(score("8/8/8/3k4/3Q4/8/8/4K3 b - - 0 1"), None);
+    assert_eq!(score("8/8/8/3k4/4R3/8/8/K7 b - - 0 1"), None);
+    assert_eq!(score("8/8/8/3k4/4N3/8/8/K4B2 b - - 0 1"), None);
+
+    // Defended, or with the strong side to move
+    assert!(score("8/8/8/3k4/3Q4/4K3/8/8 b - - 0 1").unwrap() < -KNOWN_WIN);
+    assert!(score("8/8/8/3k4/4R3/8/8/K7 w - - 0 1").unwrap() > KNOWN_WIN);
+    // The bishop defends the knight through the square of the weak king
+    assert!(score("8/8/8/8/4N3/3k4/8/1B2K3 b - - 0 1").unwrap() < -KNOWN_WIN);
+}
+
+#[test]
+fn drawish_material_is_scaled_down() {
+    // Bishops of opposite colours halve the score
+    let opposite = position("8/5b1p/4k3/8/2P5/1P2K3/P4B2/8 w - - 0 1");
+    assert_eq!(scale_factor(&opposite, 100), SCALE_NORMAL / 2);
+    assert_eq!(scale(&opposite, 100), 50);
+    let same = position("8/6bp/4k3/8/2P5/1P2K3/P4B2/8 w - - 0 1");
+    assert_eq!(scale_factor(&same, 100), SCALE_NORMAL);
+
+    // A lone minor piece can not win
+    let bishop = position("8/8/8/3k4/8/8/8/3BK3 w - - 0 1");
+    assert_eq!(scale(&bishop, 300), 0);
+}
@@ -0,0 +1,102 @@
+use checkm8::{
+    Score, SearchControl,
+    engine::{Engine, EngineError, PositionError},
+};
+use shakmaty::{CastlingMode, Move, Position, uci::UciMove};
+use std::{thread, time::Duration};
+
+const MATE_IN_ONE: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+#[test]
+fn search_finds_mate_in_one() {
+    let mut engine = Engine::new();
+    engine.set_position(Some(MATE_IN_ONE), &[]).unwrap();
+
+    let mut depths = Vec::new();
+    let result = engine
+        .search_with(SearchControl::ToDepth(2), |info| depths.push(info.depth))
+        .unwrap();
+    assert_eq!(depths, [1, 2]);
+    let best_move = UciMove::from_move(result.best_move, CastlingMode::Standard);
+    assert_eq!(best_move.to_string(), "a1a8");
+    assert_eq!(result.pv.first(), Some(&result.best_move));
+    assert_eq!(result.mate, Some(1));
+    assert_eq!(result.depth, 2);
+    assert_eq!(result.seldepth, 2);
+    assert!(result.nodes > 0);
+}
+
+#[test]
+fn invalid_positions_keep_the_previous_one() {
+    let mut engine = Engine::new();
+    engine.set_position(None, &["e2e4"]).unwrap();
+    let board = engine.position().board().clone();
+
+    assert!(matches!(
+        engine.set_position(Some("not a fen"), &[]),
+        Err(EngineError::Position(PositionError::InvalidPosition { .. }))
+    ));
+    assert_eq!(
+        engine.set_position(None, &["e2e4", "e2e4"]),
+        Err(EngineError::Position(PositionError::IllegalMove {
+            mv: "e2e4".into(),
+            number: 2
+        }))
+    );
+    assert_eq!(engine.position().board(), &board);
+}
+
+#[test]
+fn checkmated_positions_fail_to_search() {
+    let mut engine = Engine::new();
+    engine
+        .set_position(Some("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"), &[])
+        .unwrap();
+    assert!(matches!(
+        engine.search(SearchControl::ToDepth(1)),
+        Err(EngineError::Search(_))
+    ));
+
+    // The engine keeps searching after a failed search
+    engine.set_position(None, &[]).unwrap();
+    assert!(engine.search(SearchControl::ToDepth(1)).is_ok());
+}
+
+#[test]
+fn cancelled_searches_return_a_legal_move() {
+    let engine = Engine::new();
+    let cancel = engine.cancel_handle();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        cancel.cancel();
+    });
+
+    let result = engine.search(SearchControl::ToDepth(u8::MAX)).unwrap();
+    canceller.join().unwrap();
+    assert!(engine.position().is_legal(result.best_move));
+}
+
+/// Moves of a short game the engine plays against itself, seeded unless `seed` is `None`.
+fn self_play(seed: Option<u64>, randomness: Score) -> Vec<Move> {
+    let mut engine = Engine::new();
+    if let Some(seed) = seed {
+        engine.set_seed(seed);
+    }
+    engine.set_randomness(randomness);
+
+    let mut moves = Vec::new();
+    for _ in 0..8 {
+        let mv = engine.search(SearchControl::ToDepth(2)).unwrap().best_move;
+        engine.set_chess(engine.position().clone().play(mv).unwrap());
+        moves.push(mv);
+    }
+    moves
+}
+
+#[test]
+fn games_are_reproduced_from_the_seed() {
+    assert_eq!(self_play(None, 0), self_play(None, 0));
+    assert_eq!(self_play(None, 100), self_play(None, 100));
+    assert_eq!(self_play(Some(7), 100), self_play(Some(7), 100));
+    assert_ne!(self_play(Some(7), 100), self_play(Some(8), 100));
+}
@@ -0,0 +1,141 @@
+use checkm8::{
+    SearchControl,
+    engine::Engine,
+    epd::{Epd, EpdError, Runner, read_suite},
+    search::mate_distance,
+};
+use shakmaty::{Move, san::San};
+
+const SUITES: [&str; 2] = [
+    include_str!("../assets/mates.epd"),
+    include_str!("../assets/wac.epd"),
+];
+
+fn san(epd: &Epd, moves: &[Move]) -> Vec<String> {
+    moves
+        .iter()
+        .map(|&mv| San::from_move(&epd.position, mv).to_string())
+        .collect()
+}
+
+#[test]
+fn shipped_suites_parse() {
+    for suite in SUITES {
+        for (line, epd) in read_suite(suite) {
+            let epd = epd.unwrap_or_else(|err| panic!("line {}: {}", line, err));
+            assert!(epd.id.is_some(), "line {}", line);
+        }
+    }
+}
+
+#[test]
+fn mate_suite_holds_the_shortest_mates() {
+    let mut engine = Engine::new();
+    for (_, epd) in read_suite(SUITES[0]) {
+        let epd = epd.unwrap();
+        let mate = epd.mate.unwrap();
+        engine.set_chess(epd.position.clone());
+
+        // A full-width search prefers the shortest mate within its depth
+        let depth = 2 * mate as u8 - 1;
+        let result = engine.search(SearchControl::ToDepth(depth)).unwrap();
+        assert_eq!(result.mate, Some(mate as i32), "{:?}", epd.id);
+        assert!(
+            epd.is_solution(result.best_move, Some(result.score), depth),
+            "{:?}",
+            epd.id
+        );
+    }
+}
+
+#[test]
+fn runner_reports_solved_and_failed_positions() {
+    let mut runner = Runner::new();
+
+    let epd: Epd = "5r1k/6pp/7N/3Q4/8/8/6PP/6K1 w - - dm 2; id \"mate\";"
+        .parse()
+        .unwrap();
+    let attempt = runner.run(&epd, SearchControl::ToDepth(3));
+    assert!(attempt.solved);
+    assert_eq!(attempt.depth, 3);
+    assert!(
+        attempt
+            .solution_nodes
+            .is_some_and(|nodes| nodes <= attempt.nodes)
+    );
+    assert!(attempt.to_json().contains("\"mate\":2"));
+
+    // Finding the move to avoid fails the position
+    let epd: Epd = "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - am Rd8#;"
+        .parse()
+        .unwrap();
+    let attempt = runner.run(&epd, SearchControl::ToDepth(2));
+    assert!(!attempt.solved);
+    assert_eq!(attempt.best_move.to_string(), "Rd8#");
+    assert_eq!(attempt.solution_time, None);
+}
+
+#[test]
+fn operations_are_read() {
+    let epd: Epd = r#"r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq -
+        bm Bb5 f1c4; am Qe2; c0 "comment; with \"quotes\""; id "open\"ing""#
+        .parse()
+        .unwrap();
+    assert_eq!(san(&epd, &epd.best_moves), ["Bb5", "Bc4"]);
+    assert_eq!(san(&epd, &epd.avoid_moves), ["Qe2"]);
+    assert_eq!(epd.id.as_deref(), Some("open\"ing"));
+    assert_eq!(epd.mate, None);
+    assert_eq!(epd.solution(), "bm Bb5 Bc4; am Qe2");
+
+    let epd: Epd = "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - dm 1; bm Rd8#;"
+        .parse()
+        .unwrap();
+    assert_eq!(epd.mate, Some(1));
+    assert_eq!(epd.solution(), "bm Rd8#; dm 1");
+}
+
+#[test]
+fn invalid_lines_are_rejected() {
+    let cases = [
+        (
+            "8/8/8/8/8/8/8/8 w - - bm e4;",
+            EpdError::InvalidPosition("8/8/8/8/8/8/8/8 w - -".into()),
+        ),
+        ("7k/6Q1/6K1/8/8/8/8/8 b - - bm Kh8;", EpdError::GameOver),
+        (
+            "4k3/8/8/8/8/8/8/4K2R w K - bm O-O-O;",
+            EpdError::IllegalMove("O-O-O".into()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K2R w K - dm x;",
+            EpdError::InvalidOperation("dm".into()),
+        ),
+        ("4k3/8/8/8/8/8/8/4K2R w K - id \"x\";", EpdError::NoSolution),
+    ];
+    for (line, err) in cases {
+        assert_eq!(line.parse::<Epd>().unwrap_err(), err, "{}", line);
+    }
+}
+
+#[test]
+fn mate_distances_do_not_depend_on_the_depth() {
+    let mut engine = Engine::new();
+    // Mating in one, being mated in one and no mate at all
+    for (fen, mate) in [
+        ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", Some(1)),
+        ("k7/8/1K6/8/8/8/8/7R b - - 0 1", Some(-1)),
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", None),
+    ] {
+        engine.set_position(Some(fen), &[]).unwrap();
+        for depth in 2..=4 {
+            let result = engine.search(SearchControl::ToDepth(depth)).unwrap();
+            assert_eq!(
+                mate_distance(result.score, depth),
+                mate,
+                "{} {}",
+                fen,
+                depth
+            );
+        }
+    }
+}
@@ -0,0 +1,219 @@
+mod common;
+
+use checkm8::scoring::incremental::IncrementalEval;
+use common::positions;
+use rand::{RngExt, SeedableRng, rngs::Xoshiro256PlusPlus};
+use shakmaty::{Chess, Color, Position, Role};
+
+type Score = i32;
+
+// Random moves played from every position
+const MOVES: usize = 8;
+
+/// Incremental material and PST score plus mobility, as used by `evaluate`.
+fn incremental_score(state: &IncrementalEval, position: &Chess) -> Score {
+    state.score(position.turn()) + position.legal_moves().len() as Score * 2
+}
+
+#[test]
+fn incremental_eval_matches_baseline_evaluation() {
+    for position in positions() {
+        let state = IncrementalEval::new(&position);
+        assert_eq!(
+            incremental_score(&state, &position),
+            baseline_evaluate(&position)
+        );
+    }
+}
+
+#[test]
+fn incremental_eval_matches_baseline_along_moves() {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+    for start in positions() {
+        let mut position = start.clone();
+        let mut state = IncrementalEval::new(&position);
+        let mut line = Vec::new();
+
+        for _ in 0..MOVES {
+            let moves = position.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let mv = moves[rng.random_range(0..moves.len())];
+
+            state.update(&position, mv);
+            line.push((position.clone(), mv));
+            position = position.play(mv).unwrap();
+
+            assert_eq!(state, IncrementalEval::new(&position));
+            assert_eq!(
+                incremental_score(&state, &position),
+                baseline_evaluate(&position)
+            );
+        }
+
+        // Taking the moves back must restore every intermediate state
+        while let Some((previous, mv)) = line.pop() {
+            state.undo(&previous, mv);
+            assert_eq!(state, IncrementalEval::new(&previous));
+            assert_eq!(
+                incremental_score(&state, &previous),
+                baseline_evaluate(&previous)
+            );
+        }
+        assert_eq!(state, IncrementalEval::new(&start));
+    }
+}
+
+/// Copy of the original evaluation, which tapers the PST value of every piece on its own.
+///
+/// Checkmates and draws are left out, as they never reach the incremental state.
+fn baseline_evaluate(position: &Chess) -> Score {
+    let phase = game_phase(position);
+    let max_phase = 24;
+
+    let mut score = 0;
+    for (square, piece) in position.board() {
+        let idx = match piece.color {
+            Color::White => square as usize,
+            Color::Black => mirror_idx(square as usize),
+        };
+
+        let mat = piece_value(piece.role);
+        let pst = match piece.role {
+            Role::Pawn => blend_pst(PAWN_PST_MG[idx], PAWN_PST_EG[idx], phase, max_phase),
+            Role::Knight => blend_pst(KNIGHT_PST_MG[idx], KNIGHT_PST_EG[idx], phase, max_phase),
+            Role::Bishop => blend_pst(BISHOP_PST_MG[idx], BISHOP_PST_EG[idx], phase, max_phase),
+            Role::Rook => blend_pst(ROOK_PST_MG[idx], ROOK_PST_EG[idx], phase, max_phase),
+            Role::Queen => blend_pst(QUEEN_PST_MG[idx], QUEEN_PST_EG[idx], phase, max_phase),
+            Role::King => blend_pst(KING_PST_MG[idx], KING_PST_EG[idx], phase, max_phase),
+        };
+
+        if piece.color == position.turn() {
+            score += mat + pst;
+        } else {
+            score -= mat + pst;
+        }
+    }
+
+    score + position.legal_moves().len() as Score * 2
+}
+
+fn piece_value(role: Role) -> Score {
+    match role {
+        Role::Pawn => 100,
+        Role::Knight => 320,
+        Role::Bishop => 330,
+        Role::Rook => 500,
+        Role::Queen => 900,
+        Role::King => 10000,
+    }
+}
+
+fn mirror_idx(idx: usize) -> usize {
+    (7 - idx / 8) * 8 + idx % 8
+}
+
+fn game_phase(position: &Chess) -> i32 {
+    let mut phase = 0;
+    for (_, piece) in position.board() {
+        phase += match piece.role {
+            Role::Pawn | Role::King => 0,
+            Role::Knight | Role::Bishop => 1,
+            Role::Rook => 2,
+            Role::Queen => 4,
+        };
+    }
+    phase
+}
+
+fn blend_pst(mg: Score, eg: Score, phase: i32, max_phase: i32) -> Score {
+    (mg * phase + eg * (max_phase - phase)) / max_phase
+}
+
+// --- PAWN PSTs ---
+
+const PAWN_PST_MG: [Score; 64] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 5, 10, 10, -20, -20, 10, 10, 5, 5, -5, -10, 0, 0, -10, -5, 5, 0, 0, 0,
+    20, 20, 0, 0, 0, 5, 5, 10, 25, 25, 10, 5, 5, 10, 10, 20, 30, 30, 20, 10, 10, 50, 50, 50, 50,
+    50, 50, 50, 50, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const PAWN_PST_EG: [Score; 64] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 10, 15, 15, -10, -10, 15, 15, 10, 10, 0, -5, 5, 5, -5, 0, 10, 0, 5, 10,
+    25, 25, 10, 5, 0, 10, 10, 20, 35, 35, 20, 10, 10, 15, 15, 25, 35, 35, 25, 15, 15, 60, 60, 60,
+    60, 60, 60, 60, 60, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+// --- KNIGHT PSTs ---
+
+const KNIGHT_PST_MG: [Score; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50, -40, -20, 0, 0, 0, 0, -20, -40, -30, 0, 10, 15, 15, 10,
+    0, -30, -30, 5, 15, 20, 20, 15, 5, -30, -30, 0, 15, 20, 20, 15, 0, -30, -30, 5, 10, 15, 15, 10,
+    5, -30, -40, -20, 0, 5, 5, 0, -20, -40, -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+const KNIGHT_PST_EG: [Score; 64] = [
+    -40, -30, -25, -25, -25, -25, -30, -40, -30, -15, -5, -5, -5, -5, -15, -30, -25, -5, 5, 10, 10,
+    5, -5, -25, -25, 0, 10, 15, 15, 10, 0, -25, -25, -5, 10, 15, 15, 10, -5, -25, -25, 0, 5, 10,
+    10, 5, 0, -25, -30, -15, -5, 0, 0, -5, -15, -30, -40, -30, -25, -25, -25, -25, -30, -40,
+];
+
+// --- BISHOP PSTs ---
+
+const BISHOP_PST_MG: [Score; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20, -10, 0, 0, 0, 0, 0, 0, -10, -10, 0, 5, 10, 10, 5, 0,
+    -10, -10, 5, 5, 10, 10, 5, 5, -10, -10, 0, 10, 10, 10, 10, 0, -10, -10, 10, 10, 10, 10, 10, 10,
+    -10, -10, 5, 0, 0, 0, 0, 5, -10, -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+const BISHOP_PST_EG: [Score; 64] = [
+    -15, -5, -5, -5, -5, -5, -5, -15, -5, 5, 5, 5, 5, 5, 5, -5, -5, 5, 10, 15, 15, 10, 5, -5, -5,
+    10, 10, 15, 15, 10, 10, -5, -5, 5, 15, 15, 15, 15, 5, -5, -5, 10, 15, 15, 15, 15, 10, -5, -5,
+    5, 5, 5, 5, 5, 5, -5, -15, -5, -5, -5, -5, -5, -5, -15,
+];
+
+// --- ROOK PSTs ---
+
+const ROOK_PST_MG: [Score; 64] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 5, 10, 10, 10, 10, 10, 10, 5, -5, 0, 0, 0, 0, 0, 0, -5, -5, 0, 0, 0, 0,
+    0, 0, -5, -5, 0, 0, 0, 0, 0, 0, -5, -5, 0, 0, 0, 0, 0, 0, -5, -5, 0, 0, 0, 0, 0, 0, -5, 0, 0,
+    0, 5, 5, 0, 0, 0,
+];
+
+const ROOK_PST_EG: [Score; 64] = [
+    0, 0, 5, 10, 10, 5, 0, 0, 5, 10, 15, 15, 15, 15, 10, 5, 0, 5, 10, 15, 15, 10, 5, 0, 0, 5, 10,
+    15, 15, 10, 5, 0, 0, 5, 10, 15, 15, 10, 5, 0, 0, 5, 10, 15, 15, 10, 5, 0, -5, 0, 5, 10, 10, 5,
+    0, -5, 0, 0, 0, 5, 5, 0, 0, 0,
+];
+
+// --- QUEEN PSTs ---
+
+const QUEEN_PST_MG: [Score; 64] = [
+    -20, -10, -10, -5, -5, -10, -10, -20, -10, 0, 0, 0, 0, 0, 0, -10, -10, 0, 5, 5, 5, 5, 0, -10,
+    -5, 0, 5, 5, 5, 5, 0, -5, 0, 0, 5, 5, 5, 5, 0, -5, -10, 5, 5, 5, 5, 5, 0, -10, -10, 0, 5, 0, 0,
+    0, 0, -10, -20, -10, -10, -5, -5, -10, -10, -20,
+];
+
+const QUEEN_PST_EG: [Score; 64] = [
+    -15, -10, -5, -5, -5, -5, -10, -15, -10, 0, 0, 0, 0, 0, 0, -10, -5, 0, 5, 10, 10, 5, 0, -5, -5,
+    0, 10, 15, 15, 10, 0, -5, -5, 0, 10, 15, 15, 10, 0, -5, -5, 0, 5, 10, 10, 5, 0, -5, -10, 0, 0,
+    5, 5, 0, 0, -10, -15, -10, -5, -5, -5, -5, -10, -15,
+];
+
+// --- KING PSTs ---
+
+const KING_PST_MG: [Score; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30, -30, -40, -40, -50, -50, -40, -40, -30, -30, -40, -40,
+    -50, -50, -40, -40, -30, -30, -40, -40, -50, -50, -40, -40, -30, -20, -30, -30, -40, -40, -30,
+    -30, -20, -10, -20, -20, -20, -20, -20, -20, -10, 20, 20, 0, 0, 0, 0, 20, 20, 20, 30, 10, 0, 0,
+    10, 30, 20,
+];
+
+const KING_PST_EG: [Score; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50, -30, -20, -10, 0, 0, -10, -20, -30, -30, -10, 20, 30,
+    30, 20, -10, -30, -30, -10, 30, 40, 40, 30, -10, -30, -30, -10, 30, 40, 40, 30, -10, -30, -30,
+    -10, 20, 30, 30, 20, -10, -30, -30, -20, -10, 0, 0, -10, -20, -30, -50, -40, -30, -20, -20,
+    -30, -40, -50,
+];
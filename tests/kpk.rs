@@ -0,0 +1,171 @@
+use checkm8::scoring::endgame::{Wdl, kpk_probe};
+use rand::{RngExt, SeedableRng, rngs::Xoshiro256PlusPlus};
+use shakmaty::{
+    Board, CastlingMode, Chess, Color, EnPassantMode, File, Position, Rank, Role, Setup, Square,
+    fen::Fen,
+};
+use std::collections::HashMap;
+
+// Random positions compared with the brute force results
+const SAMPLES: usize = 2000;
+
+/// Side to move, strong king, weak king and pawn, with white as the strong side.
+type Key = (Color, Square, Square, Square);
+
+/// Result of a move: a position with the pawn on the same file, or a decided outcome.
+enum Successor {
+    Position(usize),
+    Outcome(bool),
+}
+
+/// The legal position of a key, with the given colour as the strong side.
+fn position((turn, strong_king, weak_king, pawn): Key, strong: Color) -> Option<Chess> {
+    if strong_king == weak_king || pawn == strong_king || pawn == weak_king {
+        return None;
+    }
+
+    let orient = |square: Square| strong.fold_wb(square, square.flip_vertical());
+    let mut board = Board::empty();
+    board.set_piece_at(orient(strong_king), strong.king());
+    board.set_piece_at(orient(weak_king), (!strong).king());
+    board.set_piece_at(orient(pawn), strong.pawn());
+
+    let mut setup = Setup::empty();
+    setup.board = board;
+    setup.turn = strong.fold_wb(turn, !turn);
+    setup.position(CastlingMode::Standard).ok()
+}
+
+fn key(position: &Chess) -> Key {
+    let board = position.board();
+    (
+        position.turn(),
+        board.king_of(Color::White).unwrap(),
+        board.king_of(Color::Black).unwrap(),
+        board.pawns().first().unwrap(),
+    )
+}
+
+/// Whether a promotion wins, which it does unless black captures the new piece or is
+/// stalemated, or the pawn became a minor piece.
+fn promotion_wins(position: &Chess, role: Role) -> bool {
+    let moves = position.legal_moves();
+    if moves.is_empty() {
+        return position.is_checkmate();
+    }
+    matches!(role, Role::Queen | Role::Rook) && !moves.iter().any(|mv| mv.is_capture())
+}
+
+/// Whether white wins, for every legal position with the white pawn on the file.
+///
+/// All positions are expanded with the move generator, after which wins are propagated until
+/// nothing changes: white needs one winning move, black loses when every move loses.
+fn solve(file: File) -> HashMap<Key, bool> {
+    let mut positions = Vec::new();
+    for rank in 1..7 {
+        let pawn = Square::from_coords(file, Rank::new(rank));
+        for strong_king in (0..64).map(Square::new) {
+            for weak_king in (0..64).map(Square::new) {
+                for turn in [Color::White, Color::Black] {
+                    positions.extend(position((turn, strong_king, weak_king, pawn), Color::White));
+                }
+            }
+        }
+    }
+
+    let index: HashMap<Key, usize> = positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| (key(position), i))
+        .collect();
+
+    let successors: Vec<Vec<Successor>> = positions
+        .iter()
+        .map(|position| {
+            let moves = position.legal_moves();
+            if moves.is_empty() {
+                // Only black can be checkmated
+                let mated = position.is_checkmate() && position.turn() == Color::Black;
+                return vec![Successor::Outcome(mated)];
+            }
+
+            moves
+                .iter()
+                .map(|&mv| {
+                    let next = position.clone().play(mv).unwrap();
+                    match mv.promotion() {
+                        Some(role) => Successor::Outcome(promotion_wins(&next, role)),
+                        // Black took the pawn
+                        None if mv.is_capture() => Successor::Outcome(false),
+                        None => Successor::Position(index[&key(&next)]),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut wins = vec![false; positions.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..positions.len() {
+            if wins[i] {
+                continue;
+            }
+
+            let wins_after = |successor: &Successor| match *successor {
+                Successor::Position(j) => wins[j],
+                Successor::Outcome(win) => win,
+            };
+            let win = match positions[i].turn() {
+                Color::White => successors[i].iter().any(wins_after),
+                Color::Black => successors[i].iter().all(wins_after),
+            };
+
+            if win {
+                wins[i] = true;
+                changed = true;
+            }
+        }
+    }
+
+    index.into_iter().map(|(key, i)| (key, wins[i])).collect()
+}
+
+#[test]
+fn bitbase_matches_brute_force() {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+    let mut solved: Vec<Option<HashMap<Key, bool>>> = (0..8).map(|_| None).collect();
+
+    let mut samples = 0;
+    while samples < SAMPLES {
+        let file = File::new(rng.random_range(0..8));
+        let pawn = Square::from_coords(file, Rank::new(rng.random_range(1..7)));
+        let turn = if rng.random::<bool>() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let strong_king = Square::new(rng.random_range(0..64));
+        let weak_king = Square::new(rng.random_range(0..64));
+        let key = (turn, strong_king, weak_king, pawn);
+
+        let Some(white) = position(key, Color::White) else {
+            continue;
+        };
+        let wins = solved[file as usize].get_or_insert_with(|| solve(file))[&key];
+        let expected = match (wins, turn) {
+            (false, _) => Wdl::Draw,
+            (true, Color::White) => Wdl::Win,
+            (true, Color::Black) => Wdl::Loss,
+        };
+
+        // The same position with colours swapped has the same result for the side to move
+        let black = position(key, Color::Black).unwrap();
+        for position in [white, black] {
+            let fen = Fen::from_position(&position, EnPassantMode::Legal);
+            assert_eq!(kpk_probe(&position), Some(expected), "{}", fen);
+        }
+        samples += 1;
+    }
+}
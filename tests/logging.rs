@@ -0,0 +1,65 @@
+use checkm8::logging::{Kind, Level, LogConfig, Logger};
+use std::fs;
+
+/// Fresh directory for the log files of a test.
+fn log_dir(name: &str) -> String {
+    let dir = format!("{}/logging-{}", env!("CARGO_TARGET_TMPDIR"), name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn logs_are_rotated_beyond_their_size() {
+    let path = format!("{}/engine.log", log_dir("rotation"));
+    let logger = Logger::new(LogConfig {
+        path: path.clone(),
+        max_size: 100,
+        ..LogConfig::default()
+    });
+    for index in 0..50 {
+        logger.log(Level::Info, Kind::In, &format!("go depth {}", index));
+    }
+    drop(logger);
+
+    // The last lines are kept in the current file and three rotated ones
+    let mut lines = Vec::new();
+    for suffix in ["", ".1", ".2", ".3"] {
+        let content = fs::read_to_string(format!("{}{}", path, suffix)).unwrap();
+        assert!(content.len() < 100 + 20, "{}{}", path, suffix);
+        lines.splice(0..0, content.lines().map(String::from));
+    }
+    assert!(fs::metadata(format!("{}.4", path)).is_err());
+    assert_eq!(lines.last().unwrap(), " IN: 'go depth 49'");
+    for (line, index) in lines.iter().rev().zip((0..50).rev()) {
+        assert_eq!(line, &format!(" IN: 'go depth {}'", index));
+    }
+}
+
+#[test]
+fn json_records_are_escaped() {
+    let path = format!("{}/engine.log", log_dir("json"));
+    let logger = Logger::new(LogConfig {
+        path: path.clone(),
+        json: true,
+        ..LogConfig::default()
+    });
+    logger.log(Level::Warn, Kind::Engine, "quoted \"name\"\tand a \\");
+    logger.log(Level::Debug, Kind::Search, "dropped below the level");
+    logger.flush();
+
+    let content = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 1);
+    let (time, record) = lines[0]
+        .strip_prefix("{\"time\":\"")
+        .unwrap()
+        .split_once('"')
+        .unwrap();
+    assert!(time.contains('T'), "{}", time);
+    assert_eq!(
+        record,
+        ",\"level\":\"warn\",\"kind\":\"engine\",\
+         \"message\":\"quoted \\\"name\\\"\\u0009and a \\\\\"}"
+    );
+}
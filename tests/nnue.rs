@@ -0,0 +1,49 @@
+mod common;
+
+use checkm8::scoring::nnue::{Accumulator, INPUTS, Network};
+use common::positions;
+use rand::{RngExt, SeedableRng, rngs::Xoshiro256PlusPlus};
+use shakmaty::Position;
+
+const HIDDEN: usize = 8;
+
+// Random moves played from every position
+const MOVES: usize = 8;
+
+/// Network file with random weights.
+fn random_network(rng: &mut Xoshiro256PlusPlus) -> Network {
+    let mut bytes = b"CM8N".to_vec();
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend((HIDDEN as u32).to_le_bytes());
+    for _ in 0..INPUTS * HIDDEN + HIDDEN + 2 * HIDDEN {
+        bytes.extend(rng.random_range(-512i16..512).to_le_bytes());
+    }
+    bytes.extend(rng.random_range(-10_000i32..10_000).to_le_bytes());
+    Network::from_bytes(&bytes).unwrap()
+}
+
+#[test]
+fn updated_accumulators_match_refreshed_ones() {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+    let network = random_network(&mut rng);
+
+    for mut position in positions() {
+        let mut acc = Accumulator::new(&position, &network);
+        for _ in 0..MOVES {
+            let moves = position.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            let mv = moves[rng.random_range(0..moves.len())];
+
+            // Undoing a move restores the accumulator it was played from
+            let mut undone = acc.play(&position, mv, &network);
+            undone.undo(&position, mv, &network);
+            assert_eq!(undone, acc);
+
+            acc = acc.play(&position, mv, &network);
+            position = position.play(mv).unwrap();
+            assert_eq!(acc, Accumulator::new(&position, &network));
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use checkm8::bot::{controller::Controller, input::InputListener};
+use checkm8::logging::Logger;
+use checkm8::perft::{Perft, PerftPosition, perft};
+use crossbeam_channel::unbounded;
+use shakmaty::{CastlingMode, Chess, Position, fen::Fen, uci::UciMove};
+use std::{
+    fs,
+    sync::{Arc, atomic::AtomicBool},
+};
+
+// Counts above these are skipped to keep the tests fast in debug builds
+const MAX_NODES: u64 = 200_000;
+const MAX_BULK_NODES: u64 = 5_000_000;
+
+fn suite() -> Vec<PerftPosition> {
+    let text =
+        fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/perft.epd")).unwrap();
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn perft_matches_known_counts() {
+    for suite_position in suite() {
+        for &(depth, expected) in &suite_position.counts {
+            if expected <= MAX_NODES {
+                assert_eq!(perft(&suite_position.position, depth), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn bulk_and_hashed_perft_match_known_counts() {
+    let mut perft = Perft::new(true, 16);
+    for suite_position in suite() {
+        for &(depth, expected) in &suite_position.counts {
+            if expected <= MAX_BULK_NODES {
+                assert_eq!(perft.count(&suite_position.position, depth), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn divide_sums_to_the_total() {
+    for suite_position in suite() {
+        let divide = Perft::new(true, 0).divide(&suite_position.position, 3);
+        let total: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+        assert_eq!(total, perft(&suite_position.position, 3));
+    }
+}
+
+/// Output of a controller running the commands.
+fn controller_output(commands: &[&str]) -> Vec<String> {
+    let (input_tx, input_rx) = unbounded();
+    let (cmd_tx, _cmd_rx) = unbounded();
+    let (_info_tx, info_rx) = unbounded();
+    let (output_tx, output_rx) = unbounded();
+
+    let script: String = commands.iter().map(|line| format!("{}\n", line)).collect();
+    InputListener::new(input_tx, script.as_bytes()).run();
+    let abort = Arc::new(AtomicBool::new(false));
+    Controller::with_output(
+        input_rx,
+        cmd_tx,
+        info_rx,
+        abort,
+        Logger::disabled(),
+        output_tx,
+    )
+    .run()
+    .unwrap();
+
+    output_rx.try_iter().collect()
+}
+
+#[test]
+fn controller_divides_the_position_after_its_moves() {
+    // En passant, castling on both sides and underpromotion in the moves played
+    let cases = [
+        (None, "e2e4 d7d5 e4e5 f7f5", 3),
+        (
+            Some("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+            "e1g1 e8c8",
+            2,
+        ),
+        (Some("8/P6k/8/8/8/8/6Kp/8 w - - 0 1"), "a7a8q h2h1n", 3),
+    ];
+
+    for (fen, moves, depth) in cases {
+        let command = match fen {
+            Some(fen) => format!("position fen {} moves {}", fen, moves),
+            None => format!("position startpos moves {}", moves),
+        };
+        let output = controller_output(&[&command, &format!("perft {}", depth)]);
+
+        // The expected counts of shakmaty's own perft, after playing the moves with shakmaty
+        let mut position: Chess = match fen {
+            Some(fen) => fen
+                .parse::<Fen>()
+                .unwrap()
+                .into_position(CastlingMode::Standard)
+                .unwrap(),
+            None => Chess::default(),
+        };
+        for mv in moves.split_whitespace() {
+            let mv = mv.parse::<UciMove>().unwrap().to_move(&position).unwrap();
+            position = position.play(mv).unwrap();
+        }
+        let mut expected: Vec<String> = position
+            .legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let child = position.clone().play(mv).unwrap();
+                let mv = UciMove::from_move(mv, CastlingMode::Standard);
+                format!("{}: {}", mv, shakmaty::perft(&child, depth - 1))
+            })
+            .collect();
+        expected.sort();
+
+        let moves = expected.len();
+        let mut divide = output[..moves].to_vec();
+        divide.sort();
+        assert_eq!(divide, expected, "{}", command);
+        assert_eq!(output[moves], "");
+        assert_eq!(
+            output[moves + 1],
+            format!("Nodes searched: {}", shakmaty::perft(&position, depth)),
+            "{}",
+            command
+        );
+        assert!(output[moves + 2].starts_with("Time: "));
+        assert_eq!(output.len(), moves + 3);
+    }
+}
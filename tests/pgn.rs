@@ -0,0 +1,145 @@
+use checkm8::pgn::{EngineComment, EngineScore, Game, PgnError, read_games};
+use shakmaty::{Move, Position, Square, san::San};
+use std::time::Duration;
+
+const ANNOTATED: &str = r#"[Event "Casual \"blitz\" game"]
+[Site "?"]
+[Date "2024.01.02"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+{Ruy Lopez} 1. e4 e5 2. Nf3 $1 Nc6 (2... d6 {Philidor} 3. d4 (3. Bc4 Be7)
+3... exd4) 3. Bb5 a6 $5 4. Ba4 Nf6 5. O-O Be7 {+0.35/12 1.2s} 1-0
+"#;
+
+/// SAN of the main line moves.
+fn mainline(game: &Game) -> Vec<String> {
+    let mut position = game.initial_position().clone();
+    game.mainline()
+        .map(|mv| {
+            let san = San::from_move(&position, mv);
+            position.play_unchecked(mv);
+            san.to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn annotated_games_are_written_back() {
+    let game: Game = ANNOTATED.parse().unwrap();
+    assert_eq!(game.tag("Event"), Some("Casual \"blitz\" game"));
+    assert_eq!(game.moves.len(), 10);
+    assert_eq!(game.to_string(), ANNOTATED);
+
+    let reread: Game = game.to_string().parse().unwrap();
+    assert_eq!(reread.tags, game.tags);
+    assert_eq!(reread.moves, game.moves);
+}
+
+#[test]
+fn annotations_belong_to_their_moves() {
+    let game: Game = ANNOTATED.parse().unwrap();
+    let moves = &game.moves;
+    assert_eq!(moves[0].starting_comment.as_deref(), Some("Ruy Lopez"));
+    assert_eq!(moves[2].nags, [1]);
+    // Suffix annotations are read as NAGs
+    assert_eq!(moves[5].nags, [5]);
+
+    // The variation replaces 2... Nc6 and holds one of its own
+    let variation = &moves[3].variations[0];
+    assert_eq!(variation.len(), 3);
+    assert_eq!(variation[0].comment.as_deref(), Some("Philidor"));
+    assert_eq!(variation[1].variations[0].len(), 2);
+
+    let comment: EngineComment = moves[9].comment.as_deref().unwrap().parse().unwrap();
+    assert_eq!(comment.score, EngineScore::Centipawns(35));
+}
+
+#[test]
+fn move_numbers_may_take_any_form() {
+    let spaced: Game = "1. e4 e5 2. Nf3 Nc6 3. Bb5 *".parse().unwrap();
+    let attached: Game = "1.e4 e5 2.Nf3 2...Nc6 3 . Bb5 *".parse().unwrap();
+    let bare: Game = "e4 e5 Nf3 Nc6 Bb5 *".parse().unwrap();
+
+    assert_eq!(mainline(&spaced), ["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+    assert_eq!(attached.moves, spaced.moves);
+    assert_eq!(bare.moves, spaced.moves);
+}
+
+#[test]
+fn castling_may_be_written_with_zeros() {
+    let game: Game = "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 Nf6 5. d3 0-0! 1/2-1/2"
+        .parse()
+        .unwrap();
+    assert_eq!(mainline(&game)[6], "O-O");
+    assert_eq!(
+        game.moves[9].mv,
+        Move::Castle {
+            king: Square::E8,
+            rook: Square::H8
+        }
+    );
+    assert_eq!(game.moves[9].nags, [1]);
+}
+
+#[test]
+fn games_from_positions_keep_their_fen() {
+    let game: Game = "[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 40\"]\n\n40... Kd7 41. e4 *"
+        .parse()
+        .unwrap();
+    assert_eq!(mainline(&game), ["Kd7", "e4"]);
+
+    let reread: Game = game.to_string().parse().unwrap();
+    assert_eq!(reread.initial_position(), game.initial_position());
+    assert_eq!(reread.moves, game.moves);
+    assert!(game.to_string().contains("40... Kd7 41. e4 *"));
+}
+
+#[test]
+fn invalid_games_do_not_affect_the_others() {
+    let text = "1. e4 e5 1-0\n\n1. e5 *\n\n1. d4 (1. e4 *\n\n[Event \"last\"]\n\n1. c4 0-1\n";
+    let games = read_games(text);
+    assert_eq!(games.len(), 4);
+    assert!(games[0].is_ok());
+    assert_eq!(
+        games[1].as_ref().unwrap_err(),
+        &PgnError::IllegalMove("e5".into())
+    );
+    assert_eq!(
+        games[2].as_ref().unwrap_err(),
+        &PgnError::UnbalancedVariation
+    );
+    assert_eq!(games[3].as_ref().unwrap().tag("Event"), Some("last"));
+}
+
+#[test]
+fn engine_comments_are_read_and_written() {
+    let comment: EngineComment = "-M3/20 0.5s".parse().unwrap();
+    assert_eq!(
+        comment,
+        EngineComment {
+            score: EngineScore::Mate(-3),
+            depth: 20,
+            time: Duration::from_millis(500),
+        }
+    );
+    assert_eq!(comment.to_string(), "-M3/20 0.5s");
+
+    let comment = EngineComment {
+        score: EngineScore::Centipawns(-7),
+        depth: 1,
+        time: Duration::from_secs(12),
+    };
+    assert_eq!(comment.to_string(), "-0.07/1 12.0s");
+    assert_eq!(comment.to_string().parse(), Ok(comment));
+    assert_eq!(
+        "+M1/5 0.0s".parse::<EngineComment>().unwrap().score,
+        EngineScore::Mate(1)
+    );
+
+    for text in ["book", "+0.35", "+0.35/x 1.0s", "+Mx/3 1.0s"] {
+        assert!(text.parse::<EngineComment>().is_err(), "{}", text);
+    }
+}
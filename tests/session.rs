@@ -0,0 +1,103 @@
+use checkm8::session::{
+    Change, Direction, Recorder, Transcript, diff, is_progress, normalize, replay,
+};
+use std::{fs, time::Duration};
+
+const TRANSCRIPT: &str = "\
+# Mate in one, and a checkmated position
+0 in isready
+0 out readyok
+1 in position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1
+1 in go depth 1
+2 out info depth 1 seldepth 1 time 0 nodes 1 nps 0 score mate 1 pv a1a8
+2 out bestmove a1a8
+3 in position fen 7k/6Q1/6K1/8/8/8/8/8 b - - 0 1
+3 in not a command
+3 in go
+4 out info depth 0 score mate 0
+4 out bestmove 0000
+5 in quit
+";
+
+/// Output of the transcript and of its replay, without progress infos or the values of timed or
+/// searched info fields.
+fn replayed(transcript: &str) -> (Vec<String>, Vec<String>) {
+    let transcript: Transcript = transcript.parse().unwrap();
+    let ignore = ["time", "nodes", "nps", "score"].map(String::from);
+    let normalized = |lines: Vec<String>| -> Vec<String> {
+        lines
+            .iter()
+            .filter(|line| !is_progress(line))
+            .map(|line| normalize(line, &ignore))
+            .collect()
+    };
+
+    let actual = replay(&transcript, Duration::from_secs(10));
+    (normalized(transcript.output()), normalized(actual))
+}
+
+#[test]
+fn replay_reproduces_the_recorded_output() {
+    let (expected, actual) = replayed(TRANSCRIPT);
+    assert_eq!(expected[1], "info depth 1 seldepth 1 pv a1a8");
+    assert_eq!(diff(&expected, &actual), []);
+}
+
+#[test]
+fn replay_reports_changed_output() {
+    let transcript = TRANSCRIPT.replace("bestmove a1a8", "bestmove a1a7");
+    let (expected, actual) = replayed(&transcript);
+    assert_eq!(
+        diff(&expected, &actual),
+        [
+            Change::Missing("bestmove a1a7".into()),
+            Change::Added("bestmove a1a8".into()),
+        ]
+    );
+}
+
+#[test]
+fn replay_skips_progress_of_long_searches() {
+    // Searches for more than a second, so that progress infos are sent, though not as many as were
+    // recorded
+    let mut transcript = String::from("0 in position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\n");
+    transcript.push_str("0 in go depth 7\n");
+    for depth in 1..=7 {
+        transcript.push_str(&format!(
+            "1 out info depth {depth} seldepth {depth} time 0 nodes 0 nps 0 score cp 0 pv a1a8\n"
+        ));
+        for number in 1..=3 {
+            transcript.push_str(&format!(
+                "1 out info depth {depth} currmove a1a8 currmovenumber {number} nodes 0\n"
+            ));
+        }
+    }
+    transcript.push_str("2 out bestmove a1a8\n2 in isready\n2 out readyok\n2 in quit\n");
+
+    assert!(is_progress(
+        "info depth 7 currmove a1a8 currmovenumber 1 nodes 0"
+    ));
+    let (expected, actual) = replayed(&transcript);
+    assert_eq!(expected.len(), 9);
+    assert_eq!(diff(&expected, &actual), []);
+}
+
+#[test]
+fn recorded_transcripts_parse() {
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/session.txt");
+    let recorder = Recorder::create(path).unwrap();
+    recorder.record(Direction::In, "uci");
+    recorder.record(Direction::Out, "id name checkm8");
+    drop(recorder);
+
+    let transcript: Transcript = fs::read_to_string(path).unwrap().parse().unwrap();
+    let lines: Vec<(Direction, &str)> = transcript
+        .entries
+        .iter()
+        .map(|entry| (entry.direction, entry.line.as_str()))
+        .collect();
+    assert_eq!(
+        lines,
+        [(Direction::In, "uci"), (Direction::Out, "id name checkm8")]
+    );
+}
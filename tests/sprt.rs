@@ -0,0 +1,77 @@
+use checkm8::arena::sprt::{Decision, Results, Sprt, elo, expected_score};
+
+const SPRT: Sprt = Sprt {
+    elo0: 0.0,
+    elo1: 5.0,
+    alpha: 0.05,
+    beta: 0.05,
+};
+
+fn results(wins: u32, draws: u32, losses: u32) -> Results {
+    Results {
+        wins,
+        draws,
+        losses,
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn elo_conversion_round_trips() {
+    assert_close(expected_score(0.0), 0.5);
+    assert_close(expected_score(400.0), 10.0 / 11.0);
+    for score in [0.1, 0.5, 0.64, 0.9] {
+        assert_close(expected_score(elo(score)), score);
+    }
+}
+
+#[test]
+fn bounds_follow_from_the_error_rates() {
+    let (lower, upper) = SPRT.bounds();
+    assert_close(lower, (0.05f64 / 0.95).ln());
+    assert_close(upper, -lower);
+    assert_close(upper, 2.944438979);
+}
+
+#[test]
+fn llr_matches_the_gsprt_approximation() {
+    // LLR = N (s1 - s0) (2s - s0 - s1) / (2 var), as used by fishtest and cutechess
+    for (wins, draws, losses, llr, decision) in [
+        (300, 1000, 250, 1.580063900, Decision::Continue),
+        (1200, 2000, 1000, 4.684515591, Decision::AcceptH1),
+        (100, 200, 110, -0.364253894, Decision::Continue),
+        (1000, 2000, 1200, -6.352114091, Decision::AcceptH0),
+    ] {
+        let results = results(wins, draws, losses);
+        assert_close(SPRT.llr(&results), llr);
+        assert_eq!(SPRT.decision(&results), decision, "{}", results);
+    }
+
+    // Without wins or losses the test keeps going
+    assert_eq!(SPRT.llr(&results(0, 10, 0)), 0.0);
+    assert_eq!(SPRT.decision(&results(20, 0, 0)), Decision::Continue);
+}
+
+#[test]
+fn elo_has_95_percent_error_bars() {
+    let (diff, error) = results(300, 1000, 250).elo().unwrap();
+    assert_close(diff, 11.211489440);
+    assert_close(error, 10.301823602);
+
+    let (diff, error) = results(1000, 2000, 1200).elo().unwrap();
+    assert_close(diff, -16.557074063);
+    assert_close(error, 7.606732436);
+
+    // No estimate without games, or when the interval reaches a score of 0 or 1
+    assert_eq!(Results::default().elo(), None);
+    assert_eq!(results(5, 0, 0).elo(), None);
+    assert_eq!(results(1, 1, 0).elo(), None);
+}
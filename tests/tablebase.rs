@@ -0,0 +1,179 @@
+mod common;
+
+use checkm8::{
+    SearchControl,
+    engine::Engine,
+    tablebase::{Dtm, Material, Table, Tablebase, generate::generate_with_dependencies},
+};
+use common::position;
+use shakmaty::{Board, CastlingMode, Chess, Color, FromSetup, Role, Setup, Square};
+use std::{
+    fs,
+    sync::{Arc, OnceLock},
+};
+
+// Squares of the white king in pawnless tables, every other square is a symmetry of these
+const TRIANGLE: [Square; 10] = [
+    Square::A1,
+    Square::B1,
+    Square::C1,
+    Square::D1,
+    Square::B2,
+    Square::C2,
+    Square::D2,
+    Square::C3,
+    Square::D3,
+    Square::D4,
+];
+
+/// Generated KQK and KRK tables, with the file contents of every table.
+fn tables() -> &'static (Arc<Tablebase>, Vec<Vec<u8>>) {
+    static TABLES: OnceLock<(Arc<Tablebase>, Vec<Vec<u8>>)> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tablebase = Tablebase::new();
+        let mut files = Vec::new();
+        for material in ["KQK", "KRK"] {
+            let material: Material = material.parse().unwrap();
+            generate_with_dependencies(&material, &mut tablebase, &mut |table: &Table| {
+                files.push(table.to_bytes())
+            });
+        }
+        (Arc::new(tablebase), files)
+    })
+}
+
+/// Longest win of white with a single piece against a bare king.
+fn longest_win(tablebase: &Tablebase, role: Role) -> u32 {
+    let mut longest = 0;
+    for white_king in TRIANGLE {
+        for black_king in Square::ALL {
+            for square in Square::ALL {
+                if square == white_king || square == black_king || white_king == black_king {
+                    continue;
+                }
+
+                let mut board = Board::empty();
+                board.set_piece_at(white_king, Color::White.king());
+                board.set_piece_at(black_king, Color::Black.king());
+                board.set_piece_at(square, role.of(Color::White));
+
+                let setup = Setup {
+                    board,
+                    ..Setup::empty()
+                };
+                let Ok(position) = Chess::from_setup(setup, CastlingMode::Standard) else {
+                    continue;
+                };
+                if let Some(Dtm::Win(plies)) = tablebase.probe(&position) {
+                    longest = longest.max(plies);
+                }
+            }
+        }
+    }
+    longest
+}
+
+#[test]
+fn longest_mates_match_the_known_values() {
+    let (tablebase, _) = tables();
+
+    // Mate in 10 moves with the queen and in 16 with the rook
+    assert_eq!(longest_win(tablebase, Role::Queen), 19);
+    assert_eq!(longest_win(tablebase, Role::Rook), 31);
+}
+
+#[test]
+fn probes_agree_with_the_search() {
+    let (tablebase, _) = tables();
+
+    for (fen, dtm) in [
+        ("k7/8/1K6/8/8/8/7Q/8 w - - 0 1", Dtm::Win(1)),
+        ("k7/8/2K5/8/8/8/7Q/8 w - - 0 1", Dtm::Win(3)),
+        ("7k/8/8/6K1/8/8/8/R7 w - - 0 1", Dtm::Win(3)),
+        ("6k1/8/6K1/8/8/8/8/3R4 b - - 0 1", Dtm::Loss(4)),
+        // Black has the stronger side, so the board is mirrored before probing
+        ("8/8/8/8/8/1k6/7q/K7 b - - 0 1", Dtm::Win(1)),
+        ("K7/8/2k5/8/8/8/8/6q1 w - - 0 1", Dtm::Loss(4)),
+        ("r7/8/8/8/8/4k3/8/4K3 b - - 0 1", Dtm::Win(1)),
+    ] {
+        assert_eq!(tablebase.probe(&position(fen)), Some(dtm), "{}", fen);
+
+        let mut engine = Engine::new();
+        engine.set_position(Some(fen), &[]).unwrap();
+        let result = engine.search(SearchControl::ToDepth(6)).unwrap();
+        let moves = match dtm {
+            Dtm::Win(plies) => plies.div_ceil(2) as i32,
+            Dtm::Loss(plies) => -((plies / 2) as i32),
+            Dtm::Draw => unreachable!(),
+        };
+        assert_eq!(result.mate, Some(moves), "{}", fen);
+    }
+}
+
+#[test]
+fn searches_ending_in_the_tablebase_report_their_selective_depth() {
+    let (tablebase, _) = tables();
+
+    let mut engine = Engine::new();
+    engine.set_tablebase(Some(tablebase.clone()));
+    engine
+        .set_position(Some("k7/8/2K5/8/8/8/7Q/8 w - - 0 1"), &[])
+        .unwrap();
+
+    // Every position after the root move is probed, so no line goes beyond the first ply
+    let mut infos = Vec::new();
+    let result = engine
+        .search_with(SearchControl::ToDepth(4), |info| {
+            infos.push((info.depth, info.seldepth))
+        })
+        .unwrap();
+    assert_eq!(infos, [(1, 1), (2, 1), (3, 1), (4, 1)]);
+    assert_eq!(result.mate, Some(2));
+    assert!(result.tbhits > 0);
+}
+
+#[test]
+fn positions_outside_the_tables_are_not_probed() {
+    let (tablebase, _) = tables();
+
+    assert_eq!(
+        tablebase.probe(&position("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1")),
+        None
+    );
+    assert_eq!(
+        tablebase.probe(&position("4k3/8/8/8/8/8/8/1B2K3 w - - 0 1")),
+        None
+    );
+    assert_eq!(tablebase.probe(&Chess::default()), None);
+}
+
+#[test]
+fn tables_round_trip_through_files() {
+    let (tablebase, files) = tables();
+    assert_eq!(files.len(), 2);
+
+    let dir = concat!(env!("CARGO_TARGET_TMPDIR"), "/tablebase");
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(dir).unwrap();
+
+    for bytes in files {
+        let table = Table::from_bytes(bytes).unwrap();
+        assert_eq!(&table.to_bytes(), bytes);
+        table.save(dir).unwrap();
+
+        // Cut off files are rejected
+        assert!(Table::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    let loaded = Tablebase::load(dir).unwrap();
+    assert_eq!(loaded.len(), 2);
+    for fen in [
+        "k7/8/2K5/8/8/8/7Q/8 w - - 0 1",
+        "K7/8/2k5/8/8/8/8/6q1 w - - 0 1",
+        "6k1/8/6K1/8/8/8/8/3R4 b - - 0 1",
+        "8/8/8/4k3/8/8/8/R3K3 b - - 0 1",
+    ] {
+        let position = position(fen);
+        assert_eq!(loaded.probe(&position), tablebase.probe(&position));
+    }
+}
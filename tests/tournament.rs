@@ -0,0 +1,149 @@
+use checkm8::arena::tournament::{Format, Pairing, Standings, StateFile, schedule};
+use shakmaty::{Color, KnownOutcome};
+use std::fs;
+
+const WHITE_WINS: KnownOutcome = KnownOutcome::Decisive {
+    winner: Color::White,
+};
+const BLACK_WINS: KnownOutcome = KnownOutcome::Decisive {
+    winner: Color::Black,
+};
+
+fn names(count: usize) -> Vec<String> {
+    (0..count).map(|index| format!("engine{}", index)).collect()
+}
+
+#[test]
+fn schedules_play_every_opening_with_both_colours() {
+    let games = schedule(Format::RoundRobin, 3, 2, 1);
+    assert_eq!(games.len(), 12);
+    assert_eq!(
+        games[..2],
+        [
+            Pairing {
+                white: 0,
+                black: 1,
+                opening: 0
+            },
+            Pairing {
+                white: 1,
+                black: 0,
+                opening: 0
+            },
+        ]
+    );
+    for engine in 0..3 {
+        let white = games.iter().filter(|game| game.white == engine).count();
+        let black = games.iter().filter(|game| game.black == engine).count();
+        assert_eq!((white, black), (4, 4));
+    }
+
+    // The first engine meets every other one, which never meet each other
+    let games = schedule(Format::Gauntlet, 4, 3, 2);
+    assert_eq!(games.len(), 18);
+    assert!(games.iter().all(|game| game.white == 0 || game.black == 0));
+    let openings: Vec<usize> = games.iter().step_by(6).map(|game| game.opening).collect();
+    assert_eq!(openings, [0, 1, 0]);
+}
+
+#[test]
+fn standings_count_results_for_both_engines() {
+    let mut standings = Standings::new(names(3));
+    standings.add(0, 1, WHITE_WINS);
+    standings.add(1, 0, WHITE_WINS);
+    standings.add(2, 0, BLACK_WINS);
+    standings.add(1, 2, KnownOutcome::Draw);
+
+    let total = standings.total(0);
+    assert_eq!((total.wins, total.draws, total.losses), (2, 0, 1));
+    let total = standings.total(1);
+    assert_eq!((total.wins, total.draws, total.losses), (1, 1, 1));
+    let total = standings.total(2);
+    assert_eq!((total.wins, total.draws, total.losses), (0, 1, 1));
+}
+
+#[test]
+fn ratings_match_the_closed_form_for_two_engines() {
+    let mut standings = Standings::new(names(2));
+    for outcome in [
+        [WHITE_WINS; 6].as_slice(),
+        &[KnownOutcome::Draw; 2],
+        &[BLACK_WINS; 2],
+    ]
+    .concat()
+    {
+        standings.add(0, 1, outcome);
+    }
+
+    // 7.5 points out of 11 games with the virtual draw, split evenly around zero
+    let difference = 400.0 * (7.5f64 / 3.5).log10();
+    let ratings = standings.ratings();
+    assert!(
+        (ratings[0].elo - difference / 2.0).abs() < 1e-3,
+        "{:?}",
+        ratings
+    );
+    assert!((ratings[0].elo + ratings[1].elo).abs() < 1e-9);
+    assert!(ratings[0].error > 0.0 && ratings[0].error.is_finite());
+
+    // More games of the same results narrow the interval
+    for outcome in [
+        [WHITE_WINS; 6].as_slice(),
+        &[KnownOutcome::Draw; 2],
+        &[BLACK_WINS; 2],
+    ]
+    .concat()
+    {
+        standings.add(0, 1, outcome);
+    }
+    assert!(standings.ratings()[0].error < ratings[0].error);
+}
+
+#[test]
+fn ratings_stay_finite_and_ordered() {
+    // Every engine beats the next one every time, and the last one has not played
+    let mut standings = Standings::new(names(4));
+    for _ in 0..10 {
+        standings.add(0, 1, WHITE_WINS);
+        standings.add(1, 2, WHITE_WINS);
+    }
+
+    let ratings = standings.ratings();
+    assert!(ratings[..3].iter().all(|rating| rating.elo.is_finite()));
+    assert!(ratings[0].elo > ratings[1].elo && ratings[1].elo > ratings[2].elo);
+    assert!(ratings.iter().map(|rating| rating.elo).sum::<f64>().abs() < 1e-6);
+    assert!(ratings[3].error.is_infinite());
+}
+
+#[test]
+fn state_files_resume_after_an_incomplete_line() {
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/tournament.state");
+    let _ = fs::remove_file(path);
+    let names = names(2);
+
+    let (mut state, finished) = StateFile::open(path, &names, 4).unwrap();
+    assert!(finished.is_empty());
+    state.record(0, WHITE_WINS).unwrap();
+    state.record(2, KnownOutcome::Draw).unwrap();
+    drop(state);
+
+    // Interrupted while recording the next game
+    let mut text = fs::read_to_string(path).unwrap();
+    text.push_str("1 1/2");
+    fs::write(path, &text).unwrap();
+
+    let (mut state, finished) = StateFile::open(path, &names, 4).unwrap();
+    assert_eq!(finished.len(), 2);
+    assert_eq!(finished[&2], KnownOutcome::Draw);
+    state.record(1, BLACK_WINS).unwrap();
+    drop(state);
+
+    let (_, finished) = StateFile::open(path, &names, 4).unwrap();
+    assert_eq!(finished.len(), 3);
+    assert_eq!(finished[&1], BLACK_WINS);
+
+    // Invalid lines before the last one and other tournaments are still refused
+    fs::write(path, text.replace("0 1-0", "0 1-")).unwrap();
+    assert!(StateFile::open(path, &names, 4).is_err());
+    assert!(StateFile::open(path, &names, 6).is_err());
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use checkm8::scoring::{evaluate, trace::EvalTrace};
+use common::{position, positions};
+use shakmaty::Position;
+
+#[test]
+fn trace_totals_match_the_evaluation() {
+    for position in positions() {
+        let trace = EvalTrace::new(&position);
+        assert_eq!(
+            trace.score(),
+            evaluate(&position, 0),
+            "{:?}",
+            position.board()
+        );
+    }
+}
+
+#[test]
+fn trace_scores_checkmates_draws_and_endgames() {
+    for fen in [
+        // Checkmate, stalemate and a specialised endgame
+        "7k/6Q1/6K1/8/8/8/8/8 b - - 0 1",
+        "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+        "8/8/8/4k3/8/8/8/R3K3 w - - 0 1",
+    ] {
+        let position = position(fen);
+        let trace = EvalTrace::new(&position);
+        assert!(
+            trace.terminal.is_some() || trace.endgame.is_some(),
+            "{}",
+            fen
+        );
+        assert_eq!(trace.score(), evaluate(&position, 0), "{}", fen);
+    }
+}